@@ -2015,11 +2015,11 @@ def limpiar_cache_comparativas():
             elim_res = 0; elim_per = 0
             if PLAZA_RUST_AVAILABLE and _plaza_rust_mod:
                 try:
-                    elim_res = _plaza_rust_mod.limpiar_resultados_expirados(0)
+                    elim_res = _plaza_rust_mod.limpiar_resultados_expirados(0)["n"]
                 except Exception:
                     pass
                 try:
-                    elim_per = _plaza_rust_mod.limpiar_periodos_lru(0, CURRENT_YEAR)
+                    elim_per = _plaza_rust_mod.limpiar_periodos_lru(0, CURRENT_YEAR)["n"]
                 except Exception:
                     pass
             _periodo_cache.evictar_historicos_python()
@@ -212,9 +212,9 @@ class Watchdog:
         # 1. Limpiar resultados Rust expirados (TTL)
         if rust is not None:
             try:
-                n = rust.limpiar_resultados_expirados(RESULT_TTL_S)
-                if n:
-                    log.info(f"🧹 Watchdog: {n} resultados Rust expirados eliminados")
+                r = rust.limpiar_resultados_expirados(RESULT_TTL_S)
+                if r["n"]:
+                    log.info(f"🧹 Watchdog: {r['n']} resultados Rust expirados eliminados ({r['bytes']} bytes)")
             except Exception as exc:
                 log.warning(f"limpiar_resultados_expirados: {exc}")
 
@@ -242,9 +242,10 @@ class Watchdog:
         # 4. Evicción LRU Rust (solo históricos)
         if rust is not None:
             try:
-                n_evict = rust.limpiar_periodos_lru(MAX_PERIODOS_HISTORICOS, CURRENT_YEAR)
-                if n_evict:
-                    log.warning(f"♻️  LRU Rust: {n_evict} periodos históricos evictados")
+                r = rust.limpiar_periodos_lru(MAX_PERIODOS_HISTORICOS, CURRENT_YEAR)
+                if r["n"]:
+                    claves = [d[0] for d in r["desalojados"]]
+                    log.warning(f"♻️  LRU Rust: {r['n']} periodos históricos evictados {claves} ({r['bytes']} bytes)")
             except Exception as exc:
                 log.warning(f"limpiar_periodos_lru: {exc}")
 
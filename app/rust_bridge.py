@@ -443,13 +443,14 @@ def agregaciones_por_estado(filtro_situacion: int = -1) -> list[dict]:
     return resultado
 
 
-def filtrar_indices(estado_id: int = -1, situacion: int = -1) -> list[int]:
-    """Filtra por estado y/o situación y devuelve índices de filas."""
+def filtrar_indices(estado_id: int = -1, situacion: int = -1, donde: Optional[str] = None) -> list[int]:
+    """Filtra por estado y/o situación (y condiciones sobre métricas como
+    "cn_total >= 10 AND aten_total == 0") y devuelve índices de filas."""
     if not _initialized or not RUST_AVAILABLE:
         return []
 
     try:
-        return _rust.filtrar_indices(estado_id, situacion)
+        return _rust.filtrar_indices(estado_id, situacion, donde)
     except Exception as exc:
         logger.error(f"❌ rust_bridge.filtrar_indices: {exc}")
         return []
@@ -477,3 +478,33 @@ def engine_stats() -> dict:
 def is_ready() -> bool:
     """True si el motor está inicializado y listo para responder."""
     return _initialized and RUST_AVAILABLE
+
+
+def decodificar_comparacion(blob: bytes, claves: list[str]) -> dict:
+    """
+    Decodifica comparar_periodos(..., binario=True) (o comprimido=True si
+    está instalado zstandard) a {"periodo1": {estado_id: {clave: valor}},
+    "periodo2": {...}}. `claves` son las métricas en el orden del formato
+    pedido (esquema_resultado()["metricas"]); layout en empaquetado.rs.
+    """
+    import struct
+    from array import array
+
+    if blob[:4] != b"PLZC":
+        import zstandard
+        blob = zstandard.ZstdDecompressor().decompress(blob)
+    version, flags, m, n1, n2 = struct.unpack_from("<BBHII", blob, 4)
+    if version != 1:
+        raise ValueError(f"blob versión {version} no soportada")
+    valores = array("q", blob[16:])
+    if sys.byteorder == "big":
+        valores.byteswap()
+
+    def bloque(pos: int, n: int) -> dict:
+        ids = valores[pos:pos + n]
+        cols = [valores[pos + (k + 1) * n:pos + (k + 2) * n] for k in range(m)]
+        return {eid: dict(zip(claves, (c[i] for c in cols))) for i, eid in enumerate(ids)}
+
+    periodo1 = bloque(0, n1)
+    periodo2 = periodo1 if flags & 1 else bloque(n1 * (m + 1), n2)
+    return {"periodo1": periodo1, "periodo2": periodo2}
@@ -0,0 +1,162 @@
+// ==============================================================================
+// plaza_rust/src/aislamiento.rs
+//
+// Ranking de aislamiento: las plazas cuya vecina más cercana está más lejos,
+// para priorizar visitas de supervisión en zonas remotas.
+//
+// La vecina más cercana de cada plaza se busca en una rejilla lat/lng (unas
+// 2 plazas por celda) recorriendo anillos de celdas hasta que una cota
+// inferior de la distancia al siguiente anillo supera la mejor encontrada;
+// el resultado es exacto (haversine) sin comparar todos contra todos. No
+// considera el antimeridiano (los datos son de México).
+//
+// Solo cuentan filas con coordenadas válidas; dos filas en el mismo punto
+// son vecinas a 0 km. Empates: la fila que aparece primero en el parquet.
+// ==============================================================================
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{clave, config, haversine, periodo, EngineData};
+
+const R_KM: f64 = 6_371.0;
+
+/// (fila original, lat, lng) de las filas con coordenadas válidas.
+type Punto = (usize, f64, f64);
+
+/// Índice de celdas en formato CSR: los puntos de la celda c son
+/// `orden[inicio[c]..inicio[c + 1]]`.
+struct Rejilla {
+    lat0:    f64,
+    lng0:    f64,
+    celda:   f64,
+    filas:   usize,
+    cols:    usize,
+    inicio:  Vec<usize>,
+    orden:   Vec<u32>,
+    // mayor |lat| de los puntos: acota cos(lat) para la cota en lng
+    cos_min: f64,
+}
+
+impl Rejilla {
+    fn new(pts: &[Punto]) -> Self {
+        let (mut la0, mut lo0, mut la1, mut lo1) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for &(_, la, lo) in pts {
+            (la0, lo0, la1, lo1) = (la0.min(la), lo0.min(lo), la1.max(la), lo1.max(lo));
+        }
+        let (alto, ancho) = (la1 - la0, lo1 - lo0);
+        let objetivo = (pts.len() / 2).max(1) as f64;
+        let celda = (alto * ancho / objetivo).sqrt().max(alto.max(ancho) / objetivo).max(1e-6);
+        let filas = (alto / celda) as usize + 1;
+        let cols = (ancho / celda) as usize + 1;
+
+        let mut r = Rejilla {
+            lat0: la0, lng0: lo0, celda, filas, cols,
+            inicio: vec![0; filas * cols + 1],
+            orden: vec![0; pts.len()],
+            cos_min: la0.abs().max(la1.abs()).min(90.0).to_radians().cos(),
+        };
+        let ids: Vec<usize> = pts.iter().map(|&(_, la, lo)| r.id(r.celda_de(la, lo))).collect();
+        for &c in &ids { r.inicio[c + 1] += 1; }
+        for c in 0..filas * cols { r.inicio[c + 1] += r.inicio[c]; }
+        let mut cursor = r.inicio.clone();
+        for (k, &c) in ids.iter().enumerate() {
+            r.orden[cursor[c]] = k as u32;
+            cursor[c] += 1;
+        }
+        r
+    }
+
+    fn celda_de(&self, lat: f64, lng: f64) -> (usize, usize) {
+        let f = (((lat - self.lat0) / self.celda) as usize).min(self.filas - 1);
+        let c = (((lng - self.lng0) / self.celda) as usize).min(self.cols - 1);
+        (f, c)
+    }
+
+    fn id(&self, (f, c): (usize, usize)) -> usize {
+        f * self.cols + c
+    }
+
+    /// Distancia mínima posible (km) a un punto separado por al menos `k`
+    /// celdas completas en lat o en lng.
+    fn cota(&self, k: usize) -> f64 {
+        let d = (k as f64 * self.celda).to_radians();
+        let en_lat = R_KM * d;
+        let en_lng = 2.0 * R_KM * (self.cos_min * (d / 2.0).min(std::f64::consts::FRAC_PI_2).sin()).asin();
+        en_lat.min(en_lng)
+    }
+
+    /// (distancia km, fila original) de la vecina más cercana del punto `k`.
+    fn vecina(&self, pts: &[Punto], k: usize) -> Option<(f64, usize)> {
+        let (_, la, lo) = pts[k];
+        let (f0, c0) = self.celda_de(la, lo);
+        let mut mejor: Option<(f64, usize)> = None;
+        for anillo in 0..=self.filas.max(self.cols) {
+            if anillo > 0 && mejor.is_some_and(|(d, _)| self.cota(anillo - 1) > d) { break; }
+            let (fa, fb) = (f0.saturating_sub(anillo), (f0 + anillo).min(self.filas - 1));
+            let (ca, cb) = (c0.saturating_sub(anillo), (c0 + anillo).min(self.cols - 1));
+            for f in fa..=fb {
+                for c in ca..=cb {
+                    // solo el borde del anillo; el interior ya se recorrió
+                    if f.abs_diff(f0) != anillo && c.abs_diff(c0) != anillo { continue; }
+                    let id = self.id((f, c));
+                    for &j in &self.orden[self.inicio[id]..self.inicio[id + 1]] {
+                        let j = j as usize;
+                        if j == k { continue; }
+                        let (o, lj, gj) = pts[j];
+                        let d = haversine(la, lo, lj, gj);
+                        if mejor.is_none_or(|(md, mo)| d < md || (d == md && o < mo)) {
+                            mejor = Some((d, o));
+                        }
+                    }
+                }
+            }
+        }
+        mejor
+    }
+}
+
+/// (fila, km a la vecina, fila vecina) de las `n` más aisladas.
+fn aisladas(eng: &EngineData, n: usize) -> Vec<(usize, f64, usize)> {
+    let pts: Vec<Punto> = (0..eng.n)
+        .map(|i| (eng.fila_original(i), eng.lats.get(i), eng.lngs.get(i)))
+        .filter(|&(_, la, lo)| !la.is_nan() && !lo.is_nan())
+        .collect();
+    if pts.len() < 2 { return Vec::new(); }
+    let rejilla = Rejilla::new(&pts);
+    let por_punto = |k: usize| rejilla.vecina(&pts, k).map(|(d, v)| (pts[k].0, d, v));
+    let mut res: Vec<(usize, f64, usize)> = if config::secuencial(pts.len()) {
+        (0..pts.len()).filter_map(por_punto).collect()
+    } else {
+        config::en_pool(|| (0..pts.len()).into_par_iter().filter_map(por_punto).collect())
+    };
+    res.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    res.truncate(n);
+    res
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// Las `n` filas más aisladas, de mayor a menor distancia a su vecina:
+/// [{"indice", "distancia_km", "vecina"}] (índices en el orden del parquet).
+#[pyfunction]
+pub(crate) fn plazas_aisladas(py: Python<'_>, periodo_key: ArgClave, n: usize) -> PyResult<Vec<Bound<'_, PyDict>>> {
+    let periodo_key = clave(periodo_key)?;
+    let res = py.allow_threads(|| -> Result<_, Error> {
+        let eng = periodo(periodo_key)?;
+        eng.tocar();
+        Ok(aisladas(&eng, n))
+    })?;
+    res.into_iter().map(|(o, d, v)| {
+        let f = PyDict::new(py);
+        f.set_item("indice", o)?;
+        f.set_item("distancia_km", d)?;
+        f.set_item("vecina", v)?;
+        Ok(f)
+    }).collect()
+}
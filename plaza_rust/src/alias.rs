@@ -0,0 +1,124 @@
+// ==============================================================================
+// plaza_rust/src/alias.rs
+//
+// Alias de periodos: toda función que recibe un periodo_key acepta también
+// un str, así la capa de API no calcula fechas ni decide "cuál es el último
+// periodo cargado" por su cuenta (y sin carreras con una carga en curso).
+//
+//   "latest"   / "ultimo"    el periodo físico cargado con la clave mayor
+//   "previous" / "anterior"  el anterior a ese
+//   registrar_alias("base", 202401)  cualquier otro nombre, fijo a una clave
+//
+// Los nombres se comparan sin mayúsculas ni espacios alrededor. Los
+// integrados están reservados y se resuelven en cada llamada; un alias
+// registrado puede apuntar a un periodo aún no cargado o a uno virtual.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::cerrojos::Cerrojo;
+use crate::errores::Error;
+use crate::{clave, PeriodoKey, ENGINE_PERIODOS};
+
+static ALIASES: Cerrojo<BTreeMap<String, PeriodoKey>> = Cerrojo::new("ALIASES", BTreeMap::new());
+
+const INTEGRADOS: [&str; 4] = ["latest", "ultimo", "previous", "anterior"];
+
+/// periodo_key tal como llega de Python: una clave numérica o un alias.
+#[derive(Clone)]
+pub(crate) enum ArgClave {
+    Numero(PeriodoKey),
+    Alias(String),
+}
+
+impl<'py> FromPyObject<'py> for ArgClave {
+    fn extract_bound(ob: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(k) = ob.extract::<PeriodoKey>() {
+            return Ok(ArgClave::Numero(k));
+        }
+        if let Ok(s) = ob.extract::<String>() {
+            return Ok(ArgClave::Alias(s));
+        }
+        Err(PyTypeError::new_err(format!(
+            "periodo_key debe ser int (u32) o str (alias), no {}",
+            ob.get_type().name()?,
+        )))
+    }
+}
+
+fn normalizar(alias: &str) -> String {
+    alias.trim().to_lowercase()
+}
+
+/// La `atras`-ésima clave física cargada contando desde la mayor.
+fn cargado_desde_el_final(atras: usize) -> Result<Option<PeriodoKey>, Error> {
+    let guard = ENGINE_PERIODOS.leer("alias")?;
+    let Some(m) = guard.as_ref() else { return Ok(None) };
+    let mut keys: Vec<PeriodoKey> = m.keys().copied().collect();
+    keys.sort_unstable();
+    Ok(keys.iter().rev().nth(atras).copied())
+}
+
+/// Clave a la que apunta `alias` en este momento.
+pub(crate) fn resolver(alias: &str) -> Result<PeriodoKey, Error> {
+    let nombre = normalizar(alias);
+    let integrado = match nombre.as_str() {
+        "latest" | "ultimo"     => Some(0),
+        "previous" | "anterior" => Some(1),
+        _ => None,
+    };
+    if let Some(atras) = integrado {
+        return cargado_desde_el_final(atras)?.ok_or_else(|| {
+            Error::Motor(format!("alias {alias:?}: hay menos de {} periodos cargados", atras + 1))
+        });
+    }
+    ALIASES.leer("resolver")?.get(&nombre).copied()
+        .ok_or_else(|| Error::Motor(format!("alias desconocido: {alias:?}")))
+}
+
+impl ArgClave {
+    pub(crate) fn resolver(self) -> Result<PeriodoKey, Error> {
+        match self {
+            ArgClave::Numero(k) => Ok(k),
+            ArgClave::Alias(a)  => resolver(&a),
+        }
+    }
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Fija `alias` a la clave que `periodo_key` resuelve ahora (otro alias se
+/// copia, no se encadena). Devuelve la clave.
+#[pyfunction]
+pub(crate) fn registrar_alias(alias: &str, periodo_key: ArgClave) -> PyResult<PeriodoKey> {
+    let nombre = normalizar(alias);
+    if nombre.is_empty() || nombre.chars().all(|c| c.is_ascii_digit()) {
+        return Err(PyValueError::new_err(format!("alias inválido: {alias:?} (vacío o numérico)")));
+    }
+    if INTEGRADOS.contains(&nombre.as_str()) {
+        return Err(PyValueError::new_err(format!("alias reservado: {alias:?}")));
+    }
+    let key = clave(periodo_key)?;
+    ALIASES.escribir("registrar_alias")?.insert(nombre, key);
+    Ok(key)
+}
+
+#[pyfunction]
+pub(crate) fn quitar_alias(alias: &str) -> PyResult<bool> {
+    Ok(ALIASES.escribir("quitar_alias")?.remove(&normalizar(alias)).is_some())
+}
+
+/// {alias: clave} de los registrados más los integrados que hoy resuelven.
+#[pyfunction]
+pub(crate) fn aliases() -> PyResult<BTreeMap<String, PeriodoKey>> {
+    let mut out = ALIASES.leer("aliases")?.clone();
+    for nombre in INTEGRADOS {
+        if let Ok(k) = resolver(nombre) { out.insert(nombre.to_string(), k); }
+    }
+    Ok(out)
+}
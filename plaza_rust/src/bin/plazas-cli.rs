@@ -0,0 +1,182 @@
+// ==============================================================================
+// plazas-cli
+// Agregaciones y exportaciones offline sobre archivos Parquet, sin Python.
+//
+// Uso:
+//   plazas-cli [--config motor.toml] agregar  <archivo>             [opciones]
+//   plazas-cli [--config motor.toml] comparar <archivo1> <archivo2> [opciones]
+//   plazas-cli [--config motor.toml] exportar <archivo>             [opciones]
+//
+// Opciones:
+//   --filtro N        situación a filtrar (default -1 = todas)
+//   --formato F       json | csv  (default json)
+//   --salida RUTA     escribe a un archivo en vez de stdout
+//
+// Los archivos pueden venir comprimidos con gzip o zstd.
+// ==============================================================================
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use plaza_rust::offline::{self, Agregado, Periodo};
+
+const USO: &str = "\
+uso: plazas-cli [--config motor.toml] <comando> [args] [opciones]
+
+comandos:
+  agregar  <archivo>             agregación por estado
+  comparar <archivo1> <archivo2> agregación de ambos periodos
+  exportar <archivo>             filas crudas (columnas canónicas)
+
+opciones:
+  --filtro N      situación a filtrar (default -1 = todas)
+  --formato F     json | csv (default json)
+  --salida RUTA   archivo de salida (default stdout)";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Formato { Json, Csv }
+
+struct Args {
+    config:   Option<PathBuf>,
+    comando:  String,
+    archivos: Vec<PathBuf>,
+    filtro:   i64,
+    formato:  Formato,
+    salida:   Option<PathBuf>,
+}
+
+fn parse_args(mut it: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut args = Args {
+        config: None, comando: String::new(), archivos: Vec::new(),
+        filtro: -1, formato: Formato::Json, salida: None,
+    };
+    let valor = |it: &mut dyn Iterator<Item = String>, flag: &str| {
+        it.next().ok_or_else(|| format!("{flag} requiere un valor"))
+    };
+    while let Some(a) = it.next() {
+        match a.as_str() {
+            "--config"  => args.config = Some(valor(&mut it, "--config")?.into()),
+            "--salida"  => args.salida = Some(valor(&mut it, "--salida")?.into()),
+            "--filtro"  => {
+                let v = valor(&mut it, "--filtro")?;
+                args.filtro = v.parse().map_err(|_| format!("--filtro inválido: {v}"))?;
+            }
+            "--formato" => {
+                args.formato = match valor(&mut it, "--formato")?.as_str() {
+                    "json" => Formato::Json,
+                    "csv"  => Formato::Csv,
+                    otro   => return Err(format!("--formato desconocido: {otro}")),
+                };
+            }
+            "-h" | "--help" => return Err(String::new()),
+            f if f.starts_with("--") => return Err(format!("opción desconocida: {f}")),
+            _ if args.comando.is_empty() => args.comando = a,
+            _ => args.archivos.push(a.into()),
+        }
+    }
+    let esperados = match args.comando.as_str() {
+        "agregar" | "exportar" => 1,
+        "comparar"             => 2,
+        ""                     => return Err(String::new()),
+        otro                   => return Err(format!("comando desconocido: {otro}")),
+    };
+    if args.archivos.len() != esperados {
+        return Err(format!("{} espera {esperados} archivo(s)", args.comando));
+    }
+    Ok(args)
+}
+
+fn escribir_agregado_json<W: Write>(w: &mut W, agr: &Agregado) -> io::Result<()> {
+    w.write_all(b"{")?;
+    for (i, (eid, v)) in agr.iter().enumerate() {
+        if i > 0 { w.write_all(b",")?; }
+        write!(w, "\"{eid}\":{{")?;
+        for (j, (k, x)) in offline::metricas().iter().zip(v).enumerate() {
+            if j > 0 { w.write_all(b",")?; }
+            write!(w, "\"{k}\":{x}")?;
+        }
+        w.write_all(b"}")?;
+    }
+    w.write_all(b"}")
+}
+
+fn escribir_agregado_csv<W: Write>(w: &mut W, periodo: Option<u8>, agr: &Agregado) -> io::Result<()> {
+    for (eid, v) in agr {
+        if let Some(p) = periodo { write!(w, "{p},")?; }
+        write!(w, "{eid}")?;
+        for x in v { write!(w, ",{x}")?; }
+        w.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), String> {
+    if let Some(path) = &args.config {
+        let texto = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        offline::configurar(&texto)?;
+    }
+
+    let periodos = args.archivos.iter()
+        .map(|p| Periodo::desde_archivo(p))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut out: Box<dyn Write> = match &args.salida {
+        Some(p) => Box::new(File::create(p).map_err(|e| format!("{}: {e}", p.display()))?),
+        None    => Box::new(io::stdout().lock()),
+    };
+    let mut w = BufWriter::new(&mut out);
+    let cabecera = offline::metricas().join(",");
+
+    let res = match (args.comando.as_str(), args.formato) {
+        ("agregar", Formato::Json) => {
+            escribir_agregado_json(&mut w, &periodos[0].agregar(args.filtro)?)
+                .and_then(|_| w.write_all(b"\n"))
+        }
+        ("agregar", Formato::Csv) => {
+            let agr = periodos[0].agregar(args.filtro)?;
+            writeln!(w, "estado_id,{cabecera}")
+                .and_then(|_| escribir_agregado_csv(&mut w, None, &agr))
+        }
+        ("comparar", formato) => {
+            let (a1, a2) = rayon::join(
+                || periodos[0].agregar(args.filtro),
+                || periodos[1].agregar(args.filtro),
+            );
+            let (a1, a2) = (a1?, a2?);
+            if formato == Formato::Json {
+                w.write_all(b"{\"periodo1\":")
+                    .and_then(|_| escribir_agregado_json(&mut w, &a1))
+                    .and_then(|_| w.write_all(b",\"periodo2\":"))
+                    .and_then(|_| escribir_agregado_json(&mut w, &a2))
+                    .and_then(|_| w.write_all(b"}\n"))
+            } else {
+                writeln!(w, "periodo,estado_id,{cabecera}")
+                    .and_then(|_| escribir_agregado_csv(&mut w, Some(1), &a1))
+                    .and_then(|_| escribir_agregado_csv(&mut w, Some(2), &a2))
+            }
+        }
+        (_, Formato::Json) => periodos[0].exportar_json(&mut w),
+        (_, Formato::Csv)  => periodos[0].exportar_csv(&mut w),
+    };
+    res.and_then(|_| w.flush()).map_err(|e| format!("escritura: {e}"))
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(a) => a,
+        Err(msg) => {
+            if !msg.is_empty() { eprintln!("error: {msg}\n"); }
+            eprintln!("{USO}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(msg) => {
+            eprintln!("error: {msg}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// ==============================================================================
+// plaza_rust/src/bitacora.rs
+//
+// Bitácora de operaciones en memoria para reconstruir qué pasó antes de un
+// incidente: cada carga, comparación, evicción y cambio de configuración
+// deja una entrada (hora, parámetros, duración, request_id del llamador y
+// error si lo hubo). Anillo acotado a CAPACIDAD entradas: la más vieja se
+// descarta al llegar una nueva.
+//
+// Desde Python el request_id llega como argumento opcional de cada función;
+// desde HTTP, en el header X-Request-Id. Mientras corre una operación
+// auditada su request_id queda como el del hilo: las anotaciones que deja
+// por el camino (evicciones, cuarentena) y el registro de consultas lentas
+// lo llevan, y una auditada anidada sin request_id hereda el de afuera. Los
+// recálculos en fondo que dispara un pedido (revalidacion.rs) heredan el
+// suyo, así un pedido lento se sigue de punta a punta.
+// ==============================================================================
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+const CAPACIDAD: usize = 1024;
+
+struct Entrada {
+    ts_ms:       u64,
+    operacion:   &'static str,
+    parametros:  String,
+    duracion_us: u64,
+    request_id:  Option<String>,
+    error:       Option<String>,
+}
+
+static BITACORA: Mutex<VecDeque<Entrada>> = Mutex::new(VecDeque::new());
+
+thread_local! {
+    // request_id de la operación auditada en curso en este hilo
+    static EN_CURSO: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn ahora_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn empujar(e: Entrada) {
+    // Envenenado: se pierde la entrada, nunca la operación
+    let Ok(mut b) = BITACORA.lock() else { return };
+    if b.len() >= CAPACIDAD { b.pop_front(); }
+    b.push_back(e);
+}
+
+/// request_id de la operación en curso en este hilo (None fuera de una).
+pub(crate) fn actual() -> Option<String> {
+    EN_CURSO.with(|r| r.borrow().clone())
+}
+
+/// Corre `f` con `request_id` como el del hilo (p. ej. en un hilo de fondo
+/// que sigue el trabajo de un pedido) y restaura el anterior.
+pub(crate) fn con_request_id<T>(request_id: Option<String>, f: impl FnOnce() -> T) -> T {
+    let antes = EN_CURSO.with(|r| r.replace(request_id));
+    let r = f();
+    EN_CURSO.with(|r| *r.borrow_mut() = antes);
+    r
+}
+
+/// Registra un evento sin duración (p. ej. una evicción por capacidad).
+pub(crate) fn anotar(operacion: &'static str, parametros: String) {
+    empujar(Entrada {
+        ts_ms: ahora_ms(), operacion, parametros, duracion_us: 0, request_id: actual(), error: None,
+    });
+}
+
+/// Corre `f` y registra su duración y, si falló, el error.
+pub(crate) fn auditar<T, E: Display>(
+    operacion:  &'static str,
+    parametros: String,
+    request_id: Option<&str>,
+    f:          impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let request_id = request_id.map(str::to_string).or_else(actual);
+    let (ts_ms, t0) = (ahora_ms(), Instant::now());
+    let r = con_request_id(request_id.clone(), f);
+    empujar(Entrada {
+        ts_ms,
+        operacion,
+        parametros,
+        duracion_us: t0.elapsed().as_micros() as u64,
+        request_id,
+        error:       r.as_ref().err().map(|e| e.to_string()),
+    });
+    r
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// Las últimas `n` entradas, la más vieja primero: [{"ts_ms", "operacion",
+/// "parametros", "duracion_us", "request_id", "error"}, ...].
+#[pyfunction]
+#[pyo3(signature = (n = 100))]
+pub(crate) fn log_operaciones(py: Python<'_>, n: usize) -> PyResult<Vec<Bound<'_, PyDict>>> {
+    let b = BITACORA.lock()
+        .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("Mutex: BITACORA envenenado"))?;
+    b.iter().skip(b.len().saturating_sub(n)).map(|e| {
+        let d = PyDict::new(py);
+        d.set_item("ts_ms", e.ts_ms)?;
+        d.set_item("operacion", e.operacion)?;
+        d.set_item("parametros", &e.parametros)?;
+        d.set_item("duracion_us", e.duracion_us)?;
+        d.set_item("request_id", e.request_id.as_deref())?;
+        d.set_item("error", e.error.as_deref())?;
+        Ok(d)
+    }).collect()
+}
@@ -0,0 +1,112 @@
+// ==============================================================================
+// plaza_rust/src/buffers.rs
+//
+// Pool de buffers de columna reutilizados entre cargas. Cada carga arma
+// columnas de cientos de MB que después se descartan (las de lectura que
+// reemplazan permutar, compactar y codificar, y las de relleno); pedirlas y
+// soltarlas al allocator en cada carga lo fragmenta tras días de uptime.
+// Esos buffers de trabajo vuelven aquí y parse_parquet_bytes /
+// agrupar_por_estado toman de aquí antes de reservar memoria nueva.
+//
+// Las columnas de un periodo en cache nunca vuelven: al desalojarlo su
+// memoria va al allocator y el RSS baja (el watchdog de Python cuenta con
+// eso). Por lo mismo toda evicción de periodos vacía el pool, y el pool
+// retiene a lo sumo MAX_BYTES_POR_TIPO por tipo.
+//
+// liberar_buffers() devuelve todo al allocator (p. ej. antes de un fork).
+// ==============================================================================
+
+use std::sync::Mutex;
+
+use pyo3::prelude::*;
+
+// Una carga descarta hasta 8 columnas i64 y 2 f64; se guarda una carga de margen
+const MAX_POR_TIPO: usize = 10;
+const MAX_BYTES_POR_TIPO: usize = 256 << 20;
+
+pub(crate) struct Pool<T> {
+    libres: Mutex<Vec<Vec<T>>>,
+}
+
+pub(crate) static F64: Pool<f64> = Pool::new();
+pub(crate) static I64: Pool<i64> = Pool::new();
+
+impl<T: Copy> Pool<T> {
+    const fn new() -> Self {
+        Pool { libres: Mutex::new(Vec::new()) }
+    }
+
+    /// Vec vacío con capacidad >= `cap`. Se usa el buffer libre más chico que
+    /// alcance, siempre que no sobre más del doble (un periodo chico no debe
+    /// quedarse con el buffer de uno grande).
+    pub(crate) fn tomar(&self, cap: usize) -> Vec<T> {
+        if cap > 0 {
+            if let Ok(mut libres) = self.libres.lock() {
+                let mejor = libres.iter().enumerate()
+                    .filter(|(_, v)| v.capacity() >= cap && v.capacity() / 2 <= cap)
+                    .min_by_key(|(_, v)| v.capacity())
+                    .map(|(k, _)| k);
+                if let Some(k) = mejor {
+                    return libres.swap_remove(k);
+                }
+            }
+        }
+        Vec::with_capacity(cap)
+    }
+
+    /// Como `vec![valor; n]` pero sobre un buffer del pool.
+    pub(crate) fn lleno(&self, n: usize, valor: T) -> Vec<T> {
+        let mut v = self.tomar(n);
+        v.resize(n, valor);
+        v
+    }
+
+    /// Devuelve el buffer al pool; mientras pase de MAX_POR_TIPO buffers o
+    /// de MAX_BYTES_POR_TIPO se descarta el más chico.
+    pub(crate) fn devolver(&self, mut v: Vec<T>) {
+        if v.capacity() == 0 || bytes_de(&v) > MAX_BYTES_POR_TIPO { return; }
+        v.clear();
+        let Ok(mut libres) = self.libres.lock() else { return };
+        libres.push(v);
+        while libres.len() > MAX_POR_TIPO || libres.iter().map(bytes_de).sum::<usize>() > MAX_BYTES_POR_TIPO {
+            let Some(k) = libres.iter().enumerate().min_by_key(|(_, v)| v.capacity()).map(|(k, _)| k) else { break };
+            libres.swap_remove(k);
+        }
+    }
+
+    /// Bytes retenidos por los buffers libres.
+    pub(crate) fn bytes(&self) -> usize {
+        self.libres.lock().map_or(0, |l| l.iter().map(bytes_de).sum())
+    }
+
+    fn vaciar(&self) -> usize {
+        let liberados = self.bytes();
+        if let Ok(mut l) = self.libres.lock() { *l = Vec::new(); }
+        liberados
+    }
+}
+
+fn bytes_de<T>(v: &Vec<T>) -> usize {
+    v.capacity() * std::mem::size_of::<T>()
+}
+
+/// Bytes en buffers libres de ambos pools.
+pub(crate) fn bytes_retenidos() -> usize {
+    F64.bytes() + I64.bytes()
+}
+
+/// Tras desalojar periodos: lo que se liberó no debe quedar retenido aquí.
+pub(crate) fn tras_eviccion() {
+    F64.vaciar();
+    I64.vaciar();
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// Devuelve al allocator todos los buffers libres; retorna los bytes liberados.
+#[pyfunction]
+pub(crate) fn liberar_buffers() -> usize {
+    F64.vaciar() + I64.vaciar()
+}
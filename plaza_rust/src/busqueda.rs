@@ -0,0 +1,83 @@
+// ==============================================================================
+// plaza_rust/src/busqueda.rs
+//
+// Búsqueda por subcadena en columnas de texto internadas (hoy plaza_id, la
+// única que el motor guarda como códigos a valores distintos): la caja de
+// búsqueda consulta directo al periodo cargado, sin índice aparte.
+//
+// Sin distinguir mayúsculas ni acentos (normalizar_nombre de ambos lados).
+// Primero se prueban los valores distintos en paralelo y después se marcan
+// las filas cuyo código coincidió, así que el costo de comparar texto no
+// crece con las filas repetidas.
+// ==============================================================================
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::{clave, config, normalizar_nombre, periodo, EngineData};
+
+// Columnas internadas que admite buscar()
+const COLUMNAS: [&str; 1] = ["plaza_id"];
+
+/// Códigos (índices en `valores`) cuyo texto contiene `aguja` normalizada.
+fn codigos(valores: &[String], aguja: &str) -> Vec<bool> {
+    let prueba = |v: &String| normalizar_nombre(v).contains(aguja);
+    if config::secuencial(valores.len()) {
+        valores.iter().map(prueba).collect()
+    } else {
+        config::en_pool(|| valores.par_iter().map(prueba).collect())
+    }
+}
+
+/// (fila original, valor) de hasta `limite` filas, por fila ascendente.
+fn buscar_plazas(eng: &EngineData, aguja: &str, limite: usize) -> Vec<(usize, String)> {
+    if !eng.tiene_plazas() || limite == 0 { return Vec::new(); }
+    let coincide = codigos(&eng.plazas, aguja);
+    if !coincide.contains(&true) { return Vec::new(); }
+    let pasa = |&i: &usize| {
+        let c = eng.plaza_ids.get(i);
+        c != i64::MIN && coincide[c as usize]
+    };
+    let mut filas: Vec<usize> = if config::secuencial(eng.n) {
+        (0..eng.n).filter(pasa).collect()
+    } else {
+        config::en_pool(|| (0..eng.n).into_par_iter().filter(pasa).collect())
+    };
+    filas.sort_unstable_by_key(|&i| eng.fila_original(i));
+    filas.truncate(limite);
+    filas.into_iter()
+        .map(|i| (eng.fila_original(i), eng.plaza(i).unwrap_or_default().to_string()))
+        .collect()
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// [(fila, valor)] de hasta `limite` filas del periodo cuya `columna`
+/// contiene `subcadena` (sin mayúsculas ni acentos), por fila ascendente.
+/// Una subcadena vacía no coincide con nada.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, columna, subcadena, limite = 20))]
+pub(crate) fn buscar(
+    py:          Python<'_>,
+    periodo_key: ArgClave,
+    columna:     &str,
+    subcadena:   &str,
+    limite:      usize,
+) -> PyResult<Vec<(usize, String)>> {
+    let key = clave(periodo_key)?;
+    if !COLUMNAS.contains(&columna) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "buscar: columna {columna:?} no es de texto (se espera una de {COLUMNAS:?})"
+        )));
+    }
+    let aguja = normalizar_nombre(subcadena);
+    if aguja.is_empty() { return Ok(Vec::new()); }
+    py.allow_threads(|| -> Result<_, crate::errores::Error> {
+        let eng = periodo(key)?;
+        eng.tocar();
+        Ok(buscar_plazas(&eng, &aguja, limite))
+    }).map_err(Into::into)
+}
@@ -0,0 +1,221 @@
+// ==============================================================================
+// plaza_rust/src/calidad.rs
+//
+// Reglas de calidad de datos configurables desde Python y su evaluación
+// sobre un periodo cargado. definir_reglas() reemplaza el juego completo;
+// evaluar_calidad() devuelve por regla cuántas filas la violan y una
+// muestra de índices (orden del parquet) para revisarlas.
+//
+//   {"nombre": "lat_mexico", "tipo": "rango", "columna": "lat",
+//    "min": 14.5, "max": 32.8}
+//   {"nombre": "cn_cuadra", "tipo": "mayor_igual_suma", "columna": "cn_total",
+//    "columnas": ["cn_ini", "cn_prim", "cn_sec"]}
+//   {"nombre": "sin_duplicados", "tipo": "unico", "columna": "plaza_id"}
+//   {"nombre": "con_estado", "tipo": "no_nulo", "columna": "estado_id"}
+//
+// Columnas: lat, lng, estado_id, situacion, plaza_id y las métricas (por
+// nombre de salida o canónico). Las filas con nulo en la columna evaluada
+// no cuentan para rango/mayor_igual_suma/unico; en la suma, nulo = 0.
+// ==============================================================================
+
+use std::sync::RwLock;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+use rustc_hash::FxHashSet;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{clave, config, periodo, EngineData, METRICAS};
+
+/// Columna de un periodo por nombre (también la usa distancias_cercanas).
+#[derive(Clone, Copy)]
+pub(crate) enum Campo {
+    Lat,
+    Lng,
+    EstadoId,
+    Situacion,
+    PlazaId,
+    // posición en EngineData::metricas()
+    Metrica(usize),
+}
+
+impl Campo {
+    pub(crate) fn parse(s: &str) -> Result<Self, String> {
+        Ok(match s {
+            "lat"       => Campo::Lat,
+            "lng"       => Campo::Lng,
+            "estado_id" => Campo::EstadoId,
+            "situacion" => Campo::Situacion,
+            "plaza_id"  => Campo::PlazaId,
+            otro => {
+                let m = METRICAS[1..].iter().position(|&x| x == otro)
+                    .or_else(|| config::METRICAS_COLUMNA.iter().position(|&x| x == otro))
+                    .ok_or_else(|| format!("columna desconocida: {otro:?}"))?;
+                Campo::Metrica(m)
+            }
+        })
+    }
+
+    /// Valor numérico de la fila interna `i`; None si es nulo. plaza_id da su
+    /// código en el diccionario del periodo.
+    pub(crate) fn valor(self, eng: &EngineData, i: usize) -> Option<f64> {
+        let entero = |x: i64| (x != i64::MIN).then_some(x as f64);
+        match self {
+            Campo::Lat       => Some(eng.lats.get(i)).filter(|x| !x.is_nan()),
+            Campo::Lng       => Some(eng.lngs.get(i)).filter(|x| !x.is_nan()),
+            Campo::EstadoId  => entero(eng.estado_ids.get(i)),
+            Campo::Situacion => entero(eng.situaciones.get(i)),
+            Campo::PlazaId   => if eng.tiene_plazas() { entero(eng.plaza_ids.get(i)) } else { None },
+            Campo::Metrica(m) => entero(eng.metricas()[m].get(i)),
+        }
+    }
+
+    /// Valor de la fila `i` para devolver a Python: entero, float, el
+    /// identificador de plaza como str, o None si es nulo.
+    pub(crate) fn a_python<'py>(self, py: Python<'py>, eng: &EngineData, i: usize) -> PyResult<Bound<'py, PyAny>> {
+        let Some(x) = self.valor(eng, i) else { return Ok(py.None().into_bound(py)) };
+        Ok(match self {
+            Campo::Lat | Campo::Lng => x.into_pyobject(py)?.into_any(),
+            Campo::PlazaId          => eng.plaza(i).into_pyobject(py)?.into_any(),
+            _                       => (x as i64).into_pyobject(py)?.into_any(),
+        })
+    }
+}
+
+#[derive(Clone)]
+enum Condicion {
+    Rango { min: Option<f64>, max: Option<f64> },
+    MayorIgualSuma(Vec<Campo>),
+    Unico,
+    NoNulo,
+}
+
+#[derive(Clone)]
+struct Regla {
+    nombre:    String,
+    columna:   Campo,
+    condicion: Condicion,
+}
+
+static REGLAS: RwLock<Vec<Regla>> = RwLock::new(Vec::new());
+
+fn parse_regla(d: &Bound<'_, PyDict>) -> Result<Regla, String> {
+    let texto = |k: &str| -> Result<String, String> {
+        d.get_item(k).ok().flatten().ok_or_else(|| format!("falta {k:?}"))?
+            .extract::<String>().map_err(|_| format!("{k:?} debe ser str"))
+    };
+    let numero = |k: &str| -> Result<Option<f64>, String> {
+        match d.get_item(k).ok().flatten() {
+            None => Ok(None),
+            Some(v) if v.is_none() => Ok(None),
+            Some(v) => v.extract::<f64>().map(Some).map_err(|_| format!("{k:?} debe ser número")),
+        }
+    };
+    let nombre = texto("nombre")?;
+    let columna = Campo::parse(&texto("columna")?)?;
+    let condicion = match texto("tipo")?.as_str() {
+        "rango" => {
+            let (min, max) = (numero("min")?, numero("max")?);
+            if min.is_none() && max.is_none() { return Err("rango sin min ni max".into()); }
+            Condicion::Rango { min, max }
+        }
+        "mayor_igual_suma" => {
+            let cols: Vec<String> = d.get_item("columnas").ok().flatten()
+                .ok_or("falta \"columnas\"")?
+                .extract().map_err(|_| "\"columnas\" debe ser lista de str".to_string())?;
+            Condicion::MayorIgualSuma(cols.iter().map(|c| Campo::parse(c)).collect::<Result<_, _>>()?)
+        }
+        "unico"   => Condicion::Unico,
+        "no_nulo" => Condicion::NoNulo,
+        otro => return Err(format!("tipo desconocido: {otro:?} (rango | mayor_igual_suma | unico | no_nulo)")),
+    };
+    Ok(Regla { nombre, columna, condicion })
+}
+
+struct Evaluacion {
+    evaluadas:   usize,
+    violaciones: usize,
+    muestra:     Vec<usize>,
+}
+
+/// Recorre las filas en el orden del parquet (así "unico" marca la segunda
+/// aparición en adelante y la muestra son las primeras violaciones).
+fn evaluar(eng: &EngineData, r: &Regla, max_muestra: usize) -> Evaluacion {
+    let mut internas: Vec<usize> = (0..eng.n).collect();
+    internas.sort_unstable_by_key(|&i| eng.fila_original(i));
+    let mut vistos: FxHashSet<u64> = FxHashSet::default();
+    let mut e = Evaluacion { evaluadas: 0, violaciones: 0, muestra: Vec::new() };
+    for i in internas {
+        let x = r.columna.valor(eng, i);
+        let viola = match (&r.condicion, x) {
+            (Condicion::NoNulo, x) => x.is_none(),
+            (_, None) => continue,
+            (Condicion::Rango { min, max }, Some(x)) => min.is_some_and(|m| x < m) || max.is_some_and(|m| x > m),
+            (Condicion::MayorIgualSuma(cols), Some(x)) => {
+                x < cols.iter().map(|c| c.valor(eng, i).unwrap_or(0.0)).sum::<f64>()
+            }
+            (Condicion::Unico, Some(x)) => !vistos.insert(x.to_bits()),
+        };
+        e.evaluadas += 1;
+        if viola {
+            e.violaciones += 1;
+            if e.muestra.len() < max_muestra { e.muestra.push(eng.fila_original(i)); }
+        }
+    }
+    e
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Reemplaza las reglas vigentes; con un error no cambia nada. Devuelve
+/// cuántas quedaron.
+#[pyfunction]
+pub(crate) fn definir_reglas(rules: Vec<Bound<'_, PyDict>>) -> PyResult<usize> {
+    let mut nuevas = Vec::with_capacity(rules.len());
+    for (k, d) in rules.iter().enumerate() {
+        let r = parse_regla(d).map_err(|e| PyValueError::new_err(format!("regla {k}: {e}")))?;
+        if nuevas.iter().any(|x: &Regla| x.nombre == r.nombre) {
+            return Err(PyValueError::new_err(format!("regla {k}: nombre repetido {:?}", r.nombre)));
+        }
+        nuevas.push(r);
+    }
+    let n = nuevas.len();
+    *REGLAS.write().map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("RwLock: REGLAS envenenado"))? = nuevas;
+    Ok(n)
+}
+
+/// {nombre: {"evaluadas", "violaciones", "muestra": [filas...]}} en el orden
+/// en que se definieron las reglas; hasta `muestra` índices por regla.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, muestra = 10))]
+pub(crate) fn evaluar_calidad(py: Python<'_>, periodo_key: ArgClave, muestra: usize) -> PyResult<Bound<'_, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    let reglas = REGLAS.read()
+        .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("RwLock: REGLAS envenenado"))?
+        .clone();
+    let resultados = py.allow_threads(|| -> Result<_, Error> {
+        let eng = periodo(periodo_key)?;
+        eng.tocar();
+        let evaluar_una = |r: &Regla| evaluar(&eng, r, muestra);
+        Ok(if config::secuencial(eng.n) {
+            reglas.iter().map(evaluar_una).collect::<Vec<_>>()
+        } else {
+            config::en_pool(|| reglas.par_iter().map(evaluar_una).collect())
+        })
+    })?;
+
+    let out = PyDict::new(py);
+    for (r, e) in reglas.iter().zip(resultados) {
+        let d = PyDict::new(py);
+        d.set_item("evaluadas", e.evaluadas)?;
+        d.set_item("violaciones", e.violaciones)?;
+        d.set_item("muestra", e.muestra)?;
+        out.set_item(&r.nombre, d)?;
+    }
+    Ok(out)
+}
@@ -0,0 +1,135 @@
+// ==============================================================================
+// plaza_rust/src/cambios.rs
+//
+// Reporte de cambios entre dos periodos para las alertas por correo: los
+// estados cuyas métricas se movieron más que un umbral absoluto o relativo.
+// Parte de la misma agregación por estado que comparar_periodos (y de su
+// cache), así que pedir el reporte después de la comparación no recalcula.
+//
+//   umbrales = {"inc_total": (500, 0.10), "plazas": (None, 0.05)}
+//              (absoluto, relativo); None desactiva ese lado. Un cambio entra
+//              si |delta| >= absoluto o |delta / antes| >= relativo.
+//
+// El motor todavía no carga municipio, así que el reporte es por estado.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{bitacora, clave, comparar, AgrMap, PeriodoKey, METRICAS};
+
+#[derive(Clone, Copy)]
+struct Umbral {
+    absoluto: Option<i64>,
+    relativo: Option<f64>,
+}
+
+/// Un estado/métrica que superó su umbral.
+struct Cambio {
+    estado_id:    i64,
+    metrica:      &'static str,
+    antes:        i64,
+    despues:      i64,
+    // delta / antes; None si antes == 0 (el estado aparece en key2)
+    relativo:     Option<f64>,
+    por_absoluto: bool,
+    por_relativo: bool,
+}
+
+/// Valida nombres contra METRICAS y que los umbrales no sean negativos.
+fn parse_umbrales(umbrales: BTreeMap<String, (Option<i64>, Option<f64>)>) -> Result<[Option<Umbral>; 7], String> {
+    let mut out = [None; 7];
+    for (nombre, (absoluto, relativo)) in umbrales {
+        let Some(m) = METRICAS.iter().position(|&x| x == nombre) else {
+            return Err(format!("métrica desconocida en umbrales: {nombre:?} ({})", METRICAS.join(" | ")));
+        };
+        if absoluto.is_some_and(|a| a < 0) || relativo.is_some_and(|r| r.is_nan() || r < 0.0) {
+            return Err(format!("umbral de {nombre} negativo o inválido"));
+        }
+        out[m] = Some(Umbral { absoluto, relativo });
+    }
+    Ok(out)
+}
+
+/// Cambios que superan su umbral, por estado y en el orden de METRICAS.
+/// Un estado presente en un solo periodo cuenta como 0 en el otro.
+fn detectar(agr1: &AgrMap, agr2: &AgrMap, umbrales: &[Option<Umbral>; 7]) -> Vec<Cambio> {
+    let mut estados: Vec<i64> = agr1.keys().chain(agr2.keys()).copied().collect();
+    estados.sort_unstable();
+    estados.dedup();
+
+    let mut out = Vec::new();
+    for eid in estados {
+        let v1 = agr1.get(&eid).copied().unwrap_or_default();
+        let v2 = agr2.get(&eid).copied().unwrap_or_default();
+        for (m, u) in umbrales.iter().enumerate() {
+            let Some(u) = u else { continue };
+            let (antes, despues) = (v1[m], v2[m]);
+            let delta = despues.saturating_sub(antes);
+            let relativo = (antes != 0).then(|| delta as f64 / antes as f64);
+            let por_absoluto = u.absoluto.is_some_and(|a| delta.unsigned_abs() >= a as u64);
+            let por_relativo = match (u.relativo, relativo) {
+                (Some(lim), Some(r)) => r.abs() >= lim,
+                // de 0 a algo: cualquier umbral relativo se supera
+                (Some(_), None) => delta != 0,
+                (None, _) => false,
+            };
+            if por_absoluto || por_relativo {
+                out.push(Cambio { estado_id: eid, metrica: METRICAS[m], antes, despues, relativo, por_absoluto, por_relativo });
+            }
+        }
+    }
+    out
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"key1", "key2", "filtro_situacion", "cambios": [...]}; cada cambio es
+/// {"estado_id", "metrica", "antes", "despues", "delta", "relativo",
+/// "supera": ["absoluto" | "relativo"]}, ordenados por estado y métrica.
+#[pyfunction]
+#[pyo3(signature = (key1, key2, umbrales, filtro_situacion = -1, request_id = None))]
+pub(crate) fn reporte_cambios<'py>(
+    py:               Python<'py>,
+    key1:             ArgClave,
+    key2:             ArgClave,
+    umbrales:         BTreeMap<String, (Option<i64>, Option<f64>)>,
+    filtro_situacion: i64,
+    request_id:       Option<&str>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let (key1, key2): (PeriodoKey, PeriodoKey) = (clave(key1)?, clave(key2)?);
+    let umbrales = parse_umbrales(umbrales).map_err(PyValueError::new_err)?;
+    let params = format!("key1={key1} key2={key2} filtro={filtro_situacion}");
+    let cambios = py.allow_threads(|| bitacora::auditar("reporte_cambios", params, request_id, || {
+        let (agr1, agr2, _) = comparar(key1, key2, filtro_situacion)?;
+        Ok::<_, Error>(detectar(&agr1, &agr2, &umbrales))
+    }))?;
+
+    let lista = PyList::empty(py);
+    for c in cambios {
+        let d = PyDict::new(py);
+        d.set_item("estado_id", c.estado_id)?;
+        d.set_item("metrica", c.metrica)?;
+        d.set_item("antes", c.antes)?;
+        d.set_item("despues", c.despues)?;
+        d.set_item("delta", c.despues.saturating_sub(c.antes))?;
+        d.set_item("relativo", c.relativo)?;
+        let supera: Vec<&str> = [("absoluto", c.por_absoluto), ("relativo", c.por_relativo)]
+            .into_iter().filter(|&(_, s)| s).map(|(n, _)| n).collect();
+        d.set_item("supera", supera)?;
+        lista.append(d)?;
+    }
+    let out = PyDict::new(py);
+    out.set_item("key1", key1)?;
+    out.set_item("key2", key2)?;
+    out.set_item("filtro_situacion", filtro_situacion)?;
+    out.set_item("cambios", lista)?;
+    Ok(out)
+}
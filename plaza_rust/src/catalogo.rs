@@ -0,0 +1,53 @@
+// ==============================================================================
+// plaza_rust/src/catalogo.rs
+//
+// Catálogo de estados (estado_id → nombre, p. ej. el de INEGI) registrado una
+// vez desde Python para que las respuestas lo traigan junto al id:
+//
+//   registrar_catalogo_estados({1: "Aguascalientes", ..., 32: "Zacatecas"})
+//   comparar_periodos(..., con_estado_nombre=True)
+//     → {"periodo1": {1: {"estado_nombre": "Aguascalientes", "plazas": ...}}}
+//
+// Registrar reemplaza el catálogo completo. Un id sin nombre en el catálogo
+// sale con estado_nombre = None; el catálogo no filtra ni valida estados.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::cerrojos::Cerrojo;
+use crate::errores::Error;
+
+pub(crate) type Catalogo = BTreeMap<i64, String>;
+
+static CATALOGO: Cerrojo<Catalogo> = Cerrojo::new("CATALOGO", BTreeMap::new());
+
+/// Copia del catálogo vigente (unas decenas de entradas) para armar una
+/// respuesta sin sostener el lock.
+pub(crate) fn copia() -> Result<Catalogo, Error> {
+    Ok(CATALOGO.leer("catalogo")?.clone())
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Reemplaza el catálogo por `mapping` ({estado_id: nombre}). Devuelve
+/// cuántos estados quedaron registrados.
+#[pyfunction]
+pub(crate) fn registrar_catalogo_estados(mapping: BTreeMap<i64, String>) -> PyResult<usize> {
+    if let Some((eid, _)) = mapping.iter().find(|(_, n)| n.trim().is_empty()) {
+        return Err(PyValueError::new_err(format!("catálogo: estado {eid} con nombre vacío")));
+    }
+    let n = mapping.len();
+    *CATALOGO.escribir("registrar_catalogo_estados")? = mapping;
+    Ok(n)
+}
+
+/// {estado_id: nombre} registrado (vacío si nunca se registró).
+#[pyfunction]
+pub(crate) fn catalogo_estados() -> PyResult<Catalogo> {
+    Ok(copia()?)
+}
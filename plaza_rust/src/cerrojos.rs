@@ -0,0 +1,98 @@
+// ==============================================================================
+// plaza_rust/src/cerrojos.rs
+//
+// RwLock de los caches globales con timeout y registro del escritor. Una
+// carga grande sostiene ENGINE_PERIODOS en escritura y, sin límite, cada
+// lector queda bloqueado sin ninguna pista. Con [cache] timeout_lock_ms > 0,
+// leer()/escribir() esperan hasta el límite y fallan con CacheOcupado
+// diciendo qué operación tiene el lock y desde hace cuánto. Con 0 (default)
+// se espera sin límite, como antes.
+//
+// El RwLock es el de parking_lot: la espera con timeout encola de verdad
+// (try_read_for / try_write_for) y es justo entre lectores y escritores, así
+// que un escritor no pierde ante un flujo constante de lecturas (cada
+// comparación lee ENGINE_PERIODOS) y una carga ya parseada no falla al
+// insertar por no encontrar nunca el lock libre. No hay envenenamiento.
+// ==============================================================================
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::config;
+use crate::errores::Error;
+
+pub(crate) struct Cerrojo<T> {
+    nombre:   &'static str,
+    lock:     RwLock<T>,
+    // Operación que tiene el lock en escritura y desde cuándo
+    escritor: Mutex<Option<(&'static str, Instant)>>,
+}
+
+/// Guard de escritura; al soltarse borra el registro del escritor.
+pub(crate) struct Escritura<'a, T> {
+    guard:    RwLockWriteGuard<'a, T>,
+    escritor: &'a Mutex<Option<(&'static str, Instant)>>,
+}
+
+impl<T> Deref for Escritura<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.guard }
+}
+
+impl<T> DerefMut for Escritura<'_, T> {
+    fn deref_mut(&mut self) -> &mut T { &mut self.guard }
+}
+
+impl<T> Drop for Escritura<'_, T> {
+    // Corre antes de soltar `guard`: el siguiente escritor aún no pudo anotarse
+    fn drop(&mut self) {
+        if let Ok(mut e) = self.escritor.lock() { *e = None; }
+    }
+}
+
+impl<T> Cerrojo<T> {
+    pub(crate) const fn new(nombre: &'static str, valor: T) -> Self {
+        Cerrojo { nombre, lock: RwLock::new(valor), escritor: Mutex::new(None) }
+    }
+
+    pub(crate) fn leer(&self, op: &'static str) -> Result<RwLockReadGuard<'_, T>, Error> {
+        match config::timeout_lock() {
+            None    => Ok(self.lock.read()),
+            Some(t) => self.lock.try_read_for(t).ok_or_else(|| Error::CacheOcupado(self.describir(op, t))),
+        }
+    }
+
+    pub(crate) fn escribir(&self, op: &'static str) -> Result<Escritura<'_, T>, Error> {
+        let guard = match config::timeout_lock() {
+            None    => self.lock.write(),
+            Some(t) => self.lock.try_write_for(t).ok_or_else(|| Error::CacheOcupado(self.describir(op, t)))?,
+        };
+        if let Ok(mut e) = self.escritor.lock() { *e = Some((op, Instant::now())); }
+        Ok(Escritura { guard, escritor: &self.escritor })
+    }
+
+    /// Intenta tomarlo en lectura hasta `timeout` y lo suelta (healthcheck):
+    /// no compite con los lectores ni frena pedidos, y falla solo si un
+    /// escritor lo retiene (el error dice cuál, ocupado_por()).
+    pub(crate) fn probar(&self, timeout: Duration) -> Result<(), String> {
+        self.lock.try_read_for(timeout)
+            .map(drop)
+            .ok_or_else(|| self.describir("healthcheck", timeout))
+    }
+
+    /// Operación que lo tiene en escritura y hace cuánto, si hay alguna.
+    pub(crate) fn ocupado_por(&self) -> Option<(&'static str, Duration)> {
+        self.escritor.lock().ok()?.map(|(op, t0)| (op, t0.elapsed()))
+    }
+
+    fn describir(&self, op: &str, timeout: Duration) -> String {
+        let quien = match self.ocupado_por() {
+            Some((dueño, t)) => format!("en escritura por {dueño} hace {} ms", t.as_millis()),
+            None => "tomado por lectores".to_string(),
+        };
+        format!("{}: {op} esperó {} ms; {quien}", self.nombre, timeout.as_millis())
+    }
+}
@@ -0,0 +1,232 @@
+// ==============================================================================
+// plaza_rust/src/columna.rs
+//
+// Almacenamiento de columnas con ancho variable. Por defecto todo es i64/f64;
+// con [motor] compacto = true, al cargar cada columna entera se guarda en el
+// tipo más chico que cubre su rango observado (situación cabe en i8, estado
+// en i16, conteos en i32) y las coordenadas en f32 (~1 m de resolución).
+// Un periodo típico baja de 80 a ~36 bytes por fila.
+//
+// Los nulos son el MIN de cada tipo; get() los devuelve como i64::MIN, así
+// que el resto del motor sigue viendo i64. Los kernels de suma son genéricos
+// sobre el ancho y acumulan en i64.
+//
+// La carga sigue leyendo en i64/f64 y compacta al final; esos buffers anchos
+// (y los que reemplazan permutar/codificar) vuelven al pool (buffers.rs)
+// para la siguiente carga. Las columnas de un periodo en cache no: al
+// desalojarlo su memoria vuelve al allocator.
+//
+// Con [motor] diccionario = true las 6 métricas se guardan como un código
+// por fila hacia la tupla de valores (en un mes disperso casi todas las
+// filas son la tupla en cero): los códigos se comparten entre las 6 y cada
+// una guarda solo su valor por tupla. Si hay más de MAX_TUPLAS tuplas
+// distintas o no ahorra memoria, las columnas quedan como estaban. La
+// agregación cuenta filas por código y multiplica (parcial_rango).
+// ==============================================================================
+
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+use crate::buffers;
+
+// Un conteo por tupla en cada bloque de la agregación
+pub(crate) const MAX_TUPLAS: usize = 4096;
+
+/// Enteros almacenables: `NULO` es su MIN y ningún valor real lo usa.
+pub(crate) trait Entero: Copy + PartialEq + Into<i64> + Send + Sync {
+    const NULO: Self;
+}
+
+impl Entero for i8  { const NULO: Self = i8::MIN; }
+impl Entero for i16 { const NULO: Self = i16::MIN; }
+impl Entero for i32 { const NULO: Self = i32::MIN; }
+impl Entero for i64 { const NULO: Self = i64::MIN; }
+
+#[inline(always)]
+pub(crate) fn ancho<T: Entero>(x: T) -> i64 {
+    if x == T::NULO { i64::MIN } else { x.into() }
+}
+
+pub(crate) enum ColI {
+    I8(Vec<i8>),
+    I16(Vec<i16>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    Dic(Diccionario),
+}
+
+/// Métrica en diccionario: valor de la tupla `codigos[i]` de la fila i.
+pub(crate) struct Diccionario {
+    // Compartidos por las 6 métricas del periodo; nunca nulos ni Dic
+    pub(crate) codigos: Arc<ColI>,
+    pub(crate) valores: Vec<i64>,
+}
+
+pub(crate) enum ColF {
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+}
+
+/// Evalúa `$e` con `$v` ligado al Vec concreto de la columna (monomorfiza
+/// el kernel para cada ancho). Una columna en diccionario no tiene slice:
+/// quien despacha métricas mira antes EngineData::diccionario().
+macro_rules! despachar {
+    ($col:expr, $v:ident => $e:expr) => {
+        match $col {
+            $crate::columna::ColI::I8($v)  => $e,
+            $crate::columna::ColI::I16($v) => $e,
+            $crate::columna::ColI::I32($v) => $e,
+            $crate::columna::ColI::I64($v) => $e,
+            $crate::columna::ColI::Dic(_)  => unreachable!("despachar sobre una columna en diccionario"),
+        }
+    };
+}
+pub(crate) use despachar;
+
+impl From<Vec<i64>> for ColI {
+    fn from(v: Vec<i64>) -> Self { ColI::I64(v) }
+}
+
+impl From<Vec<f64>> for ColF {
+    fn from(v: Vec<f64>) -> Self { ColF::F64(v) }
+}
+
+fn estrechar<T: Entero + TryFrom<i64>>(v: &[i64]) -> Vec<T> {
+    v.iter().map(|&x| if x == i64::MIN { T::NULO } else {
+        T::try_from(x).ok().filter(|&t| t != T::NULO).expect("rango verificado")
+    }).collect()
+}
+
+impl ColI {
+    #[inline(always)]
+    pub(crate) fn get(&self, i: usize) -> i64 {
+        match self {
+            ColI::Dic(d) => d.valores[d.codigos.get(i) as usize],
+            _            => despachar!(self, v => ancho(v[i])),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            ColI::Dic(d) => d.codigos.len(),
+            _            => despachar!(self, v => v.len()),
+        }
+    }
+
+    /// En diccionario, solo los valores: los códigos compartidos se cuentan
+    /// aparte (EngineData::bytes_por_estructura).
+    pub(crate) fn bytes(&self) -> usize {
+        fn cap<T>(v: &Vec<T>) -> usize { v.capacity() * std::mem::size_of::<T>() }
+        match self {
+            ColI::Dic(d) => cap(&d.valores),
+            _            => despachar!(self, v => cap(v)),
+        }
+    }
+
+    /// Reordena las filas según `orden` (fila nueva i = fila vieja orden[i]).
+    pub(crate) fn permutar(&mut self, orden: &[u32]) {
+        match self {
+            ColI::I64(col) => {
+                let mut nuevo = buffers::I64.tomar(orden.len());
+                nuevo.extend(orden.iter().map(|&i| col[i as usize]));
+                buffers::I64.devolver(std::mem::replace(col, nuevo));
+            }
+            ColI::I8(col)  => *col = orden.iter().map(|&i| col[i as usize]).collect(),
+            ColI::I16(col) => *col = orden.iter().map(|&i| col[i as usize]).collect(),
+            ColI::I32(col) => *col = orden.iter().map(|&i| col[i as usize]).collect(),
+            // Se codifica después de agrupar; por si acaso, vuelve a i64
+            ColI::Dic(d) => {
+                let v = orden.iter().map(|&i| d.valores[d.codigos.get(i as usize) as usize]).collect();
+                *self = ColI::I64(v);
+            }
+        }
+    }
+
+    /// Pasa al ancho mínimo que cubre [min, max] de los valores no nulos.
+    /// El buffer i64 vuelve al pool.
+    pub(crate) fn compactar(&mut self) {
+        let ColI::I64(v) = self else { return };
+        let (min, max) = v.iter().filter(|&&x| x != i64::MIN)
+            .fold((0i64, 0i64), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+        let cabe = |lo: i64, hi: i64| min > lo && max <= hi;
+        let nuevo = if cabe(i8::MIN.into(), i8::MAX.into()) {
+            ColI::I8(estrechar(v))
+        } else if cabe(i16::MIN.into(), i16::MAX.into()) {
+            ColI::I16(estrechar(v))
+        } else if cabe(i32::MIN.into(), i32::MAX.into()) {
+            ColI::I32(estrechar(v))
+        } else {
+            return;
+        };
+        std::mem::replace(self, nuevo).reciclar();
+    }
+
+    /// Reemplazada durante la carga: un buffer i64 vuelve al pool.
+    pub(crate) fn reciclar(self) {
+        if let ColI::I64(v) = self { buffers::I64.devolver(v); }
+    }
+}
+
+/// Pasa `cols` (mismo largo) a diccionario de tuplas compartiendo los
+/// códigos. `false` si hay más de MAX_TUPLAS tuplas o no ahorra memoria: las
+/// columnas no se tocan.
+pub(crate) fn codificar<const N: usize>(cols: [&mut ColI; N]) -> bool {
+    let n = cols[0].len();
+    if n == 0 || cols.iter().any(|c| c.len() != n || matches!(c, ColI::Dic(_))) { return false; }
+    let mut tuplas: Vec<[i64; N]> = Vec::new();
+    let mut indice: FxHashMap<[i64; N], i64> = FxHashMap::default();
+    let mut codigos = buffers::I64.tomar(n);
+    for i in 0..n {
+        let t: [i64; N] = std::array::from_fn(|k| cols[k].get(i));
+        let c = *indice.entry(t).or_insert_with(|| { tuplas.push(t); tuplas.len() as i64 - 1 });
+        if tuplas.len() > MAX_TUPLAS {
+            buffers::I64.devolver(codigos);
+            return false;
+        }
+        codigos.push(c);
+    }
+    let mut codigos = ColI::I64(codigos);
+    codigos.compactar();
+    let antes: usize = cols.iter().map(|c| c.bytes()).sum();
+    if codigos.bytes() + tuplas.len() * N * 8 >= antes { return false; }
+
+    let codigos = Arc::new(codigos);
+    for (k, c) in cols.into_iter().enumerate() {
+        let valores = tuplas.iter().map(|t| t[k]).collect();
+        std::mem::replace(c, ColI::Dic(Diccionario { codigos: Arc::clone(&codigos), valores })).reciclar();
+    }
+    true
+}
+
+impl ColF {
+    #[inline(always)]
+    pub(crate) fn get(&self, i: usize) -> f64 {
+        match self {
+            ColF::F32(v) => v[i] as f64,
+            ColF::F64(v) => v[i],
+        }
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        match self { ColF::F32(v) => v.capacity() * 4, ColF::F64(v) => v.capacity() * 8 }
+    }
+
+    pub(crate) fn permutar(&mut self, orden: &[u32]) {
+        match self {
+            ColF::F64(col) => {
+                let mut nuevo = buffers::F64.tomar(orden.len());
+                nuevo.extend(orden.iter().map(|&i| col[i as usize]));
+                buffers::F64.devolver(std::mem::replace(col, nuevo));
+            }
+            ColF::F32(col) => *col = orden.iter().map(|&i| col[i as usize]).collect(),
+        }
+    }
+
+    /// Coordenadas a f32 (NaN se conserva); el buffer f64 vuelve al pool.
+    pub(crate) fn compactar(&mut self) {
+        let ColF::F64(v) = self else { return };
+        let nuevo = ColF::F32(v.iter().map(|&x| x as f32).collect());
+        if let ColF::F64(v) = std::mem::replace(self, nuevo) { buffers::F64.devolver(v); }
+    }
+}
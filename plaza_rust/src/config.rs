@@ -0,0 +1,736 @@
+// ==============================================================================
+// plaza_rust/src/config.rs
+//
+// Configuración del motor desde un documento TOML:
+//
+//   [cache]
+//   max_periodos      = 24
+//   max_resultados    = 200
+//   politica_eviccion = "lru"        # lru | lfu | fifo
+//   claves_calendario = true         # periodo_key = año*100+mes; false = claves libres
+//   timeout_lock_ms   = 0            # espera máxima por un lock de cache; 0 = sin límite
+//   comprimir_resultados = 1000      # estados desde los que un resultado se guarda con zstd; 0 = nunca
+//   ttl_resultados_s  = 0            # edad desde la que un resultado se recalcula; 0 = nunca vence
+//   revalidar_en_fondo = false       # servir el vencido y recalcular en fondo (revalidacion.rs)
+//
+//   [motor]
+//   hilos = 8                        # 0 = pool global de Rayon
+//   umbral_secuencial = 50000        # filas; por debajo no se usa Rayon
+//   compacto = false                 # columnas al tipo más chico (ver columna.rs)
+//   diccionario = false              # métricas como códigos a tuplas repetidas (columna.rs)
+//   validacion = "apagada"           # apagada | registrar | error (ver validacion.rs)
+//
+//   [columnas]                       # nombre canónico → aliases en el parquet
+//   cn_total = ["cn_total", "CN_Tot_Acum"]
+//
+// Además de [columnas] hay aliases registrados en tiempo de ejecución
+// (registrar_alias_columna): se agregan al final de la lista de su columna y
+// siguen ahí aunque se vuelva a cargar la configuración. Una carga puede
+// traer los suyos (cargar_periodo_parquet(..., aliases=...)), que se
+// prueban antes que los demás solo para ese parquet.
+//
+//   [lector]                         # reader de Parquet
+//   batch_size = 65536               # filas por RecordBatch
+//   page_index = false               # leer el page index si el archivo lo trae
+//   situacion_bitmask = false        # situación entera como máscara: bit k = situación k
+//
+//   [situaciones]                    # situación en texto → id (sin acentos/mayúsculas)
+//   ACTIVA = 1
+//   BAJA   = 2
+//
+//   [negativos]                      # por métrica: recortar | incluir | error
+//   cn_total = "incluir"             # default recortar (max(x, 0))
+//
+//   [limites]                        # cargas de periodos; 0 = sin límite
+//   cargas_por_segundo  = 0          # ráfaga de N y luego N por segundo
+//   cargas_concurrentes = 0          # cargas en curso a la vez
+//
+//   [salida]
+//   nombres = "es"                   # claves de métricas: es (cn_total) | en (completions_total)
+//
+//   [salud]                          # umbrales de healthcheck()
+//   timeout_lock_ms   = 200
+//   memoria_max_mb    = 6144          # 0 = sin límite
+//   errores_max       = 10
+//   ventana_errores_s = 300
+//
+// Solo se sobreescriben las claves presentes; lo demás conserva su valor.
+// ==============================================================================
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use pyo3::prelude::*;
+use serde::Deserialize;
+
+// ---------------------------------------------------------------------------
+// Columnas canónicas y sus aliases por defecto (esquema NUEVO + LEGACY)
+// ---------------------------------------------------------------------------
+pub(crate) const COLUMNAS_DEFAULT: [(&str, &[&str]); 11] = [
+    ("lat",        &["lat", "Latitud"]),
+    ("lng",        &["lng", "Longitud"]),
+    ("estado_id",  &["estado_id", "Clave_Edo"]),
+    ("situacion",  &["situacion", "Situación", "Situacion"]),
+    ("inc_total",  &["inc_total", "Inc_Total"]),
+    ("aten_total", &["aten_total", "Aten_Total"]),
+    ("cn_total",   &["cn_total", "CN_Tot_Acum", "CN_Total_Acumulado"]),
+    ("cn_inicial", &["cn_inicial", "CN_Inicial_Acum"]),
+    ("cn_prim",    &["cn_prim", "CN_Prim_Acum"]),
+    ("cn_sec",     &["cn_sec", "CN_Sec_Acum"]),
+    ("plaza_id",   &["plaza_id", "CURP_Plaza"]),
+];
+
+// Métricas del acumulador (posiciones 1..7) por nombre canónico
+pub(crate) const METRICAS_COLUMNA: [&str; 6] = [
+    "inc_total", "aten_total", "cn_total", "cn_inicial", "cn_prim", "cn_sec",
+];
+
+/// Qué hacer con valores negativos de una métrica al agregar.
+///   Recortar: aportan 0 (comportamiento histórico)
+///   Incluir:  se suman tal cual (filas de ajuste)
+///   Error:    la agregación falla informando cuántos hay
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Negativos {
+    Recortar = 0,
+    Incluir  = 1,
+    Error    = 2,
+}
+
+impl Negativos {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "recortar" => Ok(Self::Recortar),
+            "incluir"  => Ok(Self::Incluir),
+            "error"    => Ok(Self::Error),
+            otro       => Err(format!("política de negativos desconocida: {otro:?} (recortar|incluir|error)")),
+        }
+    }
+
+    pub(crate) fn nombre(self) -> &'static str {
+        match self {
+            Self::Recortar => "recortar",
+            Self::Incluir  => "incluir",
+            Self::Error    => "error",
+        }
+    }
+
+    fn desde_u8(v: u8) -> Self {
+        match v {
+            1 => Self::Incluir,
+            2 => Self::Error,
+            _ => Self::Recortar,
+        }
+    }
+}
+
+/// Recálculo de control de cada agregación (validacion.rs).
+///   Apagada:   no se revisa
+///   Registrar: las violaciones se anotan y el resultado se devuelve igual
+///   Error:     además la agregación falla con la primera violación
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Validacion {
+    Apagada,
+    Registrar,
+    Error,
+}
+
+impl Validacion {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "apagada"   => Ok(Self::Apagada),
+            "registrar" => Ok(Self::Registrar),
+            "error"     => Ok(Self::Error),
+            otro        => Err(format!("validacion desconocida: {otro:?} (apagada|registrar|error)")),
+        }
+    }
+
+    fn desde_u8(v: u8) -> Self {
+        match v {
+            1 => Self::Registrar,
+            2 => Self::Error,
+            _ => Self::Apagada,
+        }
+    }
+}
+
+/// Esquema de nombres de las claves de métricas en las respuestas
+/// (claves_salida() en lib.rs). Las funciones que devuelven métricas
+/// aceptan además `nombres=` por llamada.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Nombres {
+    Es = 0,
+    En = 1,
+}
+
+impl Nombres {
+    pub(crate) fn parse(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "es" => Ok(Self::Es),
+            "en" => Ok(Self::En),
+            otro => Err(format!("nombres desconocido: {otro:?} (es|en)")),
+        }
+    }
+
+    fn desde_u8(v: u8) -> Self {
+        if v == 1 { Self::En } else { Self::Es }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum PoliticaEviccion {
+    Lru,
+    Lfu,
+    Fifo,
+}
+
+impl PoliticaEviccion {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "lru"  => Ok(Self::Lru),
+            "lfu"  => Ok(Self::Lfu),
+            "fifo" => Ok(Self::Fifo),
+            otro   => Err(format!("politica_eviccion desconocida: {otro:?} (lru|lfu|fifo)")),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Config {
+    pub max_periodos:      usize,
+    pub max_resultados:    usize,
+    pub hilos:             usize,
+    pub umbral_secuencial: usize,
+    pub compacto:          bool,
+    pub diccionario:       bool,
+    pub validacion:        Validacion,
+    pub nombres:           Nombres,
+    pub politica_eviccion: PoliticaEviccion,
+    pub claves_calendario: bool,
+    pub timeout_lock_ms:   u64,
+    pub comprimir_resultados: usize,
+    pub ttl_resultados_s:  u64,
+    pub revalidar_en_fondo: bool,
+    pub columnas:          HashMap<String, Vec<String>>,
+    pub negativos:         [Negativos; 6],
+    pub situaciones:       HashMap<String, i64>,
+    pub lector:            Lector,
+    pub limites:           Limites,
+    pub salud:             Salud,
+}
+
+#[derive(Clone)]
+pub(crate) struct Lector {
+    pub batch_size: usize,
+    pub page_index: bool,
+    pub situacion_bitmask: bool,
+}
+
+#[derive(Clone)]
+pub(crate) struct Limites {
+    pub cargas_por_segundo:  u32,
+    pub cargas_concurrentes: usize,
+}
+
+#[derive(Clone)]
+pub(crate) struct Salud {
+    pub timeout_lock_ms:   u64,
+    pub memoria_max_mb:    u64,
+    pub errores_max:       u64,
+    pub ventana_errores_s: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_periodos:      crate::MAX_PERIODOS,
+            max_resultados:    crate::MAX_RESULTADOS,
+            hilos:             0,
+            umbral_secuencial: UMBRAL_SECUENCIAL_DEFAULT,
+            compacto:          false,
+            diccionario:       false,
+            validacion:        Validacion::Apagada,
+            nombres:           Nombres::Es,
+            politica_eviccion: PoliticaEviccion::Lru,
+            claves_calendario: true,
+            timeout_lock_ms:   0,
+            comprimir_resultados: 1_000,
+            ttl_resultados_s:  0,
+            revalidar_en_fondo: false,
+            columnas: COLUMNAS_DEFAULT.iter()
+                .map(|(c, a)| (c.to_string(), a.iter().map(|s| s.to_string()).collect()))
+                .collect(),
+            negativos: [Negativos::Recortar; 6],
+            situaciones: HashMap::new(),
+            lector: Lector {
+                batch_size: 65_536,
+                page_index: false,
+                situacion_bitmask: false,
+            },
+            limites: Limites {
+                cargas_por_segundo:  0,
+                cargas_concurrentes: 0,
+            },
+            salud: Salud {
+                timeout_lock_ms:   200,
+                memoria_max_mb:    0,
+                errores_max:       10,
+                ventana_errores_s: 300,
+            },
+        }
+    }
+}
+
+impl Config {
+    /// Aliases configurados para una columna canónica (vacío si no existe).
+    pub fn alias(&self, canonica: &str) -> &[String] {
+        self.columnas.get(canonica).map_or(&[], |v| v.as_slice())
+    }
+
+    /// Pone `aliases` por delante de los de cada columna (override de una
+    /// carga); validar con validar_aliases() antes.
+    pub fn anteponer_aliases(&mut self, aliases: &HashMap<String, Vec<String>>) {
+        for (canonica, extra) in aliases {
+            let Some(lista) = self.columnas.get_mut(canonica) else { continue };
+            lista.retain(|a| !extra.contains(a));
+            lista.splice(0..0, extra.iter().cloned());
+        }
+    }
+
+    fn agregar_registrados(&mut self, registro: &BTreeMap<String, Vec<String>>) {
+        for (canonica, extra) in registro {
+            let Some(lista) = self.columnas.get_mut(canonica) else { continue };
+            for a in extra {
+                if !lista.contains(a) { lista.push(a.clone()); }
+            }
+        }
+    }
+}
+
+/// Nombres canónicos conocidos y listas no vacías.
+pub(crate) fn validar_aliases(aliases: &HashMap<String, Vec<String>>) -> Result<(), String> {
+    for (canonica, lista) in aliases {
+        if !COLUMNAS_DEFAULT.iter().any(|(c, _)| c == canonica) {
+            return Err(format!("columna canónica desconocida: {canonica:?}"));
+        }
+        if lista.is_empty() {
+            return Err(format!("columnas.{canonica}: la lista de aliases está vacía"));
+        }
+    }
+    Ok(())
+}
+
+// Arc: actual() reparte la misma instancia; los cambios la reemplazan o la
+// copian al escribir (Arc::make_mut)
+static CONFIG: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+// canónica → aliases registrados, en orden de registro
+static REGISTRO_ALIASES: RwLock<BTreeMap<String, Vec<String>>> = RwLock::new(BTreeMap::new());
+static POOL:   RwLock<Option<Arc<rayon::ThreadPool>>> = RwLock::new(None);
+
+// Con pocas filas el costo fijo de Rayon (salto al pool, splits, join)
+// supera al trabajo. Copia atómica para no clonar Config en cada consulta.
+const UMBRAL_SECUENCIAL_DEFAULT: usize = 50_000;
+static UMBRAL_SECUENCIAL: AtomicUsize = AtomicUsize::new(UMBRAL_SECUENCIAL_DEFAULT);
+
+// Políticas de negativos: agregar() las lee en cada llamada
+static NEGATIVOS: [AtomicU8; 6] = [const { AtomicU8::new(Negativos::Recortar as u8) }; 6];
+
+// Se valida en cada entrada que recibe un periodo_key
+static CLAVES_CALENDARIO: AtomicBool = AtomicBool::new(true);
+
+/// `true` si los periodo_key deben ser año*100+mes.
+pub(crate) fn claves_calendario() -> bool {
+    CLAVES_CALENDARIO.load(Ordering::Relaxed)
+}
+
+// agregar() la lee en cada llamada
+static VALIDACION: AtomicU8 = AtomicU8::new(Validacion::Apagada as u8);
+
+/// Modo vigente de validación de agregados.
+pub(crate) fn validacion() -> Validacion {
+    Validacion::desde_u8(VALIDACION.load(Ordering::Relaxed))
+}
+
+// Se lee en cada respuesta con métricas
+static NOMBRES: AtomicU8 = AtomicU8::new(Nombres::Es as u8);
+
+/// Esquema de nombres vigente ([salida] nombres).
+pub(crate) fn nombres() -> Nombres {
+    Nombres::desde_u8(NOMBRES.load(Ordering::Relaxed))
+}
+
+// comparar() los lee en cada hit
+static TTL_RESULTADOS_S: AtomicU64 = AtomicU64::new(0);
+static REVALIDAR_EN_FONDO: AtomicBool = AtomicBool::new(false);
+
+/// Edad en segundos desde la que un resultado vence (0 = nunca).
+pub(crate) fn ttl_resultados_s() -> u64 {
+    TTL_RESULTADOS_S.load(Ordering::Relaxed)
+}
+
+/// `true` si un resultado vencido se sirve mientras se recalcula en fondo.
+pub(crate) fn revalidar_en_fondo() -> bool {
+    REVALIDAR_EN_FONDO.load(Ordering::Relaxed)
+}
+
+// Sube con cada cambio de configuración o de aliases registrados: una carga
+// repetida con otra generación se vuelve a parsear (cargar_periodo_con)
+static GENERACION: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn generacion() -> u64 {
+    GENERACION.load(Ordering::Relaxed)
+}
+
+// cerrojos.rs lo lee en cada adquisición; 0 = sin límite
+static TIMEOUT_LOCK_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Espera máxima por un lock de cache (None = bloquear sin límite).
+pub(crate) fn timeout_lock() -> Option<Duration> {
+    match TIMEOUT_LOCK_MS.load(Ordering::Relaxed) {
+        0  => None,
+        ms => Some(Duration::from_millis(ms)),
+    }
+}
+
+/// Política vigente de cada métrica, en el orden de METRICAS_COLUMNA.
+pub(crate) fn negativos() -> [Negativos; 6] {
+    std::array::from_fn(|k| Negativos::desde_u8(NEGATIVOS[k].load(Ordering::Relaxed)))
+}
+
+/// `true` si `n` elementos se procesan mejor en el hilo actual (siempre en
+/// una comparación de prioridad baja: prioridad.rs).
+pub(crate) fn secuencial(n: usize) -> bool {
+    n < UMBRAL_SECUENCIAL.load(Ordering::Relaxed) || crate::prioridad::en_fondo()
+}
+
+/// Configuración vigente, compartida: no copia la struct (que arrastra
+/// aliases, límites y tablas). Quien necesite modificarla para una carga usa
+/// Arc::make_mut sobre su propio Arc.
+pub(crate) fn actual() -> Arc<Config> {
+    CONFIG.read().ok()
+        .and_then(|g| g.clone())
+        .unwrap_or_default()
+}
+
+/// Ejecuta `f` dentro del pool dedicado si se configuró `hilos`, o en el
+/// pool global de Rayon en caso contrario.
+pub(crate) fn en_pool<R: Send>(f: impl FnOnce() -> R + Send) -> R {
+    let pool = POOL.read().ok().and_then(|g| g.clone());
+    match pool {
+        Some(p) => p.install(f),
+        None    => f(),
+    }
+}
+
+/// `en_pool` solo si `n` supera el umbral; si no, `f` corre en este hilo.
+pub(crate) fn en_pool_si<R: Send>(n: usize, f: impl FnOnce() -> R + Send) -> R {
+    if secuencial(n) { f() } else { en_pool(f) }
+}
+
+/// Como `en_pool` pero sin esperar el resultado (fire-and-forget).
+pub(crate) fn spawn_en_pool(f: impl FnOnce() + Send + 'static) {
+    let pool = POOL.read().ok().and_then(|g| g.clone());
+    match pool {
+        Some(p) => p.spawn(f),
+        None    => rayon::spawn(f),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Documento TOML
+// ---------------------------------------------------------------------------
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Documento {
+    #[serde(default)]
+    cache:    SeccionCache,
+    #[serde(default)]
+    motor:    SeccionMotor,
+    #[serde(default)]
+    columnas: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    situaciones: HashMap<String, i64>,
+    #[serde(default)]
+    negativos: HashMap<String, String>,
+    #[serde(default)]
+    lector:   SeccionLector,
+    #[serde(default)]
+    limites:  SeccionLimites,
+    #[serde(default)]
+    salida:   SeccionSalida,
+    #[serde(default)]
+    salud:    SeccionSalud,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SeccionCache {
+    max_periodos:      Option<usize>,
+    max_resultados:    Option<usize>,
+    politica_eviccion: Option<String>,
+    claves_calendario: Option<bool>,
+    timeout_lock_ms:   Option<u64>,
+    comprimir_resultados: Option<usize>,
+    ttl_resultados_s:  Option<u64>,
+    revalidar_en_fondo: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SeccionMotor {
+    hilos:             Option<usize>,
+    umbral_secuencial: Option<usize>,
+    compacto:          Option<bool>,
+    diccionario:       Option<bool>,
+    validacion:        Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SeccionLector {
+    batch_size: Option<usize>,
+    page_index: Option<bool>,
+    situacion_bitmask: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SeccionLimites {
+    cargas_por_segundo:  Option<u32>,
+    cargas_concurrentes: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SeccionSalida {
+    nombres: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct SeccionSalud {
+    timeout_lock_ms:   Option<u64>,
+    memoria_max_mb:    Option<u64>,
+    errores_max:       Option<u64>,
+    ventana_errores_s: Option<u64>,
+}
+
+fn aplicar(doc: Documento, cfg: &mut Config) -> Result<(), String> {
+    if let Some(n) = doc.cache.max_periodos {
+        if n == 0 { return Err("cache.max_periodos debe ser > 0".into()); }
+        cfg.max_periodos = n;
+    }
+    if let Some(n) = doc.cache.max_resultados {
+        if n == 0 { return Err("cache.max_resultados debe ser > 0".into()); }
+        cfg.max_resultados = n;
+    }
+    if let Some(p) = doc.cache.politica_eviccion {
+        cfg.politica_eviccion = PoliticaEviccion::parse(&p)?;
+    }
+    if let Some(b) = doc.cache.claves_calendario {
+        cfg.claves_calendario = b;
+    }
+    if let Some(ms) = doc.cache.timeout_lock_ms {
+        cfg.timeout_lock_ms = ms;
+    }
+    if let Some(n) = doc.cache.comprimir_resultados {
+        cfg.comprimir_resultados = n;
+    }
+    if let Some(s) = doc.cache.ttl_resultados_s {
+        cfg.ttl_resultados_s = s;
+    }
+    if let Some(b) = doc.cache.revalidar_en_fondo {
+        cfg.revalidar_en_fondo = b;
+    }
+    if let Some(h) = doc.motor.hilos {
+        cfg.hilos = h;
+    }
+    if let Some(u) = doc.motor.umbral_secuencial {
+        cfg.umbral_secuencial = u;
+    }
+    if let Some(c) = doc.motor.compacto {
+        cfg.compacto = c;
+    }
+    if let Some(d) = doc.motor.diccionario {
+        cfg.diccionario = d;
+    }
+    if let Some(v) = doc.motor.validacion {
+        cfg.validacion = Validacion::parse(&v)?;
+    }
+    if let Some(n) = doc.lector.batch_size {
+        if n == 0 { return Err("lector.batch_size debe ser > 0".into()); }
+        cfg.lector.batch_size = n;
+    }
+    if let Some(b) = doc.lector.page_index {
+        cfg.lector.page_index = b;
+    }
+    if let Some(b) = doc.lector.situacion_bitmask {
+        cfg.lector.situacion_bitmask = b;
+    }
+    if let Some(v) = doc.limites.cargas_por_segundo  { cfg.limites.cargas_por_segundo = v; }
+    if let Some(v) = doc.limites.cargas_concurrentes { cfg.limites.cargas_concurrentes = v; }
+    if let Some(n) = doc.salida.nombres {
+        cfg.nombres = Nombres::parse(&n)?;
+    }
+    let s = doc.salud;
+    if let Some(v) = s.timeout_lock_ms   { cfg.salud.timeout_lock_ms = v; }
+    if let Some(v) = s.memoria_max_mb    { cfg.salud.memoria_max_mb = v; }
+    if let Some(v) = s.errores_max       { cfg.salud.errores_max = v; }
+    if let Some(v) = s.ventana_errores_s { cfg.salud.ventana_errores_s = v; }
+    for (texto, id) in doc.situaciones {
+        if id < 0 {
+            return Err(format!("situaciones.{texto}: el id debe ser >= 0 (los negativos significan \"sin filtro\")"));
+        }
+        cfg.situaciones.insert(texto, id);
+    }
+    for (metrica, politica) in doc.negativos {
+        let k = METRICAS_COLUMNA.iter().position(|&m| m == metrica)
+            .ok_or_else(|| format!("negativos: métrica desconocida {metrica:?}"))?;
+        cfg.negativos[k] = Negativos::parse(&politica)?;
+    }
+    validar_aliases(&doc.columnas)?;
+    let recargadas = !doc.columnas.is_empty();
+    cfg.columnas.extend(doc.columnas);
+    // Una lista de [columnas] reemplaza la anterior: los registrados vuelven al final
+    if recargadas {
+        let registro = REGISTRO_ALIASES.read().map_err(|e| format!("RwLock: {e}"))?;
+        cfg.agregar_registrados(&registro);
+    }
+    Ok(())
+}
+
+/// Parsea y aplica un documento TOML sobre la configuración vigente.
+/// Errores: `Err(msg)` listo para mostrar (sintaxis, claves o valores inválidos).
+pub(crate) fn aplicar_toml(texto: &str) -> Result<(), String> {
+    let doc: Documento = toml::from_str(texto).map_err(|e| format!("config: {e}"))?;
+
+    let mut cfg = Config::clone(&actual());
+    let hilos_antes = cfg.hilos;
+    let negativos_antes = cfg.negativos;
+    aplicar(doc, &mut cfg)?;
+
+    if cfg.hilos != hilos_antes {
+        let pool = if cfg.hilos == 0 { None } else {
+            let p = rayon::ThreadPoolBuilder::new()
+                .num_threads(cfg.hilos)
+                .thread_name(|i| format!("plaza-rust-{i}"))
+                .build()
+                .map_err(|e| format!("pool: {e}"))?;
+            Some(Arc::new(p))
+        };
+        *POOL.write().map_err(|e| format!("RwLock: {e}"))? = pool;
+    }
+
+    UMBRAL_SECUENCIAL.store(cfg.umbral_secuencial, Ordering::Relaxed);
+    CLAVES_CALENDARIO.store(cfg.claves_calendario, Ordering::Relaxed);
+    TIMEOUT_LOCK_MS.store(cfg.timeout_lock_ms, Ordering::Relaxed);
+    VALIDACION.store(cfg.validacion as u8, Ordering::Relaxed);
+    NOMBRES.store(cfg.nombres as u8, Ordering::Relaxed);
+    TTL_RESULTADOS_S.store(cfg.ttl_resultados_s, Ordering::Relaxed);
+    REVALIDAR_EN_FONDO.store(cfg.revalidar_en_fondo, Ordering::Relaxed);
+    for (a, p) in NEGATIVOS.iter().zip(cfg.negativos) {
+        a.store(p as u8, Ordering::Relaxed);
+    }
+    let invalidar = cfg.negativos != negativos_antes;
+    *CONFIG.write().map_err(|e| format!("RwLock: {e}"))? = Some(Arc::new(cfg));
+    GENERACION.fetch_add(1, Ordering::Relaxed);
+    // Los resultados cacheados se calcularon con la política anterior
+    if invalidar {
+        crate::vaciar_resultados()?;
+        crate::particion::vaciar()?;
+    }
+    Ok(())
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// Registra `alias` para la columna `canonica` en todas las cargas que
+/// siguen. Devuelve False si ya estaba registrado.
+#[pyfunction]
+#[pyo3(signature = (canonica, alias, request_id = None))]
+pub(crate) fn registrar_alias_columna(canonica: &str, alias: &str, request_id: Option<&str>) -> PyResult<bool> {
+    let alias = alias.trim();
+    if alias.is_empty() {
+        return Err(pyo3::exceptions::PyValueError::new_err("alias vacío"));
+    }
+    let params = format!("canonica={canonica} alias={alias}");
+    crate::bitacora::auditar("alias_columna", params, request_id, || {
+        let una = HashMap::from([(canonica.to_string(), vec![alias.to_string()])]);
+        validar_aliases(&una).map_err(pyo3::exceptions::PyValueError::new_err)?;
+        let err = |e: String| pyo3::exceptions::PyRuntimeError::new_err(e);
+        let mut registro = REGISTRO_ALIASES.write().map_err(|e| err(format!("RwLock: {e}")))?;
+        let lista = registro.entry(canonica.to_string()).or_default();
+        if lista.iter().any(|a| a == alias) { return Ok(false); }
+        lista.push(alias.to_string());
+        let mut cfg = CONFIG.write().map_err(|e| err(format!("RwLock: {e}")))?;
+        Arc::make_mut(cfg.get_or_insert_with(Arc::default)).agregar_registrados(&registro);
+        GENERACION.fetch_add(1, Ordering::Relaxed);
+        Ok(true)
+    })
+}
+
+/// Quita un alias registrado de la lista vigente (si además es de fábrica
+/// se conserva); devuelve False si no estaba registrado.
+#[pyfunction]
+#[pyo3(signature = (canonica, alias, request_id = None))]
+pub(crate) fn quitar_alias_columna(canonica: &str, alias: &str, request_id: Option<&str>) -> PyResult<bool> {
+    let alias = alias.trim();
+    let params = format!("canonica={canonica} alias={alias} quitar=true");
+    crate::bitacora::auditar("alias_columna", params, request_id, || {
+        let err = |e: String| pyo3::exceptions::PyRuntimeError::new_err(e);
+        let mut registro = REGISTRO_ALIASES.write().map_err(|e| err(format!("RwLock: {e}")))?;
+        let Some(lista) = registro.get_mut(canonica) else { return Ok(false) };
+        let Some(i) = lista.iter().position(|a| a == alias) else { return Ok(false) };
+        lista.remove(i);
+        if lista.is_empty() { registro.remove(canonica); }
+        let mut cfg = CONFIG.write().map_err(|e| err(format!("RwLock: {e}")))?;
+        if let Some(lista) = cfg.as_mut().and_then(|c| Arc::make_mut(c).columnas.get_mut(canonica)) {
+            let de_base = COLUMNAS_DEFAULT.iter()
+                .any(|(c, a)| *c == canonica && a.contains(&alias));
+            if !de_base { lista.retain(|a| a != alias); }
+        }
+        GENERACION.fetch_add(1, Ordering::Relaxed);
+        Ok(true)
+    })
+}
+
+/// {"columnas": {canónica: aliases vigentes, en el orden en que se prueban},
+/// "registrados": {canónica: aliases registrados}}.
+#[pyfunction]
+pub(crate) fn aliases_columnas(py: Python<'_>) -> PyResult<Bound<'_, pyo3::types::PyDict>> {
+    let cfg = actual();
+    let registro = REGISTRO_ALIASES.read()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
+    let columnas = pyo3::types::PyDict::new(py);
+    for (c, _) in COLUMNAS_DEFAULT {
+        columnas.set_item(c, cfg.alias(c))?;
+    }
+    let out = pyo3::types::PyDict::new(py);
+    out.set_item("columnas", columnas)?;
+    out.set_item("registrados", &*registro)?;
+    Ok(out)
+}
+
+/// Acepta una ruta a un archivo .toml o el documento TOML como texto.
+#[pyfunction]
+#[pyo3(signature = (path_or_str, request_id = None))]
+pub(crate) fn cargar_configuracion(path_or_str: &str, request_id: Option<&str>) -> PyResult<()> {
+    let es_archivo = std::path::Path::new(path_or_str).is_file();
+    let params = if es_archivo {
+        format!("archivo={path_or_str}")
+    } else {
+        format!("texto={} bytes", path_or_str.len())
+    };
+    crate::bitacora::auditar("configuracion", params, request_id, || {
+        let texto = if es_archivo {
+            std::fs::read_to_string(path_or_str)
+                .map_err(|e| pyo3::exceptions::PyOSError::new_err(format!("config: {e}")))?
+        } else {
+            path_or_str.to_string()
+        };
+        aplicar_toml(&texto).map_err(pyo3::exceptions::PyValueError::new_err)
+    })
+}
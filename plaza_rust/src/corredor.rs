@@ -0,0 +1,121 @@
+// ==============================================================================
+// plaza_rust/src/corredor.rs
+//
+// Plazas a lo largo de una ruta (carretera, recorrido de supervisión) para la
+// herramienta de logística de campo: las que quedan a <= buffer_km de una
+// polilínea [(lat, lng), ...].
+//
+// La distancia a cada tramo es sobre la esfera: cross-track si la proyección
+// cae dentro del tramo, si no la distancia al extremo más cercano. Cada
+// plaza se reporta con su posición sobre la ruta (km desde el primer punto
+// hasta su proyección), y se devuelven en ese orden.
+// ==============================================================================
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{clave, config, haversine, periodo, rumbo, CajaRadio, EngineData};
+
+const R_KM: f64 = 6_371.0;
+
+struct Tramo {
+    a:      (f64, f64),
+    b:      (f64, f64),
+    largo:  f64,
+    // km de ruta hasta `a`
+    inicio: f64,
+    // todo punto a <= buffer del tramo está a <= buffer + largo de `a`
+    caja:   CajaRadio,
+}
+
+impl Tramo {
+    /// (distancia km al tramo, km desde `a` hasta la proyección).
+    fn distancia(&self, (la, lo): (f64, f64)) -> (f64, f64) {
+        let ((la1, lo1), (la2, lo2)) = (self.a, self.b);
+        let d13 = haversine(la1, lo1, la, lo);
+        if self.largo == 0.0 { return (d13, 0.0); }
+        let dt = rumbo(la1, lo1, la, lo) - rumbo(la1, lo1, la2, lo2);
+        let xt = ((d13 / R_KM).sin() * dt.sin()).clamp(-1.0, 1.0).asin();
+        let at = ((d13 / R_KM).cos() / xt.cos()).clamp(-1.0, 1.0).acos() * R_KM * dt.cos().signum();
+        if at <= 0.0 { return (d13, 0.0); }
+        if at >= self.largo { return (haversine(la2, lo2, la, lo), self.largo); }
+        (xt.abs() * R_KM, at)
+    }
+}
+
+fn tramos(polyline: &[(f64, f64)], buffer_km: f64) -> Vec<Tramo> {
+    let mut inicio = 0.0;
+    polyline.windows(2).map(|w| {
+        let (a, b) = (w[0], w[1]);
+        let largo = haversine(a.0, a.1, b.0, b.1);
+        let t = Tramo { a, b, largo, inicio, caja: CajaRadio::new(a.0, a.1, buffer_km + largo) };
+        inicio += largo;
+        t
+    }).collect()
+}
+
+/// (fila original, km al corredor, km de ruta) de las filas dentro del buffer.
+fn en_corredor(eng: &EngineData, tramos: &[Tramo], buffer_km: f64, filtro: i64) -> Vec<(usize, f64, f64)> {
+    let por_fila = |i: usize| {
+        if filtro >= 0 && !eng.tiene_situacion(i, filtro) { return None; }
+        let p = (eng.lats.get(i), eng.lngs.get(i));
+        // el primer tramo a la distancia mínima
+        tramos.iter()
+            .filter(|t| t.caja.contiene(p.0, p.1))
+            .map(|t| { let (d, at) = t.distancia(p); (d, t.inicio + at) })
+            .filter(|&(d, _)| d <= buffer_km)
+            .reduce(|m, x| if x.0 < m.0 { x } else { m })
+            .map(|(d, km)| (eng.fila_original(i), d, km))
+    };
+    let mut res: Vec<(usize, f64, f64)> = if config::secuencial(eng.n.saturating_mul(tramos.len())) {
+        (0..eng.n).filter_map(por_fila).collect()
+    } else {
+        config::en_pool(|| (0..eng.n).into_par_iter().filter_map(por_fila).collect())
+    };
+    res.sort_unstable_by(|a, b| a.2.total_cmp(&b.2).then(a.0.cmp(&b.0)));
+    res
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"indices", "distancias_km", "km_ruta"}: filas (orden del parquet) a
+/// <= buffer_km de `polyline` con la situación `filtro` (< 0 = todas),
+/// ordenadas por km_ruta. Un solo punto equivale a un radio.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, polyline, buffer_km, filtro = -1))]
+pub(crate) fn filtrar_por_corredor<'py>(
+    py:          Python<'py>,
+    periodo_key: ArgClave,
+    polyline:    Vec<(f64, f64)>,
+    buffer_km:   f64,
+    filtro:      i64,
+) -> PyResult<Bound<'py, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    if polyline.is_empty() || polyline.iter().any(|&(la, lo)| !la.is_finite() || !lo.is_finite()) {
+        return Err(PyValueError::new_err("polyline debe tener al menos un punto y sin NaN"));
+    }
+    if !(buffer_km.is_finite() && buffer_km >= 0.0) {
+        return Err(PyValueError::new_err(format!("buffer_km inválido: {buffer_km}")));
+    }
+    // Un punto suelto es un tramo de largo 0
+    let puntos = if polyline.len() == 1 { vec![polyline[0]; 2] } else { polyline };
+    let tramos = tramos(&puntos, buffer_km);
+
+    let res = py.allow_threads(|| -> Result<_, Error> {
+        let eng = periodo(periodo_key)?;
+        eng.tocar();
+        Ok(en_corredor(&eng, &tramos, buffer_km, filtro))
+    })?;
+
+    let out = PyDict::new(py);
+    out.set_item("indices", res.iter().map(|r| r.0).collect::<Vec<_>>())?;
+    out.set_item("distancias_km", res.iter().map(|r| r.1).collect::<Vec<_>>())?;
+    out.set_item("km_ruta", res.iter().map(|r| r.2).collect::<Vec<_>>())?;
+    Ok(out)
+}
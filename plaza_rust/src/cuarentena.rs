@@ -0,0 +1,102 @@
+// ==============================================================================
+// plaza_rust/src/cuarentena.rs
+//
+// Cuarentena de cargas fallidas: cuando cargar_periodo falla se guardan los
+// bytes tal como llegaron y dónde iba el parseo (etapa, esquema del archivo,
+// grupo de filas, columna), para diagnosticar un upload de 300 MB sin
+// reproducirlo. ultimo_error_carga() lo devuelve.
+//
+// Se retienen las últimas CAPACIDAD cargas fallidas (la más vieja sale al
+// entrar una nueva); vaciar_cuarentena() libera los bytes antes. Los bytes
+// son los mismos del objeto que llegó de Python (sin copia), así que
+// retenerlos mantiene vivo ese objeto.
+// ==============================================================================
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict};
+
+use crate::{now_secs, PeriodoKey};
+
+const CAPACIDAD: usize = 3;
+
+/// Hasta dónde llegó el parseo; parse_parquet_contexto() lo va actualizando.
+#[derive(Default)]
+pub(crate) struct Contexto {
+    pub(crate) etapa:   &'static str,
+    // (nombre, tipo arrow) de cada campo del archivo, si se llegó a leer
+    pub(crate) esquema: Vec<(String, String)>,
+    // filas de cada row group, del footer
+    pub(crate) grupos:  Vec<i64>,
+    // primera fila del lote que se estaba leyendo
+    pub(crate) fila:    Option<usize>,
+    pub(crate) columna: Option<String>,
+}
+
+impl Contexto {
+    /// Row group que contiene `fila` (el lote puede seguir en el siguiente).
+    fn grupo_filas(&self) -> Option<usize> {
+        let fila = self.fila? as i64;
+        let mut acumuladas = 0;
+        self.grupos.iter().position(|&n| { acumuladas += n; fila < acumuladas })
+    }
+}
+
+struct Entrada {
+    periodo_key: PeriodoKey,
+    ts:          u64,
+    error:       String,
+    contexto:    Contexto,
+    raw:         Bytes,
+}
+
+static CUARENTENA: Mutex<VecDeque<Entrada>> = Mutex::new(VecDeque::new());
+
+/// Guarda una carga fallida. Envenenado: se pierde la entrada, no el error.
+pub(crate) fn guardar(periodo_key: PeriodoKey, error: String, contexto: Contexto, raw: Bytes) {
+    let Ok(mut c) = CUARENTENA.lock() else { return };
+    if c.len() >= CAPACIDAD { c.pop_front(); }
+    c.push_back(Entrada { periodo_key, ts: now_secs(), error, contexto, raw });
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// La carga fallida más reciente o None: {"periodo_key", "ts", "error",
+/// "etapa", "esquema": [(nombre, tipo)], "grupo_filas", "fila", "columna",
+/// "bytes"}. Con `incluir_bytes`, "raw" trae los bytes recibidos.
+#[pyfunction]
+#[pyo3(signature = (incluir_bytes = false))]
+pub(crate) fn ultimo_error_carga(py: Python<'_>, incluir_bytes: bool) -> PyResult<Option<Bound<'_, PyDict>>> {
+    let c = CUARENTENA.lock()
+        .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("Mutex: CUARENTENA envenenado"))?;
+    let Some(e) = c.back() else { return Ok(None) };
+    let d = PyDict::new(py);
+    d.set_item("periodo_key", e.periodo_key)?;
+    d.set_item("ts", e.ts)?;
+    d.set_item("error", &e.error)?;
+    d.set_item("etapa", e.contexto.etapa)?;
+    d.set_item("esquema", &e.contexto.esquema)?;
+    d.set_item("grupo_filas", e.contexto.grupo_filas())?;
+    d.set_item("fila", e.contexto.fila)?;
+    d.set_item("columna", e.contexto.columna.as_deref())?;
+    d.set_item("bytes", e.raw.len())?;
+    if incluir_bytes {
+        d.set_item("raw", PyBytes::new(py, &e.raw))?;
+    }
+    Ok(Some(d))
+}
+
+/// Descarta la cuarentena y devuelve cuántas cargas tenía.
+#[pyfunction]
+pub(crate) fn vaciar_cuarentena() -> PyResult<usize> {
+    let mut c = CUARENTENA.lock()
+        .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("Mutex: CUARENTENA envenenado"))?;
+    let n = c.len();
+    c.clear();
+    Ok(n)
+}
@@ -0,0 +1,109 @@
+// ==============================================================================
+// plaza_rust/src/demanda.rs
+//
+// Asignación de puntos de demanda a su plaza más cercana (haversine) para el
+// reporte de capacidad vs demanda: por punto, qué plaza lo atiende y a qué
+// distancia; por plaza, cuántos puntos y cuánta demanda le tocaron.
+//
+// Solo participan filas con coordenadas válidas. Con radio_km, un punto sin
+// plazas a <= radio_km queda sin asignar. Empate de distancia: la fila que
+// aparece primero en el parquet.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{clave, config, haversine, periodo, CajaRadio, EngineData};
+
+/// (fila original, distancia km) de la plaza más cercana a `p`.
+fn mas_cercana(eng: &EngineData, (lat, lng): (f64, f64), radio_km: f64) -> Option<(usize, f64)> {
+    let caja = CajaRadio::new(lat, lng, radio_km);
+    let mut mejor: Option<(usize, f64)> = None;
+    for i in 0..eng.n {
+        let (la, lo) = (eng.lats.get(i), eng.lngs.get(i));
+        if !caja.contiene(la, lo) { continue; }
+        let d = haversine(lat, lng, la, lo);
+        if d > radio_km { continue; }
+        let o = eng.fila_original(i);
+        if mejor.is_none_or(|(mo, md)| d < md || (d == md && o < mo)) {
+            mejor = Some((o, d));
+        }
+    }
+    mejor
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"indices": [fila o None por punto], "distancias_km": [km o None],
+/// "por_plaza": {fila: {"puntos", "demanda"}}, "sin_asignar": n}. `pesos`
+/// es la demanda de cada punto (default 1); "por_plaza" solo trae las filas
+/// que recibieron algún punto.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, demand_points, pesos = None, radio_km = None))]
+pub(crate) fn asignar_demanda<'py>(
+    py:            Python<'py>,
+    periodo_key:   ArgClave,
+    demand_points: Vec<(f64, f64)>,
+    pesos:         Option<Vec<f64>>,
+    radio_km:      Option<f64>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    if demand_points.iter().any(|&(la, lo)| la.is_nan() || lo.is_nan()) {
+        return Err(PyValueError::new_err("demand_points no pueden tener NaN"));
+    }
+    if let Some(p) = &pesos {
+        if p.len() != demand_points.len() {
+            return Err(PyValueError::new_err(format!(
+                "pesos con {} valores para {} demand_points", p.len(), demand_points.len()
+            )));
+        }
+        if p.iter().any(|w| !w.is_finite()) {
+            return Err(PyValueError::new_err("pesos deben ser finitos"));
+        }
+    }
+    let radio = radio_km.unwrap_or(f64::INFINITY);
+    if radio.is_nan() || radio < 0.0 {
+        return Err(PyValueError::new_err(format!("radio_km inválido: {radio}")));
+    }
+
+    let asignadas = py.allow_threads(|| -> Result<_, Error> {
+        let eng = periodo(periodo_key)?;
+        eng.tocar();
+        let por_punto = |&p: &(f64, f64)| mas_cercana(&eng, p, radio);
+        Ok(if config::secuencial(eng.n.saturating_mul(demand_points.len())) {
+            demand_points.iter().map(por_punto).collect::<Vec<_>>()
+        } else {
+            config::en_pool(|| demand_points.par_iter().map(por_punto).collect())
+        })
+    })?;
+
+    let mut por_plaza: BTreeMap<usize, (usize, f64)> = BTreeMap::new();
+    for (j, a) in asignadas.iter().enumerate() {
+        let Some((o, _)) = a else { continue };
+        let e = por_plaza.entry(*o).or_default();
+        e.0 += 1;
+        e.1 += pesos.as_ref().map_or(1.0, |p| p[j]);
+    }
+
+    let plazas = PyDict::new(py);
+    for (o, (puntos, demanda)) in por_plaza {
+        let d = PyDict::new(py);
+        d.set_item("puntos", puntos)?;
+        d.set_item("demanda", demanda)?;
+        plazas.set_item(o, d)?;
+    }
+    let out = PyDict::new(py);
+    out.set_item("indices", asignadas.iter().map(|a| a.map(|(o, _)| o)).collect::<Vec<_>>())?;
+    out.set_item("distancias_km", asignadas.iter().map(|a| a.map(|(_, d)| d)).collect::<Vec<_>>())?;
+    out.set_item("por_plaza", plazas)?;
+    out.set_item("sin_asignar", asignadas.iter().filter(|a| a.is_none()).count())?;
+    Ok(out)
+}
@@ -0,0 +1,74 @@
+// ==============================================================================
+// plaza_rust/src/descartes.rs
+//
+// Carga tolerante (cargar_periodo_parquet(..., tolerante=True)): en vez de
+// abortar, parse_parquet_contexto() descarta
+//
+//   - la fila con un valor que no convierte al tipo de su columna (en modo
+//     estricto ese valor queda nulo) o una situación de texto sin id en
+//     [situaciones] (en modo estricto aborta la carga);
+//   - el row group entero que no se puede decodificar.
+//
+// Se cuentan todas las filas descartadas; las muestras (índice en el
+// parquet y motivo) se guardan hasta `limite`.
+// ==============================================================================
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+#[derive(Clone)]
+pub(crate) struct Descartes {
+    limite:   usize,
+    filas:    usize,
+    muestras: Vec<(usize, String)>,
+    // (row group, filas, error)
+    grupos:   Vec<(usize, i64, String)>,
+}
+
+impl Descartes {
+    pub(crate) fn new(limite: usize) -> Self {
+        Descartes { limite, filas: 0, muestras: Vec::new(), grupos: Vec::new() }
+    }
+
+    /// Anota la fila `fila` del parquet; el motivo solo se arma si entra en
+    /// las muestras.
+    pub(crate) fn fila(&mut self, fila: usize, motivo: impl FnOnce() -> String) {
+        self.filas += 1;
+        if self.muestras.len() < self.limite {
+            self.muestras.push((fila, motivo()));
+        }
+    }
+
+    /// Anota un row group descartado completo (sus filas no van a muestras).
+    pub(crate) fn grupo(&mut self, grupo: usize, filas: i64, error: String) {
+        self.filas += filas.max(0) as usize;
+        self.grupos.push((grupo, filas, error));
+    }
+
+    pub(crate) fn filas(&self) -> usize {
+        self.filas
+    }
+
+    /// {"filas_descartadas", "muestras": [(indice, motivo)], "grupos_descartados": [(grupo, filas, error)]}
+    pub(crate) fn a_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let d = PyDict::new(py);
+        d.set_item("filas_descartadas", self.filas)?;
+        d.set_item("muestras", &self.muestras)?;
+        d.set_item("grupos_descartados", &self.grupos)?;
+        Ok(d)
+    }
+}
+
+/// Quita de `v[inicio..]` las posiciones relativas `malas` (ascendentes).
+pub(crate) fn quitar<T>(v: &mut Vec<T>, inicio: usize, malas: &[usize]) {
+    if malas.is_empty() { return; }
+    let mut k = 0;
+    let mut i = 0;
+    v.retain(|_| {
+        let rel = i;
+        i += 1;
+        if rel < inicio { return true; }
+        if malas.get(k) == Some(&(rel - inicio)) { k += 1; return false; }
+        true
+    });
+}
@@ -0,0 +1,176 @@
+// ==============================================================================
+// plaza_rust/src/empaquetado.rs
+//
+// Comparación como blob columnar comprimido con zstd: lo que devuelve
+// comparar_periodos(..., comprimido=True) para mandarlo a otro proceso, y
+// la forma en que RESULT_CACHE guarda las entradas con muchos estados
+// ([cache] comprimir_resultados).
+//
+// Layout del contenido del frame zstd (little-endian):
+//
+//   cabecera (16 bytes)
+//     0   4  magia b"PLZC"
+//     4   1  versión (1)
+//     5   1  flags: bit 0 = periodo2 es periodo1 (no hay segundo bloque)
+//     6   2  m: métricas por estado, u16 (las primeras m de CLAVES)
+//     8   4  n1: estados del periodo1, u32
+//     12  4  n2: estados del periodo2, u32 (0 con el bit 0)
+//   un bloque por periodo, con n = n1 o n2:
+//     n × i64        estado_id, ascendente
+//     m × (n × i64)  una columna por métrica, en el orden de CLAVES
+//
+// Un consumidor en numpy: np.frombuffer(zstd.decompress(blob), "<i8",
+// offset=16) y partir en (1 + m) columnas de n1 y luego de n2.
+//
+// Con binario=True el mismo contenido va sin zstd: en un hit armar el dict
+// bajo el GIL costaba más que la comparación, y los bytes se arman con el
+// GIL suelto. El decodificador de Python es decodificar_comparacion() en
+// app/rust_bridge.py (acepta las dos formas: el frame zstd no empieza con
+// b"PLZC"). Los dos lados se prueban contra el mismo blob de referencia,
+// datos_prueba/comparacion_v1.plzc (tests de abajo y
+// tests/test_rust_bridge.py).
+// ==============================================================================
+
+use crate::{AgrMap, Fila, ANCHO};
+
+const MAGIA: &[u8; 4] = b"PLZC";
+const VERSION: u8 = 1;
+const CABECERA: usize = 16;
+const MISMO_PERIODO: u8 = 1;
+
+// Nivel de zstd: los bloques son chicos y se comprimen en cada miss
+const NIVEL: i32 = 3;
+
+fn bloque(out: &mut Vec<u8>, agr: &AgrMap, m: usize) {
+    let mut filas: Vec<(&i64, &Fila)> = agr.iter().collect();
+    filas.sort_unstable_by_key(|&(&eid, _)| eid);
+    for (eid, _) in &filas {
+        out.extend_from_slice(&eid.to_le_bytes());
+    }
+    for k in 0..m {
+        for (_, v) in &filas {
+            out.extend_from_slice(&v[k].to_le_bytes());
+        }
+    }
+}
+
+/// Contenido sin comprimir de `agr1` y `agr2` (None = mismo periodo) con
+/// las primeras `m` métricas de cada estado.
+pub(crate) fn serializar(agr1: &AgrMap, agr2: Option<&AgrMap>, m: usize) -> Vec<u8> {
+    let m = m.min(ANCHO);
+    let n2 = agr2.map_or(0, |a| a.len());
+    let mut raw = Vec::with_capacity(CABECERA + (agr1.len() + n2) * (m + 1) * 8);
+    raw.extend_from_slice(MAGIA);
+    raw.push(VERSION);
+    raw.push(if agr2.is_none() { MISMO_PERIODO } else { 0 });
+    raw.extend_from_slice(&(m as u16).to_le_bytes());
+    raw.extend_from_slice(&(agr1.len() as u32).to_le_bytes());
+    raw.extend_from_slice(&(n2 as u32).to_le_bytes());
+    bloque(&mut raw, agr1, m);
+    if let Some(a) = agr2 { bloque(&mut raw, a, m); }
+    raw
+}
+
+/// serializar() comprimido con zstd.
+pub(crate) fn empaquetar(agr1: &AgrMap, agr2: Option<&AgrMap>, m: usize) -> Result<Vec<u8>, String> {
+    zstd::bulk::compress(&serializar(agr1, agr2, m), NIVEL).map_err(|e| format!("zstd: {e}"))
+}
+
+fn leer_u32(raw: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(raw[pos..pos + 4].try_into().expect("4 bytes"))
+}
+
+fn leer_bloque(raw: &[u8], pos: &mut usize, n: usize, m: usize) -> Result<AgrMap, String> {
+    let fin = *pos + n * (m + 1) * 8;
+    let datos = raw.get(*pos..fin).ok_or("blob truncado")?;
+    let i64_en = |k: usize| i64::from_le_bytes(datos[k * 8..k * 8 + 8].try_into().expect("8 bytes"));
+    let agr = (0..n).map(|i| {
+        let mut v: Fila = [0; ANCHO];
+        for (k, x) in v.iter_mut().enumerate().take(m) {
+            *x = i64_en((k + 1) * n + i);
+        }
+        (i64_en(i), v)
+    }).collect();
+    *pos = fin;
+    Ok(agr)
+}
+
+/// Inverso de empaquetar(); las métricas que el blob no trae quedan en 0
+/// y las que sobran (más de ANCHO) se ignoran.
+pub(crate) fn desempaquetar(blob: &[u8]) -> Result<(AgrMap, Option<AgrMap>), String> {
+    let raw = zstd::stream::decode_all(blob).map_err(|e| format!("zstd: {e}"))?;
+    if raw.len() < CABECERA || &raw[0..4] != MAGIA {
+        return Err("blob sin cabecera PLZC".into());
+    }
+    if raw[4] != VERSION {
+        return Err(format!("blob versión {} no soportada (se espera {VERSION})", raw[4]));
+    }
+    let mismo = raw[5] & MISMO_PERIODO != 0;
+    let m = u16::from_le_bytes([raw[6], raw[7]]) as usize;
+    let (n1, n2) = (leer_u32(&raw, 8) as usize, leer_u32(&raw, 12) as usize);
+    let mut pos = CABECERA;
+    let agr1 = leer_bloque(&raw, &mut pos, n1, m)?;
+    let agr2 = if mismo { None } else { Some(leer_bloque(&raw, &mut pos, n2, m)?) };
+    Ok((agr1, agr2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config, Lados, ResultadoComp, Resumenes};
+
+    // Blob de referencia que también decodifica tests/test_rust_bridge.py;
+    // PLZC_REGENERAR=1 lo reescribe si cambia el layout (y la versión)
+    const REFERENCIA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/datos_prueba/comparacion_v1.plzc");
+
+    fn fila(base: i64) -> Fila {
+        std::array::from_fn(|k| base * (k as i64 + 1))
+    }
+
+    fn ida_y_vuelta(agr1: &AgrMap, agr2: Option<&AgrMap>) -> (AgrMap, Option<AgrMap>) {
+        desempaquetar(&empaquetar(agr1, agr2, ANCHO).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn vacio() {
+        let vacio = AgrMap::default();
+        assert_eq!(ida_y_vuelta(&vacio, None), (vacio.clone(), None));
+        assert_eq!(ida_y_vuelta(&vacio, Some(&vacio)), (vacio.clone(), Some(vacio.clone())));
+        assert_eq!(serializar(&vacio, None, ANCHO).len(), CABECERA);
+    }
+
+    #[test]
+    fn valores_negativos_y_extremos() {
+        let agr1: AgrMap = [(-1, fila(-7)), (9, fila(3)), (15, [i64::MIN; ANCHO])].into_iter().collect();
+        let agr2: AgrMap = [(9, [i64::MAX; ANCHO]), (32, fila(-1))].into_iter().collect();
+        assert_eq!(ida_y_vuelta(&agr1, Some(&agr2)), (agr1.clone(), Some(agr2)));
+        // Con menos métricas las demás vuelven en 0
+        let (corto, _) = desempaquetar(&empaquetar(&agr1, None, 7).unwrap()).unwrap();
+        assert_eq!(corto[&9][..7], fila(3)[..7]);
+        assert!(corto[&9][7..].iter().all(|&x| x == 0));
+    }
+
+    #[test]
+    fn resultado_sobre_comprimir_resultados() {
+        let n = config::actual().comprimir_resultados.max(1) + 5;
+        let agr1: AgrMap = (0..n as i64).map(|e| (e, fila(e - 500))).collect();
+        let agr2: AgrMap = (0..n as i64).map(|e| (e * 2, fila(-e))).collect();
+        assert_eq!(ida_y_vuelta(&agr1, Some(&agr2)), (agr1.clone(), Some(agr2.clone())));
+        let r = ResultadoComp::nuevo(agr1.clone(), Some(agr2.clone()), Resumenes::default(), 0);
+        assert!(matches!(r.lados, Lados::Comprimidos(_)));
+        assert_eq!(r.lados.agregados().unwrap(), (agr1, Some(agr2)));
+    }
+
+    #[test]
+    fn layout_de_referencia() {
+        // 7 métricas: filas y las seis sumas
+        let agr1: AgrMap = [(9, fila(10)), (-1, fila(-3)), (15, fila(0))].into_iter().collect();
+        let agr2: AgrMap = [(9, fila(-20)), (32, [i64::MIN; ANCHO])].into_iter().collect();
+        let raw = serializar(&agr1, Some(&agr2), 7);
+        if std::env::var_os("PLZC_REGENERAR").is_some() {
+            std::fs::write(REFERENCIA, &raw).unwrap();
+        }
+        assert_eq!(raw, std::fs::read(REFERENCIA).unwrap());
+        assert_eq!(&raw[..8], b"PLZC\x01\x00\x07\x00");
+    }
+}
@@ -0,0 +1,94 @@
+// ==============================================================================
+// plaza_rust/src/errores.rs
+//
+// Errores que Python necesita distinguir con su propia clase. El resto del
+// motor sigue usando String (RuntimeError); un caso nuevo entra aquí solo si
+// el llamador tiene algo distinto que hacer con él.
+//
+//   ParquetVacio (ValueError)  el archivo no tiene filas o ninguna columna
+//                              esperada; el mensaje lista las que sí trae.
+//   CacheOcupado (TimeoutError) un lock de cache no se liberó antes de
+//                              [cache] timeout_lock_ms; el mensaje dice quién
+//                              lo tiene y desde hace cuánto.
+//   DemasiadasSolicitudes       una carga excedió [limites]; reintentar más
+//     (RuntimeError)            tarde.
+//   CacheLleno (RuntimeError)   max_periodos alcanzado y todos los periodos
+//                              fijados; `fijados` trae sus claves.
+// ==============================================================================
+
+use std::fmt;
+
+use pyo3::exceptions::{PyRuntimeError, PyTimeoutError, PyValueError};
+use pyo3::prelude::*;
+
+pyo3::create_exception!(
+    plaza_rust, ParquetVacio, PyValueError,
+    "El parquet no tiene filas o ninguna de las columnas esperadas."
+);
+
+pyo3::create_exception!(
+    plaza_rust, CacheOcupado, PyTimeoutError,
+    "Un lock de cache siguió tomado más de [cache] timeout_lock_ms."
+);
+
+pyo3::create_exception!(
+    plaza_rust, DemasiadasSolicitudes, PyRuntimeError,
+    "La operación excedió un límite de [limites]; reintentar más tarde."
+);
+
+pyo3::create_exception!(
+    plaza_rust, CacheLleno, PyRuntimeError,
+    "La cache de periodos está en max_periodos y todos están fijados."
+);
+
+#[derive(Debug)]
+pub(crate) enum Error {
+    ParquetVacio(String),
+    CacheOcupado(String),
+    DemasiadasSolicitudes(String),
+    CacheLleno(String, Vec<u32>),
+    Motor(String),
+}
+
+impl From<String> for Error {
+    fn from(e: String) -> Self { Error::Motor(e) }
+}
+
+impl From<Error> for String {
+    fn from(e: Error) -> Self { e.to_string() }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ParquetVacio(m) | Error::CacheOcupado(m)
+            | Error::DemasiadasSolicitudes(m) | Error::CacheLleno(m, _)
+            | Error::Motor(m) => f.write_str(m),
+        }
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::ParquetVacio(m) => ParquetVacio::new_err(m),
+            Error::CacheOcupado(m) => CacheOcupado::new_err(m),
+            Error::DemasiadasSolicitudes(m) => DemasiadasSolicitudes::new_err(m),
+            Error::CacheLleno(m, fijados) => Python::with_gil(|py| {
+                let e = CacheLleno::new_err(m);
+                // Sin el atributo sigue siendo el mismo error
+                let _ = e.value(py).setattr("fijados", fijados);
+                e
+            }),
+            Error::Motor(m)        => PyRuntimeError::new_err(m),
+        }
+    }
+}
+
+/// Registra las clases de excepción en el módulo.
+pub(crate) fn registrar(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ParquetVacio", m.py().get_type::<ParquetVacio>())?;
+    m.add("CacheOcupado", m.py().get_type::<CacheOcupado>())?;
+    m.add("DemasiadasSolicitudes", m.py().get_type::<DemasiadasSolicitudes>())?;
+    m.add("CacheLleno", m.py().get_type::<CacheLleno>())
+}
@@ -0,0 +1,147 @@
+// ==============================================================================
+// plaza_rust/src/escenarios.rs
+//
+// Escenarios "qué pasa si" de consolidación: cómo quedan la cobertura de
+// puntos de demanda y los totales por estado si se quitan ciertas plazas de
+// un periodo cargado. El periodo no se modifica; los planeadores corren
+// decenas de variantes por sesión sobre los mismos datos.
+//
+// Cobertura: un punto de demanda está cubierto si alguna plaza con
+// coordenadas válidas queda a <= radio_km (haversine). Los totales después
+// son los de agregar() menos el aporte de las filas quitadas, con la misma
+// política de negativos.
+// ==============================================================================
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{agregado_a_dict, agregar, clave, config, haversine, periodo, AgrMap, CajaRadio, EngineData, METRICAS};
+
+struct Simulacion {
+    cubiertos_antes:   usize,
+    cubiertos_despues: usize,
+    // índices en demand_points que pierden cobertura
+    descubiertos:      Vec<usize>,
+    antes:             AgrMap,
+    despues:           AgrMap,
+}
+
+/// Fila interna de cada fila original (inversa de `orden`).
+fn filas_internas(eng: &EngineData) -> Vec<usize> {
+    let mut inv = vec![0; eng.n];
+    for i in 0..eng.n {
+        inv[eng.fila_original(i)] = i;
+    }
+    inv
+}
+
+/// (plazas a <= radio, de ellas cuántas se quitan) para un punto.
+fn alcance(eng: &EngineData, quitada: &[bool], radio_km: f64, (lat, lng): (f64, f64)) -> (usize, usize) {
+    let caja = CajaRadio::new(lat, lng, radio_km);
+    let mut total = 0;
+    let mut quitadas = 0;
+    for (i, &q) in quitada.iter().enumerate() {
+        let (la, lo) = (eng.lats.get(i), eng.lngs.get(i));
+        if !caja.contiene(la, lo) || haversine(lat, lng, la, lo) > radio_km { continue; }
+        total += 1;
+        quitadas += q as usize;
+    }
+    (total, quitadas)
+}
+
+fn simular(eng: &EngineData, indices: &[usize], puntos: &[(f64, f64)], radio_km: f64) -> Result<Simulacion, Error> {
+    let internas = filas_internas(eng);
+    let mut quitada = vec![false; eng.n];
+    for &o in indices {
+        quitada[internas[o]] = true;
+    }
+
+    let por_punto = |&p: &(f64, f64)| alcance(eng, &quitada, radio_km, p);
+    let alcances: Vec<(usize, usize)> = if config::secuencial(eng.n.saturating_mul(puntos.len())) {
+        puntos.iter().map(por_punto).collect()
+    } else {
+        config::en_pool(|| puntos.par_iter().map(por_punto).collect())
+    };
+    let cubiertos_antes = alcances.iter().filter(|&&(t, _)| t > 0).count();
+    let descubiertos: Vec<usize> = alcances.iter().enumerate()
+        .filter(|&(_, &(t, q))| t > 0 && t == q)
+        .map(|(j, _)| j)
+        .collect();
+
+    let antes = if eng.n == 0 { AgrMap::default() } else { config::en_pool_si(eng.n, || agregar(eng, -1))? };
+    let mut despues = antes.clone();
+    let pols = config::negativos();
+    let metricas = eng.metricas();
+    for (i, _) in quitada.iter().enumerate().filter(|&(_, &q)| q) {
+        let eid = eng.estado_ids.get(i);
+        let Some(acc) = despues.get_mut(&eid) else { continue };
+        acc[0] -= 1;
+        for (m, (col, pol)) in metricas.iter().zip(pols).enumerate() {
+            let x = col.get(i);
+            acc[m + 1] -= pol.valor(x);
+            acc[m + METRICAS.len()] -= (x != i64::MIN) as i64;
+        }
+        if acc[0] == 0 { despues.remove(&eid); }
+    }
+
+    Ok(Simulacion {
+        cubiertos_antes,
+        cubiertos_despues: cubiertos_antes - descubiertos.len(),
+        descubiertos,
+        antes,
+        despues,
+    })
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// Quita `indices` (filas en el orden del parquet) del periodo y devuelve
+/// {"puntos", "cubiertos_antes", "cubiertos_despues", "cobertura_antes",
+/// "cobertura_despues", "descubiertos" (índices en demand_points),
+/// "totales_antes", "totales_despues" (como comparar_periodos)}.
+#[pyfunction]
+pub(crate) fn simular_remocion<'py>(
+    py:            Python<'py>,
+    periodo_key:   ArgClave,
+    indices:       Vec<usize>,
+    demand_points: Vec<(f64, f64)>,
+    radio_km:      f64,
+) -> PyResult<Bound<'py, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    if !(radio_km.is_finite() && radio_km >= 0.0) {
+        return Err(PyValueError::new_err(format!("radio_km inválido: {radio_km}")));
+    }
+    if demand_points.iter().any(|&(la, lo)| la.is_nan() || lo.is_nan()) {
+        return Err(PyValueError::new_err("demand_points no pueden tener NaN"));
+    }
+    let mut indices = indices;
+    indices.sort_unstable();
+    indices.dedup();
+
+    let s = py.allow_threads(|| -> Result<_, Error> {
+        let eng = periodo(periodo_key)?;
+        if let Some(&fuera) = indices.iter().find(|&&o| o >= eng.n) {
+            return Err(Error::Motor(format!("índice {fuera} fuera de rango (periodo {periodo_key} con {} filas)", eng.n)));
+        }
+        eng.tocar();
+        simular(&eng, &indices, &demand_points, radio_km)
+    })?;
+
+    let fraccion = |c: usize| if demand_points.is_empty() { 0.0 } else { c as f64 / demand_points.len() as f64 };
+    let out = PyDict::new(py);
+    out.set_item("puntos", demand_points.len())?;
+    out.set_item("cubiertos_antes", s.cubiertos_antes)?;
+    out.set_item("cubiertos_despues", s.cubiertos_despues)?;
+    out.set_item("cobertura_antes", fraccion(s.cubiertos_antes))?;
+    out.set_item("cobertura_despues", fraccion(s.cubiertos_despues))?;
+    out.set_item("descubiertos", s.descubiertos)?;
+    out.set_item("totales_antes", agregado_a_dict(py, &s.antes)?)?;
+    out.set_item("totales_despues", agregado_a_dict(py, &s.despues)?)?;
+    Ok(out)
+}
@@ -0,0 +1,94 @@
+// ==============================================================================
+// plaza_rust/src/esquema.rs
+//
+// Descripción del formato de los agregados para que Python no repita a mano
+// los nombres de métricas ("cn_sec", ...) que cambian al agregar una:
+//
+//   esquema_resultado()  →  {"formato": 3,
+//                            "metricas": [{"nombre", "posicion", "tipo",
+//                                          "columna", "aliases", "negativos",
+//                                          "no_nulos"}],
+//                            "columnas": [{"columna", "tipo", "aliases"}]}
+//
+// "metricas" sigue el orden del acumulador (METRICAS) recortado al formato
+// pedido; "plazas" es el conteo de filas y no viene de ninguna columna. Desde
+// el formato 3 siguen los conteos de no nulos ("cn_total_n", no_nulos=True)
+// de la columna de cada métrica.
+// "columnas" son las demás columnas canónicas que se leen del parquet. Los
+// aliases son los vigentes según [columnas] de la configuración. Los nombres
+// de métricas siguen [salida] nombres o el `nombres=` de la llamada.
+//
+// El módulo expone además las constantes METRICAS (tupla, formato actual),
+// FORMATO_RESULTADO y una METRICA_<NOMBRE> por métrica (METRICA_CN_SEC =
+// "cn_sec"), para usar en vez de los literales.
+// ==============================================================================
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::{config, metricas_formato, FORMATO_ACTUAL, METRICAS};
+
+// Columnas que no son métricas, con el tipo con que se exponen
+const OTRAS_COLUMNAS: [(&str, &str); 5] = [
+    ("lat",       "f64"),
+    ("lng",       "f64"),
+    ("estado_id", "i64"),
+    // en texto se traduce con [situaciones]; en lista (o máscara, ver
+    // [lector]) una fila tiene varias y el filtro significa "contiene"
+    ("situacion", "i64"),
+    ("plaza_id",  "str"),
+];
+
+/// Constantes de nombres de métricas en el módulo.
+pub(crate) fn registrar(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("METRICAS", pyo3::types::PyTuple::new(m.py(), METRICAS)?)?;
+    m.add("FORMATO_RESULTADO", FORMATO_ACTUAL)?;
+    for nombre in METRICAS {
+        m.add(format!("METRICA_{}", nombre.to_uppercase()).as_str(), nombre)?;
+    }
+    Ok(())
+}
+
+#[pyfunction]
+#[pyo3(signature = (formato = None, nombres = None))]
+pub(crate) fn esquema_resultado<'py>(
+    py:      Python<'py>,
+    formato: Option<u32>,
+    nombres: Option<&str>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let nombres = metricas_formato(formato, nombres).map_err(PyValueError::new_err)?;
+    let cfg = config::actual();
+    let pols = config::negativos();
+
+    let metricas = nombres.iter().enumerate().map(|(k, &nombre)| {
+        let d = PyDict::new(py);
+        d.set_item("nombre", nombre)?;
+        d.set_item("posicion", k)?;
+        d.set_item("tipo", "i64")?;
+        // posición 0 = conteo de filas; hasta 7, METRICAS_COLUMNA[k - 1]; luego
+        // el conteo de no nulos de METRICAS_COLUMNA[k - 7]
+        let no_nulos = k >= METRICAS.len();
+        let m = if no_nulos { Some(k - METRICAS.len()) } else { k.checked_sub(1) };
+        let columna = m.map(|m| config::METRICAS_COLUMNA[m]);
+        d.set_item("columna", columna)?;
+        d.set_item("aliases", columna.map_or(&[][..], |c| cfg.alias(c)))?;
+        d.set_item("negativos", m.filter(|_| !no_nulos).map(|m| pols[m].nombre()))?;
+        d.set_item("no_nulos", no_nulos)?;
+        Ok(d)
+    }).collect::<PyResult<Vec<_>>>()?;
+
+    let columnas = OTRAS_COLUMNAS.iter().map(|&(columna, tipo)| {
+        let d = PyDict::new(py);
+        d.set_item("columna", columna)?;
+        d.set_item("tipo", tipo)?;
+        d.set_item("aliases", cfg.alias(columna))?;
+        Ok(d)
+    }).collect::<PyResult<Vec<_>>>()?;
+
+    let out = PyDict::new(py);
+    out.set_item("formato", formato.unwrap_or(FORMATO_ACTUAL))?;
+    out.set_item("metricas", metricas)?;
+    out.set_item("columnas", columnas)?;
+    Ok(out)
+}
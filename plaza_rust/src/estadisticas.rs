@@ -0,0 +1,155 @@
+// ==============================================================================
+// plaza_rust/src/estadisticas.rs
+//
+// Estadísticas de columna del footer del parquet (min, max y nulos por row
+// group), guardadas al cargar para las columnas que el motor usa. Responden
+// "¿hay algún valor fuera de rango?" o "¿puede haber situación 7?" sin
+// recorrer filas: si el rango del archivo no lo incluye, no hay.
+//
+// Son las del archivo tal como vino: valores crudos en el tipo físico de
+// parquet (antes de convertir y de los centinelas), y los row groups que una
+// carga tolerante descartó siguen contando. Un min/max de texto puede venir
+// truncado por el escritor ("exacto": False). Si algún row group no trae
+// min/max, el del archivo es None.
+// ==============================================================================
+
+use std::cmp::Ordering;
+
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::statistics::Statistics;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::alias::ArgClave;
+use crate::{clave, periodo};
+
+#[derive(Clone, PartialEq, PartialOrd)]
+pub(crate) enum Extremo {
+    Entero(i64),
+    Real(f64),
+    Texto(String),
+}
+
+impl<'py> IntoPyObject<'py> for &Extremo {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error  = std::convert::Infallible;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        Ok(match self {
+            Extremo::Entero(x) => x.into_pyobject(py)?.into_any(),
+            Extremo::Real(x)   => x.into_pyobject(py)?.into_any(),
+            Extremo::Texto(s)  => s.into_pyobject(py)?.into_any(),
+        })
+    }
+}
+
+pub(crate) struct Grupo {
+    filas:  i64,
+    nulos:  Option<u64>,
+    min:    Option<Extremo>,
+    max:    Option<Extremo>,
+    exacto: bool,
+}
+
+pub(crate) struct Columna {
+    canonica: &'static str,
+    columna:  String,
+    grupos:   Vec<Grupo>,
+}
+
+impl Columna {
+    pub(crate) fn bytes(&self) -> usize {
+        self.columna.capacity() + self.grupos.capacity() * std::mem::size_of::<Grupo>()
+    }
+}
+
+fn extremos(s: &Statistics) -> Option<(Extremo, Extremo)> {
+    if !s.has_min_max_set() { return None; }
+    Some(match s {
+        Statistics::Boolean(v) => (Extremo::Entero(*v.min() as i64), Extremo::Entero(*v.max() as i64)),
+        Statistics::Int32(v)   => (Extremo::Entero(*v.min() as i64), Extremo::Entero(*v.max() as i64)),
+        Statistics::Int64(v)   => (Extremo::Entero(*v.min()), Extremo::Entero(*v.max())),
+        Statistics::Float(v)   => (Extremo::Real(*v.min() as f64), Extremo::Real(*v.max() as f64)),
+        Statistics::Double(v)  => (Extremo::Real(*v.min()), Extremo::Real(*v.max())),
+        Statistics::ByteArray(v) => (
+            Extremo::Texto(v.min().as_utf8().ok()?.to_string()),
+            Extremo::Texto(v.max().as_utf8().ok()?.to_string()),
+        ),
+        Statistics::Int96(_) | Statistics::FixedLenByteArray(_) => return None,
+    })
+}
+
+/// `columnas`: (canónica, índice del campo raíz en el esquema arrow, nombre).
+/// Un campo anidado (situación en lista) usa su hoja, si es una sola.
+pub(crate) fn del_footer(meta: &ParquetMetaData, columnas: &[(&'static str, usize, String)]) -> Vec<Columna> {
+    let descr = meta.file_metadata().schema_descr();
+    columnas.iter().filter_map(|(canonica, raiz, nombre)| {
+        let mut hojas = (0..descr.num_columns()).filter(|&j| descr.get_column_root_idx(j) == *raiz);
+        let hoja = hojas.next()?;
+        if hojas.next().is_some() { return None; }
+        let grupos = meta.row_groups().iter().map(|g| {
+            let s = g.column(hoja).statistics();
+            let (min, max) = s.and_then(extremos).unzip();
+            Grupo {
+                filas:  g.num_rows(),
+                nulos:  s.map(Statistics::null_count),
+                min, max,
+                exacto: s.is_some_and(|s| s.min_is_exact() && s.max_is_exact()),
+            }
+        }).collect();
+        Some(Columna { canonica, columna: nombre.clone(), grupos })
+    }).collect()
+}
+
+/// Min (o max, con `Ordering::Greater`) de todos los grupos; None si alguno
+/// no lo trae o los tipos no se comparan.
+fn del_archivo<'a>(vs: impl Iterator<Item = Option<&'a Extremo>>, gana: Ordering) -> Option<&'a Extremo> {
+    let mut out: Option<&Extremo> = None;
+    for v in vs {
+        let v = v?;
+        out = match out {
+            None => Some(v),
+            Some(o) => match v.partial_cmp(o)? {
+                c if c == gana => Some(v),
+                _ => Some(o),
+            },
+        };
+    }
+    out
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {canónica: {"columna", "min", "max", "nulos", "exacto", "grupos": [{"filas",
+/// "min", "max", "nulos", "exacto"}]}} del periodo cargado, con las
+/// estadísticas que traía el parquet; "nulos" es None si algún grupo no lo
+/// informa. Las canónicas sin columna en el archivo no aparecen.
+#[pyfunction]
+pub(crate) fn estadisticas_columnas(py: Python<'_>, periodo_key: ArgClave) -> PyResult<Bound<'_, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    let eng = py.allow_threads(|| periodo(periodo_key))?;
+    let out = PyDict::new(py);
+    for c in &eng.estadisticas {
+        let grupos = c.grupos.iter().map(|g| {
+            let d = PyDict::new(py);
+            d.set_item("filas", g.filas)?;
+            d.set_item("min", g.min.as_ref())?;
+            d.set_item("max", g.max.as_ref())?;
+            d.set_item("nulos", g.nulos)?;
+            d.set_item("exacto", g.exacto)?;
+            Ok(d)
+        }).collect::<PyResult<Vec<_>>>()?;
+        let d = PyDict::new(py);
+        d.set_item("columna", &c.columna)?;
+        d.set_item("min", del_archivo(c.grupos.iter().map(|g| g.min.as_ref()), Ordering::Less))?;
+        d.set_item("max", del_archivo(c.grupos.iter().map(|g| g.max.as_ref()), Ordering::Greater))?;
+        d.set_item("nulos", c.grupos.iter().map(|g| g.nulos).sum::<Option<u64>>())?;
+        d.set_item("exacto", c.grupos.iter().all(|g| g.exacto))?;
+        d.set_item("grupos", grupos)?;
+        out.set_item(c.canonica, d)?;
+    }
+    Ok(out)
+}
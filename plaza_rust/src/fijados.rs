@@ -0,0 +1,58 @@
+// ==============================================================================
+// plaza_rust/src/fijados.rs
+//
+// Periodos fijados: fijar_periodo(key) los saca de los desalojos automáticos
+// (la capacidad [cache] max_periodos al cargar, limpiar_periodos_lru y
+// limpiar_por_memoria); evict_periodo los sigue quitando. Se puede fijar una
+// clave que todavía no se cargó.
+//
+// Si una carga de una clave nueva encuentra la cache en max_periodos y todos
+// los periodos cargados están fijados, falla con CacheLleno (el atributo
+// `fijados` trae las claves) en vez de pasarse del tope: hay que soltar o
+// desalojar alguno antes de reintentar.
+// ==============================================================================
+
+use std::collections::BTreeSet;
+
+use pyo3::prelude::*;
+
+use crate::cerrojos::Cerrojo;
+use crate::errores::Error;
+use crate::{bitacora, clave, ArgClave, PeriodoKey};
+
+static FIJADOS: Cerrojo<BTreeSet<PeriodoKey>> = Cerrojo::new("FIJADOS", BTreeSet::new());
+
+/// Copia: quien desaloja la toma antes del lock de ENGINE_PERIODOS.
+pub(crate) fn actuales() -> Result<BTreeSet<PeriodoKey>, Error> {
+    Ok(FIJADOS.leer("fijados")?.clone())
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Fija `periodo_key`; False si ya estaba fijado.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, request_id = None))]
+pub(crate) fn fijar_periodo(periodo_key: ArgClave, request_id: Option<&str>) -> PyResult<bool> {
+    let periodo_key = clave(periodo_key)?;
+    Ok(bitacora::auditar("fijar_periodo", format!("periodo_key={periodo_key}"), request_id, || {
+        Ok::<_, Error>(FIJADOS.escribir("fijar_periodo")?.insert(periodo_key))
+    })?)
+}
+
+/// Suelta `periodo_key`; False si no estaba fijado.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, request_id = None))]
+pub(crate) fn soltar_periodo(periodo_key: ArgClave, request_id: Option<&str>) -> PyResult<bool> {
+    let periodo_key = clave(periodo_key)?;
+    Ok(bitacora::auditar("soltar_periodo", format!("periodo_key={periodo_key}"), request_id, || {
+        Ok::<_, Error>(FIJADOS.escribir("soltar_periodo")?.remove(&periodo_key))
+    })?)
+}
+
+/// Claves fijadas, ascendentes (cargadas o no).
+#[pyfunction]
+pub(crate) fn periodos_fijados() -> PyResult<Vec<PeriodoKey>> {
+    Ok(actuales()?.into_iter().collect())
+}
@@ -0,0 +1,167 @@
+// ==============================================================================
+// plaza_rust/src/filas.rs
+//
+// Comparación fila a fila entre dos periodos: cruza las plazas de ambos por
+// una llave y devuelve la diferencia de cada métrica por plaza. Responde lo
+// que una agregación por estado no puede ("qué plazas bajaron").
+//
+//   match_on = "coordenadas"   lat/lng redondeadas a 1e-5° (~1 m); con
+//                              [motor] compacto las f32 caen en la misma celda
+//   match_on = "plaza_id"      identificador de plaza (ambos periodos deben
+//                              traer la columna)
+//
+// Llaves repetidas dentro de un periodo se emparejan por orden de aparición
+// (la k-ésima del periodo 1 con la k-ésima del 2). Filas sin llave (lat/lng
+// nulas) no participan y solo se cuentan.
+// ==============================================================================
+
+use std::collections::VecDeque;
+
+use numpy::PyArray1;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rustc_hash::FxHashMap;
+
+use crate::alias::ArgClave;
+use crate::{bitacora, clave, periodo, EngineData, METRICAS};
+
+// Celdas de 1e-5° (~1.1 m en el ecuador)
+const ESCALA_COORD: f64 = 1e5;
+
+// Llave de una fila: celda (lat, lng) escalada o identificador de plaza
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Celda<'a> {
+    Coord(i64, i64),
+    Plaza(&'a str),
+}
+
+#[derive(Clone, Copy)]
+enum Llave {
+    Coordenadas,
+    PlazaId,
+}
+
+impl Llave {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "coordenadas" => Ok(Llave::Coordenadas),
+            "plaza_id"    => Ok(Llave::PlazaId),
+            otro => Err(format!("match_on desconocido: {otro:?} (coordenadas | plaza_id)")),
+        }
+    }
+
+    /// Llave de la fila `i` (orden interno); None si no tiene.
+    fn de(self, eng: &EngineData, i: usize) -> Option<Celda<'_>> {
+        match self {
+            Llave::Coordenadas => {
+                let (lat, lng) = (eng.lats.get(i), eng.lngs.get(i));
+                if !lat.is_finite() || !lng.is_finite() { return None; }
+                Some(Celda::Coord((lat * ESCALA_COORD).round() as i64, (lng * ESCALA_COORD).round() as i64))
+            }
+            Llave::PlazaId => eng.plaza(i).map(Celda::Plaza),
+        }
+    }
+
+    fn disponible(self, key: u32, eng: &EngineData) -> Result<(), String> {
+        match self {
+            Llave::PlazaId if !eng.tiene_plazas() => Err(format!("Periodo {key} sin columna plaza_id")),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Resultado del cruce; las filas son índices en el orden original del parquet.
+struct Cruce {
+    filas1:    Vec<i64>,
+    filas2:    Vec<i64>,
+    // métrica del periodo 2 menos la del 1 (nulo = 0), en el orden de METRICAS[1..]
+    deltas:    [Vec<i64>; 6],
+    solo1:     Vec<i64>,
+    solo2:     Vec<i64>,
+    sin_llave: [usize; 2],
+}
+
+/// (fila original, fila interna, llave) de las filas con llave, por fila original.
+fn llaves(eng: &EngineData, llave: Llave) -> (Vec<(usize, usize, Celda<'_>)>, usize) {
+    let mut v: Vec<_> = (0..eng.n)
+        .filter_map(|i| llave.de(eng, i).map(|k| (eng.fila_original(i), i, k)))
+        .collect();
+    v.sort_unstable_by_key(|&(o, _, _)| o);
+    let sin = eng.n - v.len();
+    (v, sin)
+}
+
+fn cruzar(e1: &EngineData, e2: &EngineData, llave: Llave) -> Cruce {
+    let (l1, sin1) = llaves(e1, llave);
+    let (l2, sin2) = llaves(e2, llave);
+
+    // llave → filas del periodo 2 pendientes de emparejar, en orden de aparición
+    let mut pendientes: FxHashMap<Celda<'_>, VecDeque<(usize, usize)>> = FxHashMap::default();
+    for &(o, i, k) in &l2 {
+        pendientes.entry(k).or_default().push_back((o, i));
+    }
+
+    let (m1, m2) = (e1.metricas(), e2.metricas());
+    let valor = |x: i64| if x == i64::MIN { 0 } else { x };
+    let mut c = Cruce {
+        filas1: Vec::new(), filas2: Vec::new(), deltas: Default::default(),
+        solo1: Vec::new(), solo2: Vec::new(), sin_llave: [sin1, sin2],
+    };
+    for &(o1, i1, k) in &l1 {
+        match pendientes.get_mut(&k).and_then(|q| q.pop_front()) {
+            Some((o2, i2)) => {
+                c.filas1.push(o1 as i64);
+                c.filas2.push(o2 as i64);
+                for (d, (a, b)) in c.deltas.iter_mut().zip(m1.iter().zip(&m2)) {
+                    d.push(valor(b.get(i2)).saturating_sub(valor(a.get(i1))));
+                }
+            }
+            None => c.solo1.push(o1 as i64),
+        }
+    }
+    c.solo2 = pendientes.into_values().flatten().map(|(o, _)| o as i64).collect();
+    c.solo2.sort_unstable();
+    c
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// Cruza las filas de dos periodos cargados. Devuelve arrays de NumPy
+/// alineados: "fila1"/"fila2" (índices en cada parquet) y, por métrica,
+/// la diferencia periodo2 - periodo1; más "solo_en_1"/"solo_en_2" con las
+/// filas sin pareja y "sin_llave1"/"sin_llave2" con las que no tienen llave.
+#[pyfunction]
+#[pyo3(signature = (key1, key2, match_on = "coordenadas", request_id = None))]
+pub(crate) fn comparar_filas<'py>(
+    py:         Python<'py>,
+    key1:       ArgClave,
+    key2:       ArgClave,
+    match_on:   &str,
+    request_id: Option<&str>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let (key1, key2) = (clave(key1)?, clave(key2)?);
+    let llave = Llave::parse(match_on).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let params = format!("key1={key1} key2={key2} match_on={match_on}");
+    let c = py.allow_threads(|| bitacora::auditar("comparar_filas", params, request_id, || {
+        let (e1, e2) = (periodo(key1)?, periodo(key2)?);
+        llave.disponible(key1, &e1)?;
+        llave.disponible(key2, &e2)?;
+        e1.tocar();
+        e2.tocar();
+        Ok::<Cruce, crate::errores::Error>(cruzar(&e1, &e2, llave))
+    }))?;
+
+    let out = PyDict::new(py);
+    out.set_item("fila1", PyArray1::from_vec(py, c.filas1))?;
+    out.set_item("fila2", PyArray1::from_vec(py, c.filas2))?;
+    for (nombre, d) in METRICAS[1..].iter().zip(c.deltas) {
+        out.set_item(*nombre, PyArray1::from_vec(py, d))?;
+    }
+    out.set_item("solo_en_1", PyArray1::from_vec(py, c.solo1))?;
+    out.set_item("solo_en_2", PyArray1::from_vec(py, c.solo2))?;
+    out.set_item("sin_llave1", c.sin_llave[0])?;
+    out.set_item("sin_llave2", c.sin_llave[1])?;
+    Ok(out)
+}
@@ -0,0 +1,285 @@
+// ==============================================================================
+// plaza_rust/src/http.rs   (feature "http")
+//
+// Servidor HTTP embebido sobre los mismos caches que usa Python:
+//
+//   PUT    /periodos/{key}                     body = parquet (gzip/zstd ok)
+//   DELETE /periodos/{key}
+//   GET    /comparar?key1=..&key2=..&filtro=..  (filtro opcional, default -1;
+//                                               estados=9,15 restringe;
+//                                               formato=N, ver FORMATO_ACTUAL;
+//                                               prioridad=alta|normal|baja;
+//                                               nombres=es|en;
+//                                               estado_nombre=true agrega el
+//                                               nombre del catálogo de estados)
+//   DELETE /resultados?key1=..&key2=..&filtro=..
+//   GET    /stats
+//   GET    /metrics                            texto Prometheus
+//   GET    /health                             healthcheck(); 503 si algo falla
+//
+// Respuestas JSON; los errores devuelven {"error": "..."} con 400/404/500
+// (503 si un lock de cache no se liberó a tiempo, 429 si una carga excede
+// [limites]). El header X-Request-Id, si viene, queda en la bitácora de
+// operaciones y en consultas_lentas() y vuelve en la respuesta.
+// ==============================================================================
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use pyo3::prelude::*;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::prioridad::{self, Prioridad};
+use crate::bitacora;
+
+struct Servidor {
+    server:  Arc<Server>,
+    workers: Vec<JoinHandle<()>>,
+    puerto:  u16,
+}
+
+static SERVIDOR: Mutex<Option<Servidor>> = Mutex::new(None);
+
+type Respuesta = (u16, Value);
+
+const PROMETHEUS_CT: &str = "text/plain; version=0.0.4";
+
+fn error(status: u16, msg: impl Into<String>) -> Respuesta {
+    (status, json!({ "error": msg.into() }))
+}
+
+fn parse_query(url: &str) -> HashMap<&str, &str> {
+    url.split_once('?')
+        .map(|(_, q)| q.split('&').filter_map(|kv| kv.split_once('=')).collect())
+        .unwrap_or_default()
+}
+
+fn param<T: std::str::FromStr>(q: &HashMap<&str, &str>, nombre: &str) -> Result<Option<T>, Respuesta> {
+    match q.get(nombre) {
+        None    => Ok(None),
+        Some(v) => v.parse().map(Some)
+            .map_err(|_| error(400, format!("parámetro inválido: {nombre}={v}"))),
+    }
+}
+
+fn param_req<T: std::str::FromStr>(q: &HashMap<&str, &str>, nombre: &str) -> Result<T, Respuesta> {
+    param(q, nombre)?.ok_or_else(|| error(400, format!("falta el parámetro {nombre}")))
+}
+
+/// periodo_key de la ruta o de un parámetro (número o alias), validado como
+/// en Python.
+fn clave(texto: &str) -> Result<u32, Respuesta> {
+    let key: u32 = match texto.parse() {
+        Ok(k) => k,
+        Err(_) if texto.chars().all(|c| c.is_ascii_digit()) => {
+            return Err(error(400, format!("periodo_key inválido: {texto}")));
+        }
+        Err(_) => crate::alias::resolver(texto).map_err(|e| error(400, e.to_string()))?,
+    };
+    crate::validar_clave(key).map_err(|e| error(400, e))
+}
+
+fn clave_param(q: &HashMap<&str, &str>, nombre: &str) -> Result<u32, Respuesta> {
+    clave(param_req::<String>(q, nombre)?.as_str())
+}
+
+/// `estados=9,15` → Some([9, 15]); ausente → None (todos).
+fn estados_param(q: &HashMap<&str, &str>) -> Result<Option<Vec<i64>>, Respuesta> {
+    let Some(v) = q.get("estados") else { return Ok(None) };
+    v.split(',').map(|e| e.trim().parse()
+        .map_err(|_| error(400, format!("parámetro inválido: estados={v}"))))
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+fn meta_json(m: &crate::MetaFilas) -> Value {
+    json!({
+        "filas": m.filas, "sin_estado": m.sin_estado,
+        "excluidas_situacion": m.excluidas_situacion, "fuera_de_estados": m.fuera_de_estados,
+    })
+}
+
+/// Mismo orden que en Python: estados ascendentes, métricas como METRICAS
+/// (serde_json con preserve_order respeta la inserción); `metricas` según
+/// el parámetro formato.
+fn agr_json(agr: &crate::AgrMap, metricas: &[&str], catalogo: Option<&crate::catalogo::Catalogo>) -> Value {
+    let mut filas: Vec<_> = agr.iter().collect();
+    filas.sort_unstable_by_key(|&(&eid, _)| eid);
+    let m: serde_json::Map<String, Value> = filas.into_iter().map(|(eid, v)| {
+        let mut metricas: serde_json::Map<String, Value> = metricas.iter()
+            .zip(v)
+            .map(|(k, x)| (k.to_string(), json!(x)))
+            .collect();
+        if let Some(c) = catalogo {
+            metricas.insert("estado_nombre".into(), json!(c.get(eid)));
+        }
+        (eid.to_string(), Value::Object(metricas))
+    }).collect();
+    Value::Object(m)
+}
+
+fn motor(e: crate::Error) -> Respuesta {
+    match e {
+        crate::Error::ParquetVacio(m) => error(400, m),
+        crate::Error::CacheOcupado(m) => error(503, m),
+        crate::Error::DemasiadasSolicitudes(m) => error(429, m),
+        crate::Error::CacheLleno(m, fijados) => (507, json!({ "error": m, "fijados": fijados })),
+        crate::Error::Motor(m)        => error(500, m),
+    }
+}
+
+fn request_id(req: &Request) -> Option<String> {
+    req.headers().iter()
+        .find(|h| h.field.equiv("X-Request-Id"))
+        .map(|h| h.value.as_str().to_string())
+}
+
+fn atender(req: &mut Request, rid: Option<&str>) -> Result<Respuesta, Respuesta> {
+    let url = req.url().to_string();
+    let path = url.split('?').next().unwrap_or("");
+    let q = parse_query(&url);
+    let segmentos: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (req.method(), segmentos.as_slice()) {
+        (Method::Put, ["periodos", key]) => {
+            let key = clave(key)?;
+            let mut raw = Vec::new();
+            req.as_reader().read_to_end(&mut raw)
+                .map_err(|e| error(400, format!("body: {e}")))?;
+            let params = format!("periodo_key={key} bytes={}", raw.len());
+            let n = bitacora::auditar("carga", params, rid, || crate::cargar_periodo(raw.into(), key))
+                .map_err(motor)?;
+            Ok((200, json!({ "periodo_key": key, "filas": n })))
+        }
+        (Method::Delete, ["periodos", key]) => {
+            let key = clave(key)?;
+            let quitado = bitacora::auditar("evict_periodo", format!("periodo_key={key}"), rid, || {
+                crate::quitar_periodo(key)
+            }).map_err(motor)?;
+            Ok((200, json!({ "eliminado": quitado })))
+        }
+        (Method::Get, ["comparar"]) => {
+            let k1 = clave_param(&q, "key1")?;
+            let k2 = clave_param(&q, "key2")?;
+            let f: i64  = param(&q, "filtro")?.unwrap_or(-1);
+            let estados = estados_param(&q)?;
+            let metricas = crate::metricas_formato(param(&q, "formato")?, q.get("nombres").copied())
+                .map_err(|e| error(400, e))?;
+            let cat = match param::<bool>(&q, "estado_nombre")? {
+                Some(true) => Some(crate::catalogo::copia().map_err(motor)?),
+                _ => None,
+            };
+            let prioridad = q.get("prioridad").map_or(Ok(Prioridad::Normal), |p| Prioridad::parse(p))
+                .map_err(|e| error(400, e))?;
+            let mut params = format!("key1={k1} key2={k2} filtro={f}");
+            if let Some(es) = &estados { params += &format!(" estados={es:?}"); }
+            let ((a1, m1, d1), (a2, m2, d2)) = bitacora::auditar("comparacion", params, rid, || {
+                prioridad::ejecutar(prioridad, || crate::comparar_con_meta(k1, k2, f, estados.as_deref()))
+            }).map_err(motor)?;
+            Ok((200, json!({
+                "periodo1": agr_json(&a1, metricas, cat.as_ref()),
+                "periodo2": agr_json(&a2, metricas, cat.as_ref()),
+                "periodo1_vacio": a1.is_empty(), "periodo2_vacio": a2.is_empty(),
+                "periodo1_meta": meta_json(&m1), "periodo2_meta": meta_json(&m2),
+                "periodo1_metadatos": d1, "periodo2_metadatos": d2,
+                "huella": crate::huella_resultado(&a1, &a2),
+            })))
+        }
+        (Method::Delete, ["resultados"]) => {
+            let k1 = clave_param(&q, "key1")?;
+            let k2 = clave_param(&q, "key2")?;
+            let f: i64  = param(&q, "filtro")?.unwrap_or(-1);
+            let params = format!("key1={k1} key2={k2} filtro={f}");
+            let quitado = bitacora::auditar("evict_resultado", params, rid, || {
+                crate::quitar_resultado((k1, k2, f))
+            }).map_err(motor)?;
+            Ok((200, json!({ "eliminado": quitado })))
+        }
+        (Method::Get, ["stats"]) => Ok((200, json!(crate::recursos()))),
+        (Method::Get, ["health"]) => {
+            let chequeos = crate::salud::evaluar(param(&q, "timeout_ms")?);
+            let ok = chequeos.iter().all(|c| c.ok);
+            let checks: serde_json::Map<String, Value> = chequeos.into_iter()
+                .map(|c| (c.nombre.to_string(), json!({ "ok": c.ok, "detalle": c.detalle, "ms": c.ms })))
+                .collect();
+            let body = json!({ "ok": ok, "timestamp": crate::now_secs(), "checks": checks });
+            Ok((if ok { 200 } else { 503 }, body))
+        }
+        _ => Err(error(404, format!("ruta desconocida: {} {path}", req.method()))),
+    }
+}
+
+fn worker(server: Arc<Server>) {
+    // recv() devuelve Err cuando detener_servidor() llama a unblock()
+    while let Ok(mut req) = server.recv() {
+        if req.method() == &Method::Get && req.url() == "/metrics" {
+            let header = Header::from_bytes("Content-Type", PROMETHEUS_CT)
+                .expect("header estático válido");
+            let _ = req.respond(Response::from_string(crate::metricas::texto_prometheus())
+                .with_header(header));
+            continue;
+        }
+        let rid = request_id(&req);
+        let (status, body) = atender(&mut req, rid.as_deref()).unwrap_or_else(|e| e);
+        let header = Header::from_bytes("Content-Type", "application/json")
+            .expect("header estático válido");
+        let mut resp = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(header);
+        // Eco para que el llamador correlacione sin guardar el suyo
+        if let Some(h) = rid.and_then(|r| Header::from_bytes("X-Request-Id", r).ok()) {
+            resp.add_header(h);
+        }
+        let _ = req.respond(resp);
+    }
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Arranca el servidor en segundo plano y devuelve el puerto real
+/// (útil con puerto=0). Falla si ya hay uno corriendo.
+#[pyfunction]
+#[pyo3(signature = (puerto, host = "127.0.0.1", hilos = 4))]
+pub(crate) fn iniciar_servidor(puerto: u16, host: &str, hilos: usize) -> PyResult<u16> {
+    let mut guard = SERVIDOR.lock()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("Mutex: {e}")))?;
+    if let Some(s) = guard.as_ref() {
+        return Err(pyo3::exceptions::PyRuntimeError::new_err(
+            format!("Servidor ya activo en el puerto {}", s.puerto)
+        ));
+    }
+
+    let server = Server::http((host, puerto))
+        .map_err(|e| pyo3::exceptions::PyOSError::new_err(format!("http: {e}")))?;
+    let puerto_real = server.server_addr().to_ip().map_or(puerto, |a| a.port());
+    let server = Arc::new(server);
+
+    let workers = (0..hilos.max(1)).map(|i| {
+        let s = Arc::clone(&server);
+        std::thread::Builder::new()
+            .name(format!("plaza-http-{i}"))
+            .spawn(move || worker(s))
+    }).collect::<Result<Vec<_>, _>>()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("thread: {e}")))?;
+
+    *guard = Some(Servidor { server, workers, puerto: puerto_real });
+    Ok(puerto_real)
+}
+
+/// Detiene el servidor (si existe) y espera a que terminen los workers.
+#[pyfunction]
+pub(crate) fn detener_servidor(py: Python<'_>) -> PyResult<bool> {
+    let servidor = SERVIDOR.lock()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("Mutex: {e}")))?
+        .take();
+    let Some(s) = servidor else { return Ok(false) };
+    py.allow_threads(|| {
+        for _ in &s.workers { s.server.unblock(); }
+        for w in s.workers { let _ = w.join(); }
+    });
+    Ok(true)
+}
@@ -0,0 +1,194 @@
+// ==============================================================================
+// plaza_rust/src/indicadores.rs
+//
+// KPIs por estado calculados sobre las filas del periodo, sin exportarlas a
+// Python:
+//
+//   indice_concentracion(key, metric)   Gini y HHI de la métrica entre las
+//                                       plazas de cada estado
+//   puntaje_compuesto(key, pesos)       suma ponderada de métricas
+//                                       normalizadas, por estado o por plaza
+//
+// En la concentración los nulos no participan y los negativos cuentan como 0
+// sea cual sea la política de [negativos]: ambos índices solo tienen sentido
+// con valores >= 0. El puntaje por estado usa los totales de agregar(), con
+// la política configurada.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+
+use numpy::PyArray1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{agregar, clave, config, periodo, EngineData, Fila, METRICAS};
+
+/// Posición en EngineData::metricas() de una métrica por nombre de salida.
+fn columna_metrica(nombre: &str) -> PyResult<usize> {
+    METRICAS[1..].iter().position(|&x| x == nombre).ok_or_else(|| {
+        PyValueError::new_err(format!("métrica desconocida: {nombre:?} ({})", METRICAS[1..].join(" | ")))
+    })
+}
+
+struct Concentracion {
+    plazas: usize,
+    total:  i64,
+    // None si el total es 0 (no hay nada que repartir)
+    gini:   Option<f64>,
+    hhi:    Option<f64>,
+}
+
+/// Gini = 2·Σ i·x(i) / (n·Σx) − (n+1)/n con x ascendente e i desde 1;
+/// HHI = Σ (x / Σx)², de 1/n (reparto parejo) a 1 (una sola plaza).
+fn concentracion(mut xs: Vec<i64>) -> Concentracion {
+    xs.sort_unstable();
+    let n = xs.len();
+    let total: i64 = xs.iter().fold(0i64, |a, &x| a.saturating_add(x));
+    if total == 0 {
+        return Concentracion { plazas: n, total, gini: None, hhi: None };
+    }
+    let t = total as f64;
+    let ponderada: f64 = xs.iter().enumerate().map(|(i, &x)| (i + 1) as f64 * x as f64).sum();
+    let gini = 2.0 * ponderada / (n as f64 * t) - (n as f64 + 1.0) / n as f64;
+    let hhi = xs.iter().map(|&x| (x as f64 / t).powi(2)).sum();
+    Concentracion { plazas: n, total, gini: Some(gini), hhi: Some(hhi) }
+}
+
+fn concentracion_por_estado(eng: &EngineData, m: usize) -> BTreeMap<i64, Concentracion> {
+    let col = eng.metricas()[m];
+    let mut valores: BTreeMap<i64, Vec<i64>> = BTreeMap::new();
+    for i in 0..eng.n {
+        let x = col.get(i);
+        if x == i64::MIN { continue; }
+        valores.entry(eng.estado_ids.get(i)).or_default().push(x.max(0));
+    }
+    valores.into_iter().map(|(eid, xs)| (eid, concentracion(xs))).collect()
+}
+
+/// Normalización min-max a [0, 1] de cada columna de `filas` (nulo = None ⇒
+/// 0) y suma ponderada por fila. Una métrica constante aporta 0.
+fn puntuar(filas: &[Vec<Option<f64>>], pesos: &[f64]) -> Vec<f64> {
+    let mut puntajes = vec![0.0; filas.len()];
+    for (j, &w) in pesos.iter().enumerate() {
+        let valores = filas.iter().filter_map(|f| f[j]);
+        let (min, max) = valores.fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), x| (a.min(x), b.max(x)));
+        if max <= min { continue; }
+        for (p, f) in puntajes.iter_mut().zip(filas) {
+            if let Some(x) = f[j] { *p += w * (x - min) / (max - min); }
+        }
+    }
+    puntajes
+}
+
+/// Índices en METRICAS y pesos, en el orden de METRICAS.
+fn parse_pesos(pesos: BTreeMap<String, f64>) -> PyResult<(Vec<usize>, Vec<f64>)> {
+    let mut v = Vec::with_capacity(pesos.len());
+    for (nombre, w) in pesos {
+        let m = METRICAS.iter().position(|&x| x == nombre).ok_or_else(|| {
+            PyValueError::new_err(format!("métrica desconocida en pesos: {nombre:?} ({})", METRICAS.join(" | ")))
+        })?;
+        if !w.is_finite() {
+            return Err(PyValueError::new_err(format!("peso de {nombre} no finito: {w}")));
+        }
+        v.push((m, w));
+    }
+    if v.is_empty() {
+        return Err(PyValueError::new_err("pesos vacío"));
+    }
+    v.sort_unstable_by_key(|&(m, _)| m);
+    Ok(v.into_iter().unzip())
+}
+
+/// (estado, puntaje) ordenado por estado, sobre los totales agregados.
+fn puntaje_por_estado(eng: &EngineData, cols: &[usize], pesos: &[f64]) -> Result<Vec<(i64, f64)>, Error> {
+    if eng.n == 0 { return Ok(Vec::new()); }
+    let agr = config::en_pool_si(eng.n, || agregar(eng, -1))?;
+    let mut estados: Vec<(i64, Fila)> = agr.into_iter().collect();
+    estados.sort_unstable_by_key(|&(eid, _)| eid);
+    let filas: Vec<Vec<Option<f64>>> = estados.iter()
+        .map(|(_, v)| cols.iter().map(|&m| Some(v[m] as f64)).collect())
+        .collect();
+    Ok(estados.iter().map(|&(eid, _)| eid).zip(puntuar(&filas, pesos)).collect())
+}
+
+/// Un puntaje por fila, en el orden original del parquet.
+fn puntaje_por_plaza(eng: &EngineData, cols: &[usize], pesos: &[f64]) -> Vec<f64> {
+    let metricas = eng.metricas();
+    let mut filas = vec![Vec::new(); eng.n];
+    for i in 0..eng.n {
+        filas[eng.fila_original(i)] = cols.iter().map(|&m| match m {
+            0 => Some(1.0),
+            m => Some(metricas[m - 1].get(i)).filter(|&x| x != i64::MIN).map(|x| x as f64),
+        }).collect();
+    }
+    puntuar(&filas, pesos)
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// {estado_id: {"plazas", "total", "gini", "hhi"}} de `metric` en el
+/// periodo; "gini"/"hhi" son None en estados cuyo total es 0.
+#[pyfunction]
+pub(crate) fn indice_concentracion<'py>(
+    py:          Python<'py>,
+    periodo_key: ArgClave,
+    metric:      &str,
+) -> PyResult<Bound<'py, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    let m = columna_metrica(metric)?;
+    let por_estado = py.allow_threads(|| -> Result<_, Error> {
+        let eng = periodo(periodo_key)?;
+        eng.tocar();
+        Ok(concentracion_por_estado(&eng, m))
+    })?;
+
+    let out = PyDict::new(py);
+    for (eid, c) in por_estado {
+        let d = PyDict::new(py);
+        d.set_item("plazas", c.plazas)?;
+        d.set_item("total", c.total)?;
+        d.set_item("gini", c.gini)?;
+        d.set_item("hhi", c.hhi)?;
+        out.set_item(eid, d)?;
+    }
+    Ok(out)
+}
+
+/// Puntaje = Σ peso · métrica normalizada a [0, 1] entre las unidades del
+/// periodo (un peso negativo penaliza). nivel = "estado": {estado_id:
+/// puntaje} sobre los totales agregados; nivel = "plaza": array de NumPy con
+/// un puntaje por fila en el orden del parquet ("plazas" vale 1 en cada fila
+/// y no aporta).
+#[pyfunction]
+#[pyo3(signature = (periodo_key, pesos, nivel = "estado"))]
+pub(crate) fn puntaje_compuesto<'py>(
+    py:          Python<'py>,
+    periodo_key: ArgClave,
+    pesos:       BTreeMap<String, f64>,
+    nivel:       &str,
+) -> PyResult<Bound<'py, PyAny>> {
+    let periodo_key = clave(periodo_key)?;
+    let (cols, pesos) = parse_pesos(pesos)?;
+    let por_plaza = match nivel {
+        "estado" => false,
+        "plaza"  => true,
+        otro => return Err(PyValueError::new_err(format!("nivel desconocido: {otro:?} (estado | plaza)"))),
+    };
+    let eng = py.allow_threads(|| periodo(periodo_key))?;
+    eng.tocar();
+    if por_plaza {
+        let puntajes = py.allow_threads(|| puntaje_por_plaza(&eng, &cols, &pesos));
+        return Ok(PyArray1::from_vec(py, puntajes).into_any());
+    }
+    let puntajes = py.allow_threads(|| puntaje_por_estado(&eng, &cols, &pesos))?;
+    let out = PyDict::new(py);
+    for (eid, p) in puntajes {
+        out.set_item(eid, p)?;
+    }
+    Ok(out.into_any())
+}
@@ -0,0 +1,183 @@
+// ==============================================================================
+// plaza_rust/src/instantaneas.rs
+//
+// Instantáneas del cache de periodos para verificar re-ingestas: después de
+// recargar, exportar_snapshot() y diff_snapshots() contra la corrida
+// anterior dicen si los datos quedaron iguales.
+//
+// Una instantánea es un TOML con, por periodo, el número de filas, los
+// totales por estado (filtro -1, como comparar_periodos) y una huella por
+// columna (SipHash de los valores en el orden interno, que es determinista
+// para el mismo parquet). La huella detecta cambios que no mueven las sumas
+// (filas intercambiadas entre estados con los mismos totales, coordenadas).
+// Las huellas se comparan entre builds del mismo toolchain: Rust no promete
+// el mismo DefaultHasher entre versiones.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hasher};
+use std::sync::Arc;
+
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::{Deserialize, Serialize};
+
+use crate::alias::ArgClave;
+use crate::calidad::Campo;
+use crate::errores::Error;
+use crate::{agregar, clave, config, now_secs, periodo, EngineData, PeriodoKey, ENGINE_PERIODOS, METRICAS};
+
+const VERSION: u32 = 1;
+
+// más las métricas (METRICAS[1..]; "plazas" es un conteo)
+const COLUMNAS: [&str; 5] = ["lat", "lng", "estado_id", "situacion", "plaza_id"];
+
+fn columnas() -> impl Iterator<Item = &'static str> {
+    COLUMNAS.into_iter().chain(METRICAS[1..].iter().copied())
+}
+
+#[derive(Serialize, Deserialize)]
+struct Instantanea {
+    version:  u32,
+    generada: u64,
+    periodos: Vec<Periodo>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Periodo {
+    periodo_key: PeriodoKey,
+    filas:       usize,
+    // columna → huella en hex (TOML no tiene u64)
+    columnas:    BTreeMap<String, String>,
+    // estado_id → métricas en el orden de METRICAS
+    estados:     BTreeMap<String, [i64; 7]>,
+}
+
+fn huella(eng: &EngineData, columna: &str) -> Result<String, String> {
+    let mut h = DefaultHasher::new();
+    if columna == "plaza_id" {
+        for i in 0..eng.n {
+            match eng.plaza(i) {
+                Some(p) => { h.write_u8(1); h.write(p.as_bytes()); }
+                None    => h.write_u8(0),
+            }
+        }
+    } else {
+        let campo = Campo::parse(columna)?;
+        for i in 0..eng.n {
+            h.write_u64(campo.valor(eng, i).map_or(u64::MAX, f64::to_bits));
+        }
+    }
+    Ok(format!("{:016x}", h.finish()))
+}
+
+fn resumir(key: PeriodoKey, eng: &EngineData) -> Result<Periodo, String> {
+    let agr = config::en_pool_si(eng.n, || agregar(eng, -1))?;
+    Ok(Periodo {
+        periodo_key: key,
+        filas:       eng.n,
+        columnas:    columnas().map(|c| Ok((c.to_string(), huella(eng, c)?))).collect::<Result<_, String>>()?,
+        // Solo METRICAS: los conteos de no nulos no entran en la instantánea
+        estados:     agr.into_iter().map(|(e, v)| (e.to_string(), std::array::from_fn(|k| v[k]))).collect(),
+    })
+}
+
+fn leer(path: &str) -> PyResult<Instantanea> {
+    let texto = std::fs::read_to_string(path)
+        .map_err(|e| PyOSError::new_err(format!("{path}: {e}")))?;
+    let inst: Instantanea = toml::from_str(&texto)
+        .map_err(|e| PyValueError::new_err(format!("{path}: instantánea inválida: {e}")))?;
+    if inst.version != VERSION {
+        return Err(PyValueError::new_err(format!(
+            "{path}: versión de instantánea {} (se esperaba {VERSION})", inst.version
+        )));
+    }
+    Ok(inst)
+}
+
+/// Diferencias de un periodo presente en ambas instantáneas; None si iguales.
+fn diferencias<'py>(py: Python<'py>, a: &Periodo, b: &Periodo) -> PyResult<Option<Bound<'py, PyDict>>> {
+    let columnas: Vec<&str> = columnas()
+        .filter(|c| a.columnas.get(*c) != b.columnas.get(*c))
+        .collect();
+    let estados = PyDict::new(py);
+    let mut ids: Vec<&String> = a.estados.keys().chain(b.estados.keys()).collect();
+    ids.sort_unstable_by_key(|e| e.parse::<i64>().unwrap_or(i64::MIN));
+    ids.dedup();
+    for e in ids {
+        let (va, vb) = (a.estados.get(e), b.estados.get(e));
+        if va == vb { continue; }
+        let m = PyDict::new(py);
+        for (k, nombre) in METRICAS.iter().enumerate() {
+            let (x, y) = (va.map(|v| v[k]), vb.map(|v| v[k]));
+            if x != y { m.set_item(nombre, (x, y))?; }
+        }
+        estados.set_item(e.parse::<i64>().unwrap_or(i64::MIN), m)?;
+    }
+    if a.filas == b.filas && columnas.is_empty() && estados.is_empty() {
+        return Ok(None);
+    }
+    let d = PyDict::new(py);
+    d.set_item("filas", (a.filas, b.filas))?;
+    d.set_item("columnas", columnas)?;
+    d.set_item("estados", estados)?;
+    Ok(Some(d))
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Escribe en `path` la instantánea de `periodos` (None = todos los
+/// cargados) y devuelve cuántos periodos incluye.
+#[pyfunction]
+#[pyo3(signature = (path, periodos = None))]
+pub(crate) fn exportar_snapshot(py: Python<'_>, path: &str, periodos: Option<Vec<ArgClave>>) -> PyResult<usize> {
+    let claves = periodos.map(|ps| ps.into_iter().map(clave).collect::<PyResult<Vec<_>>>()).transpose()?;
+    let (texto, n) = py.allow_threads(|| -> Result<_, Error> {
+        let engs: Vec<(PeriodoKey, Arc<EngineData>)> = match claves {
+            Some(ks) => ks.into_iter().map(|k| Ok((k, periodo(k)?))).collect::<Result<_, Error>>()?,
+            None => ENGINE_PERIODOS.leer("exportar_snapshot")?
+                .as_ref()
+                .map(|m| m.iter().map(|(&k, v)| (k, Arc::clone(v))).collect())
+                .unwrap_or_default(),
+        };
+        let mut periodos = engs.iter().map(|(k, e)| resumir(*k, e)).collect::<Result<Vec<_>, String>>()?;
+        periodos.sort_unstable_by_key(|p| p.periodo_key);
+        let n = periodos.len();
+        let inst = Instantanea { version: VERSION, generada: now_secs(), periodos };
+        let texto = toml::to_string(&inst).map_err(|e| Error::Motor(format!("instantánea: {e}")))?;
+        Ok((texto, n))
+    })?;
+    std::fs::write(path, texto).map_err(|e| PyOSError::new_err(format!("{path}: {e}")))?;
+    Ok(n)
+}
+
+/// {"iguales", "solo_en_a", "solo_en_b", "periodos": {key: {"filas": (a, b),
+/// "columnas": [huellas distintas], "estados": {eid: {métrica: (a, b)}}}}}.
+/// "periodos" solo trae los que difieren; una métrica ausente de un lado
+/// (estado sin filas) aparece como None.
+#[pyfunction]
+pub(crate) fn diff_snapshots<'py>(py: Python<'py>, path_a: &str, path_b: &str) -> PyResult<Bound<'py, PyDict>> {
+    let (a, b) = (leer(path_a)?, leer(path_b)?);
+    let en_b: BTreeMap<PeriodoKey, &Periodo> = b.periodos.iter().map(|p| (p.periodo_key, p)).collect();
+    let en_a: BTreeMap<PeriodoKey, &Periodo> = a.periodos.iter().map(|p| (p.periodo_key, p)).collect();
+
+    let periodos = PyDict::new(py);
+    for (k, pa) in &en_a {
+        let Some(pb) = en_b.get(k) else { continue };
+        if let Some(d) = diferencias(py, pa, pb)? {
+            periodos.set_item(k, d)?;
+        }
+    }
+    let solo_en_a: Vec<PeriodoKey> = en_a.keys().filter(|k| !en_b.contains_key(k)).copied().collect();
+    let solo_en_b: Vec<PeriodoKey> = en_b.keys().filter(|k| !en_a.contains_key(k)).copied().collect();
+
+    let out = PyDict::new(py);
+    out.set_item("iguales", periodos.is_empty() && solo_en_a.is_empty() && solo_en_b.is_empty())?;
+    out.set_item("solo_en_a", solo_en_a)?;
+    out.set_item("solo_en_b", solo_en_b)?;
+    out.set_item("periodos", periodos)?;
+    Ok(out)
+}
@@ -0,0 +1,82 @@
+// ==============================================================================
+// plaza_rust/src/invalidacion.rs
+//
+// Ganchos para invalidar caches entre procesos (varios workers de Python
+// coordinados por Redis, cada uno con su copia de los periodos):
+//
+//   worker A recarga 202401        → version_datos(202401) == 7; publica 7
+//   worker B recibe (202401, 7)    → invalidar_si_version(202401, 7)
+//                                    su versión era 6: quita resultados y
+//                                    periodo y adopta 7; con 7 o más, nada
+//
+// La versión de un periodo sube en 1 cada vez que cambia lo que se sirve
+// bajo esa clave en este proceso (carga, promoción, quitarlo) y nunca baja.
+// Como invalidar_si_version() adopta la versión recibida, la próxima carga
+// en cualquier worker publica una mayor que la que todos vieron.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+
+use pyo3::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::cerrojos::Cerrojo;
+use crate::errores::Error;
+use crate::{bitacora, clave, quitar_periodo, virtuales, PeriodoKey};
+
+// Lock: nunca se toma ENGINE_PERIODOS ni RESULT_CACHE con este en la mano
+static VERSIONES: Cerrojo<BTreeMap<PeriodoKey, u64>> = Cerrojo::new("VERSIONES_DATOS", BTreeMap::new());
+
+/// Cambió lo que se sirve bajo `key`.
+pub(crate) fn subir(key: PeriodoKey) {
+    if let Ok(mut v) = VERSIONES.escribir("subir_version") {
+        *v.entry(key).or_insert(0) += 1;
+    }
+}
+
+fn version(key: PeriodoKey) -> Result<u64, Error> {
+    Ok(VERSIONES.leer("version_datos")?.get(&key).copied().unwrap_or(0))
+}
+
+/// Con `version` mayor que la local: quita los resultados que mencionan
+/// `key` (y el periodo si `descargar`) y adopta `version`.
+fn invalidar(key: PeriodoKey, version_remota: u64, descargar: bool) -> Result<bool, Error> {
+    if version(key)? >= version_remota { return Ok(false); }
+    if descargar { quitar_periodo(key)?; }
+    virtuales::invalidar_resultados(key)?;
+    let mut v = VERSIONES.escribir("invalidar_si_version")?;
+    let local = v.entry(key).or_insert(0);
+    *local = (*local).max(version_remota);
+    Ok(true)
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Versión de los datos de `periodo_key` (0 = nunca cargado aquí); sin
+/// clave, {periodo_key: versión} de todas las conocidas.
+#[pyfunction]
+#[pyo3(signature = (periodo_key = None))]
+pub(crate) fn version_datos(py: Python<'_>, periodo_key: Option<ArgClave>) -> PyResult<PyObject> {
+    match periodo_key {
+        Some(k) => Ok(version(clave(k)?)?.into_pyobject(py)?.into_any().unbind()),
+        None    => Ok(VERSIONES.leer("version_datos")?.clone().into_pyobject(py)?.into_any().unbind()),
+    }
+}
+
+/// Invalida `periodo_key` si `version` (la publicada por otro worker) es
+/// mayor que la local. Devuelve True si invalidó. Con `descargar=False`
+/// solo quita los resultados y conserva el periodo cargado.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, version, descargar = true, request_id = None))]
+pub(crate) fn invalidar_si_version(
+    periodo_key: ArgClave,
+    version:     u64,
+    descargar:   bool,
+    request_id:  Option<&str>,
+) -> PyResult<bool> {
+    let key = clave(periodo_key)?;
+    let params = format!("periodo_key={key} version={version} descargar={descargar}");
+    Ok(bitacora::auditar("invalidar_si_version", params, request_id, || invalidar(key, version, descargar))?)
+}
@@ -0,0 +1,128 @@
+// ==============================================================================
+// plaza_rust/src/iteradores.rs
+//
+// Salidas por fila en trozos: iterar_filas() devuelve un iterador de Python
+// que entrega `tamaño` filas por vuelta en vez de una lista con todo el
+// periodo, así la memoria del lado de Python queda acotada y el primer trozo
+// llega antes de convertir el resto.
+//
+//   for trozo in iterar_filas(202401, ["estado_id", "cn_total"], tamaño=50_000,
+//                             donde="cn_total > 0"):
+//       trozo  →  {"fila": [...], "estado_id": [...], "cn_total": [...]}
+//
+// Cada trozo es columnar (listas alineadas; nulo = None) y "fila" es el
+// índice original en el parquet. Las filas salen en el orden interno del
+// motor (agrupadas por estado), no en el del parquet. El iterador sostiene
+// el Arc del periodo: un desalojo mientras se recorre no lo invalida, pero
+// la memoria no se libera hasta soltar el iterador.
+// ==============================================================================
+
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use crate::alias::ArgClave;
+use crate::calidad::Campo;
+use crate::predicados::Predicado;
+use crate::{clave, periodo, EngineData, METRICAS};
+
+// Columnas sin `columnas`: plaza_id solo si el periodo la trae
+const POR_OMISION: [&str; 4] = ["estado_id", "situacion", "lat", "lng"];
+
+#[pyclass(module = "plaza_rust")]
+pub(crate) struct IteradorFilas {
+    eng:        Arc<EngineData>,
+    campos:     Vec<(String, Campo)>,
+    donde:      Predicado,
+    tamaño:     usize,
+    // Próxima fila interna por revisar
+    siguiente:  usize,
+    entregadas: usize,
+}
+
+impl IteradorFilas {
+    /// Filas internas del próximo trozo (hasta `tamaño` que cumplen `donde`).
+    fn avanzar(&mut self) -> Vec<usize> {
+        let mut filas = Vec::with_capacity(self.tamaño.min(self.eng.n - self.siguiente));
+        while self.siguiente < self.eng.n && filas.len() < self.tamaño {
+            if self.donde.cumple(&self.eng, self.siguiente) { filas.push(self.siguiente); }
+            self.siguiente += 1;
+        }
+        filas
+    }
+}
+
+#[pymethods]
+impl IteradorFilas {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(mut slf: PyRefMut<'py, Self>, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let this = &mut *slf;
+        let filas = py.allow_threads(|| this.avanzar());
+        if filas.is_empty() { return Ok(None); }
+        slf.entregadas += filas.len();
+        let eng = &slf.eng;
+        let out = PyDict::new(py);
+        out.set_item("fila", filas.iter().map(|&i| eng.fila_original(i)).collect::<Vec<_>>())?;
+        for (nombre, campo) in &slf.campos {
+            let valores = filas.iter()
+                .map(|&i| campo.a_python(py, eng, i))
+                .collect::<PyResult<Vec<_>>>()?;
+            out.set_item(nombre, PyList::new(py, valores)?)?;
+        }
+        Ok(Some(out))
+    }
+
+    /// Filas entregadas hasta ahora.
+    #[getter]
+    fn entregadas(&self) -> usize {
+        self.entregadas
+    }
+
+    /// Filas del periodo (antes de `donde`).
+    #[getter]
+    fn total(&self) -> usize {
+        self.eng.n
+    }
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// Iterador de trozos de `tamaño` filas del periodo con `columnas` (None =
+/// estado_id, situacion, lat, lng, plaza_id si la hay y las seis métricas)
+/// que cumplen `donde` (predicados.rs).
+#[pyfunction]
+#[pyo3(signature = (periodo_key, columnas = None, tamaño = 10_000, donde = None))]
+pub(crate) fn iterar_filas(
+    py:          Python<'_>,
+    periodo_key: ArgClave,
+    columnas:    Option<Vec<String>>,
+    tamaño:      usize,
+    donde:       Option<&str>,
+) -> PyResult<IteradorFilas> {
+    let key = clave(periodo_key)?;
+    if tamaño == 0 {
+        return Err(pyo3::exceptions::PyValueError::new_err("iterar_filas: tamaño debe ser > 0"));
+    }
+    let donde = Predicado::parse(donde.unwrap_or(""))
+        .map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let eng = py.allow_threads(|| periodo(key))?;
+    eng.tocar();
+    let columnas = columnas.unwrap_or_else(|| {
+        POR_OMISION.iter().copied()
+            .chain(eng.tiene_plazas().then_some("plaza_id"))
+            .chain(METRICAS[1..].iter().copied())
+            .map(String::from)
+            .collect()
+    });
+    let campos = columnas.into_iter()
+        .map(|c| Campo::parse(&c).map(|campo| (c, campo)))
+        .collect::<Result<_, _>>()
+        .map_err(pyo3::exceptions::PyValueError::new_err)?;
+    Ok(IteradorFilas { eng, campos, donde, tamaño, siguiente: 0, entregadas: 0 })
+}
@@ -0,0 +1,43 @@
+// ==============================================================================
+// plaza_rust/src/legado.rs
+//
+// Capa de compatibilidad de las funciones del motor de un solo periodo
+// (ENGINE): distancias_cercanas, k_mas_cercanas, agregaciones_por_estado,
+// filtrar_indices y engine_stats aceptan `periodo_key` y entonces responden
+// sobre ese periodo de la cache, como el resto del módulo. Llamadas sin
+// `periodo_key` siguen usando ENGINE con la misma firma de siempre, pero
+// emiten DeprecationWarning; init_engine también. Cuando no queden
+// llamadores legados se borran ENGINE y init_engine.
+//
+// init_engine_desde_periodo no avisa: es el paso de migración (ENGINE pasa
+// a ser el Arc de un periodo cargado, sin copia).
+// ==============================================================================
+
+use std::ffi::CString;
+use std::sync::Arc;
+
+use pyo3::exceptions::{PyDeprecationWarning, PyRuntimeError};
+use pyo3::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::{clave, periodo, EngineData, ENGINE};
+
+/// DeprecationWarning de `funcion` apuntando al llamador de Python.
+pub(crate) fn avisar(py: Python<'_>, funcion: &str, reemplazo: &str) -> PyResult<()> {
+    let msg = format!("plaza_rust.{funcion}: el motor legado (ENGINE) está obsoleto; {reemplazo}");
+    let msg = CString::new(msg).unwrap_or_default();
+    PyErr::warn(py, py.get_type::<PyDeprecationWarning>().as_any(), &msg, 1)
+}
+
+/// Datos sobre los que responde `funcion`: el periodo `periodo_key` de la
+/// cache o, sin él, ENGINE (con aviso).
+pub(crate) fn motor(py: Python<'_>, funcion: &'static str, periodo_key: Option<ArgClave>) -> PyResult<Arc<EngineData>> {
+    if let Some(k) = periodo_key {
+        let eng = periodo(clave(k)?)?;
+        eng.tocar();
+        return Ok(eng);
+    }
+    avisar(py, funcion, "pasar periodo_key")?;
+    ENGINE.leer(funcion)?.clone()
+        .ok_or_else(|| PyRuntimeError::new_err("Motor no init."))
+}
@@ -0,0 +1,113 @@
+// ==============================================================================
+// plaza_rust/src/lentas.rs
+//
+// Las CAPACIDAD comparaciones más lentas desde el arranque (o desde el último
+// reinicio), para ver qué pares de periodos fallan seguido en RESULT_CACHE
+// y por qué: claves, filtro, estados, duración, si fue hit, cuántas filas
+// se recorrieron (0 en un hit) y el request_id del pedido (bitacora.rs).
+//
+// comparar() y comparar_estados() anotan las filas que agregan en el hilo
+// que llama; comparar_con_meta() (por donde entran Python y HTTP) las toma
+// al terminar y registra. Con la estructura llena, una comparación más
+// rápida que todas las guardadas no se registra.
+// ==============================================================================
+
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::{bitacora, PeriodoKey};
+
+const CAPACIDAD: usize = 32;
+
+struct Lenta {
+    ts_ms:       u64,
+    key1:        PeriodoKey,
+    key2:        PeriodoKey,
+    filtro:      i64,
+    estados:     Option<Vec<i64>>,
+    duracion_us: u64,
+    // None = sin agregar (hit)
+    filas:       Option<usize>,
+    ok:          bool,
+    request_id:  Option<String>,
+}
+
+static LENTAS: Mutex<Vec<Lenta>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static FILAS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Empieza una comparación en este hilo (descarta filas de otras llamadas).
+pub(crate) fn iniciar() {
+    FILAS.with(|f| f.set(None));
+}
+
+/// Filas que la comparación en curso en este hilo tuvo que agregar.
+pub(crate) fn anotar_filas(n: usize) {
+    FILAS.with(|f| f.set(Some(f.get().unwrap_or(0) + n)));
+}
+
+/// Registra una comparación terminada (toma las filas anotadas).
+pub(crate) fn registrar(
+    key1:     PeriodoKey,
+    key2:     PeriodoKey,
+    filtro:   i64,
+    estados:  Option<&[i64]>,
+    duracion: Duration,
+    ok:       bool,
+) {
+    let filas = FILAS.with(Cell::take);
+    let duracion_us = duracion.as_micros() as u64;
+    let Ok(mut v) = LENTAS.lock() else { return };
+    if v.len() >= CAPACIDAD {
+        // La más rápida de las guardadas sale si esta es más lenta
+        let Some((k, min)) = v.iter().enumerate().min_by_key(|(_, l)| l.duracion_us) else { return };
+        if min.duracion_us >= duracion_us { return; }
+        v.swap_remove(k);
+    }
+    let ts_ms = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64);
+    v.push(Lenta {
+        ts_ms, key1, key2, filtro, estados: estados.map(<[i64]>::to_vec), duracion_us, filas, ok,
+        request_id: bitacora::actual(),
+    });
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Las comparaciones más lentas registradas, la más lenta primero:
+/// [{"ts_ms", "key1", "key2", "filtro", "estados", "duracion_us", "hit",
+/// "filas", "ok", "request_id"}, ...]. Con `reiniciar` además se vacía el
+/// registro.
+#[pyfunction]
+#[pyo3(signature = (reiniciar = false))]
+pub(crate) fn consultas_lentas(py: Python<'_>, reiniciar: bool) -> PyResult<Vec<Bound<'_, PyDict>>> {
+    let mut lentas = {
+        let mut v = LENTAS.lock()
+            .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("Mutex: LENTAS envenenado"))?;
+        if reiniciar { std::mem::take(&mut *v) } else {
+            v.iter().map(|l| Lenta { estados: l.estados.clone(), request_id: l.request_id.clone(), ..*l }).collect()
+        }
+    };
+    lentas.sort_by(|a, b| b.duracion_us.cmp(&a.duracion_us).then(a.ts_ms.cmp(&b.ts_ms)));
+    lentas.into_iter().map(|l| {
+        let d = PyDict::new(py);
+        d.set_item("ts_ms", l.ts_ms)?;
+        d.set_item("key1", l.key1)?;
+        d.set_item("key2", l.key2)?;
+        d.set_item("filtro", l.filtro)?;
+        d.set_item("estados", l.estados)?;
+        d.set_item("duracion_us", l.duracion_us)?;
+        d.set_item("hit", l.ok && l.filas.is_none())?;
+        d.set_item("filas", l.filas.unwrap_or(0))?;
+        d.set_item("ok", l.ok)?;
+        d.set_item("request_id", l.request_id)?;
+        Ok(d)
+    }).collect()
+}
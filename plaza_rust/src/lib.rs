@@ -8,17 +8,30 @@
 //
 //   RESULT_CACHE     →  resultados de comparaciones ya calculadas
 //                        clave: (key1, key2, filtro_situacion)
-//                        valor: HashMap<estado_id, [i64; 6]> x2 + timestamp
+//                        valor: HashMap<estado_id, Fila = [i64; ANCHO]> x2 + timestamp
+//                               (zstd desde [cache] comprimir_resultados estados;
+//                               un hit clona el Arc bajo el lock y copia o
+//                               descomprime ya sin él)
+//                        + filas por estado y metadatos de cada lado al
+//                          calcular (ResumenLado)
 //
 // Cuando Python llama comparar_periodos(key1, key2, filtro):
-//   1. Busca en RESULT_CACHE   → hit: devuelve directo (sin recalcular nada)
+//   1. Busca en RESULT_CACHE   → hit: agregados y MetaFilas salen de la
+//                                entrada (ni recalcula ni necesita los
+//                                periodos cargados)
 //   2. Miss: calcula con Rayon → guarda en RESULT_CACHE → devuelve
 //
 // Evicción (llamada desde Python watchdog o TTL):
 //   - evict_resultado(key1, key2, filtro)  → borra una entrada de RESULT_CACHE
 //   - evict_periodo(key)                   → borra datos crudos
 //   - limpiar_resultados_expirados(ttl_s)  → borra resultados más viejos que ttl_s
-//   - limpiar_periodos_lru(max_n)          → deja solo los max_n más recientes
+//   - limpiar_periodos_lru(max_n, año)     → deja solo los max_n históricos más recientes
+//   - limpiar_por_memoria(max_mb)          → desaloja periodos LRU hasta max_mb
+//   (todas devuelven {"n", "bytes", "desalojados"}; con dry_run=True es lo
+//   que se quitaría, sin tocar nada)
+//
+// derivar_resultado(base, nueva) guarda la comparación invertida de una que
+// ya está en cache, sin recalcular.
 // ==============================================================================
 // ==============================================================================
 // plaza_rust/src/lib.rs  v5.2
@@ -29,15 +42,94 @@
 //   - reduce(): for i in 0..7
 //   - to_py_map(): m.insert("cn_sec", v[6])
 // ==============================================================================
+// ==============================================================================
+// plaza_rust/src/lib.rs  v5.3
+//
+// Soporte free-threaded (CPython 3.13t):
+//   - El módulo se declara gil_used = false; el GIL ya no serializa nada.
+//   - Los periodos se guardan como Arc<EngineData>: los locks solo se toman
+//     para clonar/insertar el Arc y se sueltan antes de agregar con Rayon.
+//   - Ningún lock se mantiene mientras se re-adquiere el GIL (con el GIL
+//     clásico eso provocaba deadlock entre comparar_periodos y una carga).
+// ==============================================================================
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{Cursor, Read};
-use std::sync::RwLock;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
+use bytes::Bytes;
+use numpy::{PyArray1, PyReadonlyArray1};
 use pyo3::prelude::*;
-use pyo3::types::{PyBytes, PyList};
+use pyo3::pybacked::PyBackedBytes;
+use pyo3::types::{PyBytes, PyDict, PyList, PyString};
 use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+
+mod aislamiento;
+mod alias;
+mod bitacora;
+mod buffers;
+mod busqueda;
+mod calidad;
+mod cambios;
+mod catalogo;
+mod cerrojos;
+mod columna;
+mod config;
+mod corredor;
+mod cuarentena;
+mod descartes;
+mod demanda;
+mod empaquetado;
+mod errores;
+mod escenarios;
+mod esquema;
+mod estadisticas;
+mod fijados;
+mod filas;
+#[cfg(feature = "http")]
+mod http;
+mod indicadores;
+mod instantaneas;
+mod invalidacion;
+mod iteradores;
+mod legado;
+mod lentas;
+mod limites;
+mod mapa;
+mod metadatos;
+mod memoria;
+mod metricas;
+mod multifiltro;
+pub mod offline;
+mod paginado;
+mod particion;
+mod plazas;
+mod poligonos;
+mod predicados;
+mod prioridad;
+mod proyeccion;
+#[cfg(test)]
+mod pruebas;
+mod refresco;
+mod resumen;
+mod revalidacion;
+mod salud;
+mod traza;
+mod validacion;
+mod versiones;
+mod virtuales;
+mod vistas;
+
+use alias::ArgClave;
+use cerrojos::Cerrojo;
+use columna::{despachar, ColF, ColI};
+use config::PoliticaEviccion;
+use errores::Error;
+use metricas::{Cache, Motivo, Operacion};
 
 // ---------------------------------------------------------------------------
 // Tipos
@@ -45,24 +137,339 @@ use rayon::prelude::*;
 type PeriodoKey = u32;
 type ResultKey  = (u32, u32, i64);
 
+// Rango de años aceptado en claves año*100+mes: deja fuera timestamps,
+// fechas AAAAMMDD y claves con el año truncado
+const AÑOS_VALIDOS: std::ops::RangeInclusive<u32> = 1990..=2100;
+
+/// Con `[cache] claves_calendario` (default) exige año*100+mes con mes en
+/// 1..=12 y año en AÑOS_VALIDOS; limpiar_periodos_lru y la política por año
+/// dependen de eso. Sin el flag cualquier u32 es una clave. Las claves de
+/// periodos virtuales ya definidos se aceptan siempre.
+fn validar_clave(key: PeriodoKey) -> Result<PeriodoKey, String> {
+    if !config::claves_calendario() { return Ok(key); }
+    let (año, mes) = (key / 100, key % 100);
+    if (1..=12).contains(&mes) && AÑOS_VALIDOS.contains(&año) || virtuales::es_virtual(key) {
+        return Ok(key);
+    }
+    Err(format!(
+        "periodo_key inválido: {key} (se espera año*100+mes, año {}..={}; \
+         [cache] claves_calendario = false admite claves libres)",
+        AÑOS_VALIDOS.start(), AÑOS_VALIDOS.end(),
+    ))
+}
+
+/// Resuelve el alias si lo es y valida; ValueError para las funciones
+/// exportadas.
+fn clave(key: ArgClave) -> PyResult<PeriodoKey> {
+    let key = key.resolver().map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    validar_clave(key).map_err(pyo3::exceptions::PyValueError::new_err)
+}
+
+// Columnas canónicas de coordenadas (f64); el resto se carga como i64
+const COLUMNAS_F64: [&str; 2] = ["lat", "lng"];
+
+// Nombres de salida de las posiciones 0..7 del acumulador
+const METRICAS: [&str; 7] = [
+    "plazas", "inc_total", "aten_total", "cn_total", "cn_ini", "cn_prim", "cn_sec",
+];
+
+// Posiciones 7..13: filas con valor no nulo de cada métrica de columna, para
+// promediar sobre las filas con dato y no sobre "plazas"
+const CONTEOS: [&str; 6] = [
+    "inc_total_n", "aten_total_n", "cn_total_n", "cn_ini_n", "cn_prim_n", "cn_sec_n",
+];
+
+const ANCHO: usize = METRICAS.len() + CONTEOS.len();
+
+// Claves de salida de todo el acumulador (formato 3)
+const CLAVES: [&str; ANCHO] = [
+    "plazas", "inc_total", "aten_total", "cn_total", "cn_ini", "cn_prim", "cn_sec",
+    "inc_total_n", "aten_total_n", "cn_total_n", "cn_ini_n", "cn_prim_n", "cn_sec_n",
+];
+
+// Las mismas posiciones con [salida] nombres = "en"
+const CLAVES_EN: [&str; ANCHO] = [
+    "sites", "enrolled_total", "served_total", "completions_total",
+    "completions_initial", "completions_primary", "completions_secondary",
+    "enrolled_total_n", "served_total_n", "completions_total_n",
+    "completions_initial_n", "completions_primary_n", "completions_secondary_n",
+];
+
+/// Claves de salida del acumulador en el esquema `nombres`.
+fn claves_salida(nombres: config::Nombres) -> &'static [&'static str; ANCHO] {
+    match nombres {
+        config::Nombres::Es => &CLAVES,
+        config::Nombres::En => &CLAVES_EN,
+    }
+}
+
+/// Acumulador de un estado: METRICAS y luego CONTEOS.
+type Fila = [i64; ANCHO];
+
 // ---------------------------------------------------------------------------
 // Datos crudos de un periodo
 // ---------------------------------------------------------------------------
-#[derive(Clone)]
 struct EngineData {
     n:             usize,
-    lats:          Vec<f64>,
-    lngs:          Vec<f64>,
-    estado_ids:    Vec<i64>,
-    situaciones:   Vec<i64>,
-    inc_totales:   Vec<i64>,
-    aten_totales:  Vec<i64>,
-    cn_totales:    Vec<i64>,
-    cn_ini:        Vec<i64>,
-    cn_prim:       Vec<i64>,
-    cn_sec:        Vec<i64>,
+    lats:          ColF,
+    lngs:          ColF,
+    estado_ids:    ColI,
+    situaciones:   ColI,
+    inc_totales:   ColI,
+    aten_totales:  ColI,
+    cn_totales:    ColI,
+    cn_ini:        ColI,
+    cn_prim:       ColI,
+    cn_sec:        ColI,
+    // Tras agrupar_por_estado(): filas ordenadas por estado_id, un Grupo por
+    // estado y orden[i] = fila original de la fila i. Vacíos = orden original.
+    grupos:        Vec<Grupo>,
+    orden:         Vec<u32>,
+    // Tras indexar_situaciones(): una máscara de filas por situación, ordenadas
+    // por valor. Vacío = sin índice (se evalúa la condición fila por fila).
+    bitmaps:       Vec<Bitmap>,
+    // Filas multivalor (situación en lista o máscara): `situaciones` guarda la
+    // menor y aquí van las demás como (fila, situación), ordenadas. Vacío en
+    // un periodo de una situación por fila.
+    otras_situaciones: Vec<(u32, i64)>,
+    // Identificador de plaza por fila como código en `plazas` (nulo =
+    // i64::MIN); longitud 0 si el parquet no trae plaza_id
+    plaza_ids:     ColI,
+    plazas:        Vec<String>,
+    // Canónica → nombre con que venía en el parquet (vacío en init_engine)
+    origen:        Vec<(&'static str, String)>,
+    // Etiquetas del llamador al cargar (metadatos.rs)
+    metadatos:     BTreeMap<String, String>,
+    // Min/max/nulos del footer por columna usada (estadisticas.rs)
+    estadisticas:  Vec<estadisticas::Columna>,
+    // huella_carga() de los bytes y opciones con que se cargó (0 en
+    // init_engine) y lo descartado si fue tolerante: una carga repetida
+    // igual no se vuelve a parsear
+    huella:        u64,
+    descartes:     Option<descartes::Descartes>,
     cargado_at:    u64,
-    ultimo_acceso: u64,
+    // Atómicos: el periodo vive en un Arc compartido e inmutable
+    ultimo_acceso: AtomicU64,
+    accesos:       AtomicU64,
+}
+
+#[derive(Clone, Copy)]
+struct Grupo {
+    estado: i64,
+    ini:    usize,
+    fin:    usize,
+}
+
+struct Bitmap {
+    situacion: i64,
+    bits:      Vec<u64>,
+}
+
+// Con más valores distintos el índice deja de compensar (n/8 bytes cada uno)
+const MAX_BITMAPS: usize = 64;
+
+/// Filas con bit encendido dentro de [ini, fin), en orden ascendente.
+fn bits_en_rango(bits: &[u64], ini: usize, fin: usize) -> impl Iterator<Item = usize> + '_ {
+    let palabras = if ini < fin { ini / 64..(fin - 1) / 64 + 1 } else { 0..0 };
+    palabras.flat_map(move |w| {
+        let mut m = bits[w];
+        if w == ini / 64       { m &= !0u64 << (ini % 64); }
+        if w == (fin - 1) / 64 { m &= !0u64 >> (63 - (fin - 1) % 64); }
+        std::iter::from_fn(move || {
+            if m == 0 { return None; }
+            let b = m.trailing_zeros() as usize;
+            m &= m - 1;
+            Some(w * 64 + b)
+        })
+    })
+}
+
+impl EngineData {
+    /// Reordena todas las columnas por estado_id (orden estable) y guarda los
+    /// offsets de cada grupo: la agregación suma slices contiguos y los
+    /// filtros por estado recorren un solo rango.
+    fn agrupar_por_estado(mut self) -> Self {
+        if self.n == 0 || self.n > u32::MAX as usize || self.estado_ids.len() != self.n {
+            return self;
+        }
+        let mut orden: Vec<u32> = (0..self.n as u32).collect();
+        orden.sort_by_key(|&i| self.estado_ids.get(i as usize));
+
+        self.lats.permutar(&orden);
+        self.lngs.permutar(&orden);
+        self.estado_ids.permutar(&orden);
+        self.situaciones.permutar(&orden);
+        self.inc_totales.permutar(&orden);
+        self.aten_totales.permutar(&orden);
+        self.cn_totales.permutar(&orden);
+        self.cn_ini.permutar(&orden);
+        self.cn_prim.permutar(&orden);
+        self.cn_sec.permutar(&orden);
+        if self.tiene_plazas() { self.plaza_ids.permutar(&orden); }
+        if self.multivalor() {
+            let mut nueva = vec![0u32; self.n];
+            for (j, &o) in orden.iter().enumerate() { nueva[o as usize] = j as u32; }
+            for o in &mut self.otras_situaciones { o.0 = nueva[o.0 as usize]; }
+            self.otras_situaciones.sort_unstable();
+        }
+
+        let mut grupos = Vec::new();
+        let mut ini = 0;
+        for i in 1..=self.n {
+            if i == self.n || self.estado_ids.get(i) != self.estado_ids.get(ini) {
+                grupos.push(Grupo { estado: self.estado_ids.get(ini), ini, fin: i });
+                ini = i;
+            }
+        }
+        self.grupos = grupos;
+        self.orden = orden;
+        self
+    }
+
+    /// Construye los bitmaps por situación (después de agrupar: los bits
+    /// refieren a las filas ya reordenadas). Los nulos no entran en ninguno.
+    fn indexar_situaciones(mut self) -> Self {
+        let palabras = self.n.div_ceil(64);
+        let mut mapas: std::collections::BTreeMap<i64, Vec<u64>> = Default::default();
+        let filas = (0..self.n).map(|i| (i, self.situaciones.get(i)))
+            .chain(self.otras_situaciones.iter().map(|&(i, s)| (i as usize, s)));
+        for (i, s) in filas {
+            if s == i64::MIN { continue; }
+            if !mapas.contains_key(&s) && mapas.len() == MAX_BITMAPS {
+                mapas.clear();
+                break;
+            }
+            mapas.entry(s).or_insert_with(|| vec![0u64; palabras])[i / 64] |= 1 << (i % 64);
+        }
+        self.bitmaps = mapas.into_iter()
+            .map(|(situacion, bits)| Bitmap { situacion, bits })
+            .collect();
+        self
+    }
+
+    /// `None` = el periodo no tiene índice; `Some(None)` = indexado pero
+    /// ninguna fila tiene esa situación.
+    fn bitmap(&self, situacion: i64) -> Option<Option<&[u64]>> {
+        if self.bitmaps.is_empty() { return None; }
+        Some(self.bitmaps.binary_search_by_key(&situacion, |b| b.situacion).ok()
+            .map(|k| self.bitmaps[k].bits.as_slice()))
+    }
+
+    fn multivalor(&self) -> bool {
+        !self.otras_situaciones.is_empty()
+    }
+
+    /// `true` si la fila `i` tiene la situación `s` (>= 0): en un periodo
+    /// multivalor un filtro de situación significa "contiene".
+    fn tiene_situacion(&self, i: usize, s: i64) -> bool {
+        self.situaciones.get(i) == s
+            || (self.multivalor() && self.otras_situaciones.binary_search(&(i as u32, s)).is_ok())
+    }
+
+    /// Filas del estado `eid` (solo si el periodo está agrupado).
+    fn rango_estado(&self, eid: i64) -> Option<std::ops::Range<usize>> {
+        self.grupos.binary_search_by_key(&eid, |g| g.estado).ok()
+            .map(|k| self.grupos[k].ini..self.grupos[k].fin)
+    }
+
+    /// Índice de la fila en el orden en que llegó (para devolver a Python).
+    fn fila_original(&self, i: usize) -> usize {
+        self.orden.get(i).map_or(i, |&o| o as usize)
+    }
+
+    /// Modo compacto: cada columna al tipo más chico que admite su rango.
+    /// Va al final (agrupar/indexar trabajan sobre los buffers i64 del pool).
+    fn compactar(mut self) -> Self {
+        self.lats.compactar();
+        self.lngs.compactar();
+        for c in [
+            &mut self.estado_ids, &mut self.situaciones, &mut self.inc_totales, &mut self.aten_totales,
+            &mut self.cn_totales, &mut self.cn_ini, &mut self.cn_prim, &mut self.cn_sec,
+            &mut self.plaza_ids,
+        ] {
+            c.compactar();
+        }
+        self
+    }
+
+    /// [motor] diccionario: las 6 métricas como códigos a tuplas distintas
+    /// (columna.rs). Va después de compactar.
+    fn codificar_metricas(mut self) -> Self {
+        columna::codificar([
+            &mut self.inc_totales, &mut self.aten_totales, &mut self.cn_totales,
+            &mut self.cn_ini, &mut self.cn_prim, &mut self.cn_sec,
+        ]);
+        self
+    }
+
+    /// Las 6 columnas de métricas en el orden del acumulador (posiciones 1..7).
+    fn metricas(&self) -> [&ColI; 6] {
+        [
+            &self.inc_totales, &self.aten_totales, &self.cn_totales,
+            &self.cn_ini, &self.cn_prim, &self.cn_sec,
+        ]
+    }
+
+    /// Códigos compartidos y valores por tupla de cada métrica, si el
+    /// periodo está en diccionario.
+    fn diccionario(&self) -> Option<(&ColI, [&[i64]; 6])> {
+        let cols = self.metricas();
+        let ColI::Dic(d0) = cols[0] else { return None };
+        let mut valores = [&[][..]; 6];
+        for (k, c) in cols.iter().enumerate() {
+            let ColI::Dic(d) = c else { return None };
+            if !Arc::ptr_eq(&d.codigos, &d0.codigos) { return None; }
+            valores[k] = &d.valores;
+        }
+        Some((&d0.codigos, valores))
+    }
+
+    fn tiene_plazas(&self) -> bool {
+        self.n > 0 && self.plaza_ids.len() == self.n
+    }
+
+    /// Identificador de plaza de la fila `i` (orden interno).
+    fn plaza(&self, i: usize) -> Option<&str> {
+        if !self.tiene_plazas() { return None; }
+        let c = self.plaza_ids.get(i);
+        (c != i64::MIN).then(|| self.plazas[c as usize].as_str())
+    }
+
+    fn tocar(&self) {
+        self.ultimo_acceso.store(now_secs(), Ordering::Relaxed);
+        self.accesos.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Suma de bytes_por_estructura().
+    fn bytes(&self) -> usize {
+        self.bytes_por_estructura().iter().map(|(_, b)| b).sum()
+    }
+
+    /// Bytes reservados por cada estructura (capacidad, no longitud).
+    fn bytes_por_estructura(&self) -> Vec<(&'static str, usize)> {
+        fn cap<T>(v: &Vec<T>) -> usize { v.capacity() * std::mem::size_of::<T>() }
+        vec![
+            ("lats",         self.lats.bytes()),
+            ("lngs",         self.lngs.bytes()),
+            ("estado_ids",   self.estado_ids.bytes()),
+            ("situaciones",  self.situaciones.bytes()),
+            ("inc_totales",  self.inc_totales.bytes()),
+            ("aten_totales", self.aten_totales.bytes()),
+            ("cn_totales",   self.cn_totales.bytes()),
+            ("cn_ini",       self.cn_ini.bytes()),
+            ("cn_prim",      self.cn_prim.bytes()),
+            ("cn_sec",       self.cn_sec.bytes()),
+            ("plaza_ids",    self.plaza_ids.bytes()),
+            ("plazas",       cap(&self.plazas) + self.plazas.iter().map(|s| s.capacity()).sum::<usize>()),
+            ("grupos",       cap(&self.grupos)),
+            ("orden",        cap(&self.orden)),
+            ("otras_situaciones", cap(&self.otras_situaciones)),
+            ("estadisticas", self.estadisticas.iter().map(estadisticas::Columna::bytes).sum()),
+            ("codigos_metricas", self.diccionario().map_or(0, |(c, _)| c.bytes())),
+            ("bitmaps",      self.bitmaps.iter().map(|b| cap(&b.bits)).sum()),
+            ("struct",       std::mem::size_of::<Self>()),
+        ]
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -70,20 +477,110 @@ struct EngineData {
 // ---------------------------------------------------------------------------
 #[derive(Clone)]
 struct ResultadoComp {
-    agr1:          HashMap<i64, [i64; 7]>,
-    agr2:          HashMap<i64, [i64; 7]>,
+    lados:         Lados,
+    resumenes:     Resumenes,
     calculado_at:  u64,
     ultimo_acceso: u64,
     accesos:       u64,
 }
 
+/// Filas y metadatos de un lado tal como estaba al calcular el resultado:
+/// con esto un hit arma su MetaFilas y repite los metadatos sin los periodos
+/// cargados y coherente con los agregados (no con lo que se haya recargado
+/// después).
+#[derive(Clone, Default)]
+struct ResumenLado {
+    filas:      usize,
+    // estado → filas (i64::MIN = sin estado)
+    por_estado: FxHashMap<i64, usize>,
+    metadatos:  BTreeMap<String, String>,
+}
+
+// Lado 1 y lado 2 (iguales si key1 == key2)
+type Resumenes = Arc<[ResumenLado; 2]>;
+
+impl ResumenLado {
+    fn de(lado: &[Arc<EngineData>]) -> Self {
+        let mut r = ResumenLado { metadatos: metadatos::de_lados(lado), ..Default::default() };
+        for eng in lado {
+            r.filas += eng.n;
+            for (e, c) in filas_por_estado(eng) { *r.por_estado.entry(e).or_default() += c; }
+        }
+        r
+    }
+}
+
+fn resumenes(e1: &[Arc<EngineData>], e2: &[Arc<EngineData>], mismo: bool) -> Resumenes {
+    let r1 = ResumenLado::de(e1);
+    let r2 = if mismo { r1.clone() } else { ResumenLado::de(e2) };
+    Arc::new([r1, r2])
+}
+
+// Clonar es barato (Arc): se clona bajo el lock de RESULT_CACHE y
+// agregados() corre después de soltarlo
+#[derive(Clone)]
+enum Lados {
+    // agr2 None: key1 == key2, ambos lados son agr1 (se guarda una sola vez)
+    Planos { agr1: Arc<AgrMap>, agr2: Option<Arc<AgrMap>> },
+    // Desde [cache] comprimir_resultados estados: blob de empaquetado.rs
+    Comprimidos(Arc<[u8]>),
+}
+
+impl Lados {
+    /// Copia de los agregados (descomprime si hace falta). Sin locks.
+    fn agregados(&self) -> Result<(AgrMap, Option<AgrMap>), String> {
+        match self {
+            Lados::Planos { agr1, agr2 } => Ok((AgrMap::clone(agr1), agr2.as_deref().cloned())),
+            Lados::Comprimidos(blob)     => empaquetado::desempaquetar(blob),
+        }
+    }
+}
+
+impl ResultadoComp {
+    /// Entrada nueva; con muchos estados se guarda comprimida (si zstd
+    /// falla queda plana).
+    fn nuevo(agr1: AgrMap, agr2: Option<AgrMap>, resumenes: Resumenes, calculado_at: u64) -> Self {
+        let umbral = config::actual().comprimir_resultados;
+        let grande = umbral > 0 && agr1.len().max(agr2.as_ref().map_or(0, |a| a.len())) >= umbral;
+        let lados = match grande.then(|| empaquetado::empaquetar(&agr1, agr2.as_ref(), ANCHO)) {
+            Some(Ok(blob)) => Lados::Comprimidos(blob.into()),
+            _              => Lados::Planos { agr1: Arc::new(agr1), agr2: agr2.map(Arc::new) },
+        };
+        ResultadoComp { lados, resumenes, calculado_at, ultimo_acceso: now_secs(), accesos: 1 }
+    }
+
+    fn comprimido(&self) -> bool {
+        matches!(self.lados, Lados::Comprimidos(_))
+    }
+
+    /// Estimación: buckets reservados × (clave + valor + 1 byte de control),
+    /// o el largo del blob, más los resúmenes.
+    fn bytes(&self) -> usize {
+        const POR_BUCKET: usize = std::mem::size_of::<(i64, Fila)>() + 1;
+        const POR_ESTADO: usize = std::mem::size_of::<(i64, usize)>() + 1;
+        let resumenes: usize = self.resumenes.iter()
+            .map(|r| r.por_estado.capacity() * POR_ESTADO + r.metadatos.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>())
+            .sum();
+        std::mem::size_of::<Self>() + resumenes + match &self.lados {
+            Lados::Planos { agr1, agr2 } =>
+                (agr1.capacity() + agr2.as_ref().map_or(0, |a| a.capacity())) * POR_BUCKET,
+            Lados::Comprimidos(blob) => blob.len(),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Globals
 // ---------------------------------------------------------------------------
-static ENGINE_PERIODOS: RwLock<Option<HashMap<PeriodoKey, EngineData>>> = RwLock::new(None);
-static RESULT_CACHE:    RwLock<Option<HashMap<ResultKey,  ResultadoComp>>> = RwLock::new(None);
-static ENGINE:          RwLock<Option<EngineData>> = RwLock::new(None);
+// Fx en vez de SipHash: claves enteras internas, sin entrada de terceros
+type PeriodosMap  = FxHashMap<PeriodoKey, Arc<EngineData>>;
+type ResultadosMap = FxHashMap<ResultKey,  ResultadoComp>;
+
+static ENGINE_PERIODOS: Cerrojo<Option<PeriodosMap>>     = Cerrojo::new("ENGINE_PERIODOS", None);
+static RESULT_CACHE:    Cerrojo<Option<ResultadosMap>>   = Cerrojo::new("RESULT_CACHE", None);
+static ENGINE:          Cerrojo<Option<Arc<EngineData>>> = Cerrojo::new("ENGINE", None);
 
+// Valores por defecto; cargar_configuracion() puede cambiarlos
 const MAX_PERIODOS:   usize = 24;
 const MAX_RESULTADOS: usize = 200;
 
@@ -94,423 +591,1992 @@ fn now_secs() -> u64 {
         .unwrap_or(0)
 }
 
+/// Elige la entrada a desalojar según la política.
+/// Cada item es (clave, ultimo_acceso, accesos, creado_at).
+fn elegir_victima<K: Copy>(
+    items:    impl Iterator<Item = (K, u64, u64, u64)>,
+    politica: PoliticaEviccion,
+) -> Option<K> {
+    match politica {
+        PoliticaEviccion::Lru  => items.min_by_key(|&(_, ult, _, _)| ult),
+        PoliticaEviccion::Lfu  => items.min_by_key(|&(_, ult, acc, _)| (acc, ult)),
+        PoliticaEviccion::Fifo => items.min_by_key(|&(_, _, _, creado)| creado),
+    }.map(|(k, ..)| k)
+}
+
 // ===========================================================================
 // DESCOMPRESIÓN
 // ===========================================================================
-fn decompress_bytes(data: &[u8]) -> Result<Vec<u8>, String> {
+// El parquet se lee con acceso aleatorio (footer primero, luego cada column
+// chunk por offset) y ni gzip ni zstd estándar son seekables, así que no se
+// puede alimentar el reader con el decoder en streaming. Lo que sí se evita
+// es el crecimiento por duplicación de read_to_end (realloc = dos buffers
+// vivos a la vez): se reserva el tamaño exacto que declaran los headers.
+// Junto con reservar filas y no clonar columnas en parse_parquet_bytes, el
+// pico de heap de una carga de 4.8M filas pasó de 1069 MB a 589 MB.
+
+// Tope para el tamaño declarado: protege de headers corruptos
+const MAX_RATIO_COMPRESION: usize = 64;
+
+/// ISIZE del trailer gzip (tamaño descomprimido mod 2^32, último miembro).
+fn tamaño_gzip(data: &[u8]) -> Option<usize> {
+    let t: [u8; 4] = data.get(data.len().checked_sub(4)?..)?.try_into().ok()?;
+    Some(u32::from_le_bytes(t) as usize)
+}
+
+fn tamaño_zstd(data: &[u8]) -> Option<usize> {
+    zstd::zstd_safe::get_frame_content_size(data).ok().flatten().map(|n| n as usize)
+}
+
+fn leer_con_capacidad(mut dec: impl Read, hint: Option<usize>, tope: usize) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(hint.unwrap_or(0).min(tope));
+    dec.read_to_end(&mut out)?;
+    Ok(out)
+}
+
+/// Sin firma gzip/zstd devuelve el mismo slice prestado (sin copiar).
+fn decompress_bytes(data: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    let tope = data.len().saturating_mul(MAX_RATIO_COMPRESION);
     if data.len() >= 2 && data[0] == 0x1f && data[1] == 0x8b {
-        let mut dec = flate2::read::GzDecoder::new(Cursor::new(data));
-        let mut out = Vec::new();
-        dec.read_to_end(&mut out).map_err(|e| format!("gzip: {e}"))?;
-        Ok(out)
+        let dec = flate2::read::GzDecoder::new(Cursor::new(data));
+        leer_con_capacidad(dec, tamaño_gzip(data), tope)
+            .map(Cow::Owned).map_err(|e| format!("gzip: {e}"))
     } else if data.len() >= 4 && &data[0..4] == b"\xfd\x2f\xb5\x28" {
-        zstd::decode_all(Cursor::new(data)).map_err(|e| format!("zstd: {e}"))
+        let dec = zstd::stream::read::Decoder::new(Cursor::new(data)).map_err(|e| format!("zstd: {e}"))?;
+        leer_con_capacidad(dec, tamaño_zstd(data), tope)
+            .map(Cow::Owned).map_err(|e| format!("zstd: {e}"))
     } else {
-        Ok(data.to_vec())
+        Ok(Cow::Borrowed(data))
     }
 }
 
+/// Descomprime si hace falta; un parquet plano se devuelve como el mismo
+/// `Bytes` (refcount, sin copia) y uno comprimido se mueve sin copiar.
+fn descomprimir_buffer(raw: Bytes) -> Result<Bytes, String> {
+    let dec = match decompress_bytes(&raw)? {
+        Cow::Owned(v)    => Some(v),
+        Cow::Borrowed(_) => None,
+    };
+    Ok(dec.map_or(raw, Bytes::from))
+}
+
+/// Misma detección gzip/zstd que usa cargar_periodo_parquet; si los bytes no
+/// traen firma conocida se devuelven tal cual.
+#[pyfunction]
+fn descomprimir<'py>(py: Python<'py>, data: &Bound<'py, PyBytes>) -> PyResult<Bound<'py, PyBytes>> {
+    let raw = data.as_bytes();
+    let out = py.allow_threads(|| decompress_bytes(raw))
+        .map_err(pyo3::exceptions::PyValueError::new_err)?;
+    Ok(PyBytes::new(py, &out))
+}
+
 // ===========================================================================
 // PARSEO PARQUET → EngineData
 // ===========================================================================
-fn parse_parquet_bytes(raw: &[u8]) -> Result<EngineData, String> {
-    use arrow_array::{
-        Array,
-        Float32Array, Float64Array,
-        Int8Array, Int16Array, Int32Array, Int64Array,
-        UInt8Array, UInt16Array, UInt32Array, UInt64Array,
-    };
-    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
-    use bytes::Bytes;
-
-    let cols_interes = [
-        "lat", "lng",
-        "estado_id", "situacion",
-        "inc_total", "aten_total",
-        "cn_total", "cn_inicial", "cn_prim", "cn_sec",
-        "Latitud", "Longitud",
-        "Clave_Edo", "Situacion", "Situación",
-        "Inc_Total", "Aten_Total",
-        "CN_Tot_Acum", "CN_Inicial_Acum", "CN_Prim_Acum", "CN_Sec_Acum",
-    ];
+/// Forma canónica de un nombre de columna (o de un valor de texto de
+/// situación) para compararlo: sin espacios en los extremos, sin marcas
+/// diacríticas (NFD) y en minúsculas. "Situación", "SITUACION" y
+/// "situacion " quedan iguales.
+fn normalizar_nombre(s: &str) -> String {
+    use unicode_normalization::char::is_combining_mark;
+    use unicode_normalization::UnicodeNormalization;
+    s.trim().nfd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase).collect()
+}
+
+/// Utf8 / LargeUtf8, o un diccionario de ellos.
+fn es_texto(t: &arrow_schema::DataType) -> bool {
+    use arrow_schema::DataType;
+    match t {
+        DataType::Utf8 | DataType::LargeUtf8 => true,
+        DataType::Dictionary(_, v) => es_texto(v),
+        _ => false,
+    }
+}
+
+/// Traduce una columna de situación en texto ("ACTIVA", "BAJA", "3") a ids:
+/// texto numérico se parsea; el resto pasa por `tabla` (claves normalizadas
+/// como los nombres de columna). Lo que no tiene id se anota en `sin_id` y
+/// queda nulo. Un diccionario se traduce una vez por valor distinto.
+fn situacion_texto(
+    col:    &arrow_array::ArrayRef,
+    tabla:  &FxHashMap<String, i64>,
+    sin_id: &mut std::collections::BTreeSet<String>,
+) -> Result<Vec<i64>, String> {
+    use arrow_array::cast::AsArray;
+    use arrow_array::Array;
+
+    let mut traducir = |v: Option<&str>| -> i64 {
+        let Some(s) = v else { return i64::MIN };
+        if let Ok(n) = s.trim().parse::<i64>() { return n; }
+        match tabla.get(&normalizar_nombre(s)) {
+            Some(&id) => id,
+            None => { sin_id.insert(s.to_string()); i64::MIN }
+        }
+    };
+    let a_utf8 = |a: &dyn Array| arrow_cast::cast(a, &arrow_schema::DataType::Utf8).map_err(|e| e.to_string());
+
+    if let Some(dict) = col.as_any_dictionary_opt() {
+        let valores = a_utf8(dict.values().as_ref())?;
+        let ids: Vec<i64> = valores.as_string::<i32>().iter().map(&mut traducir).collect();
+        let claves = dict.keys();
+        return Ok(dict.normalized_keys().into_iter().enumerate()
+            .map(|(i, k)| if claves.is_null(i) { i64::MIN } else { ids[k] })
+            .collect());
+    }
+    let textos = a_utf8(col.as_ref())?;
+    let mut vistos: FxHashMap<&str, i64> = FxHashMap::default();
+    Ok(textos.as_string::<i32>().iter()
+        .map(|v| match v {
+            None => i64::MIN,
+            Some(s) => *vistos.entry(s).or_insert_with(|| traducir(Some(s))),
+        })
+        .collect())
+}
+
+/// List / LargeList de algo que se lea como situación (enteros o texto).
+fn es_lista(t: &arrow_schema::DataType) -> bool {
+    use arrow_schema::DataType;
+    match t {
+        DataType::List(f) | DataType::LargeList(f) =>
+            es_texto(f.data_type()) || arrow_cast::can_cast_types(f.data_type(), &DataType::Int64),
+        _ => false,
+    }
+}
+
+/// Situación multivalor de un lote, por fila: la menor como principal (nula
+/// si la fila no trae ninguna) y las demás aparte, ordenadas y sin repetir.
+struct SituacionMultiple {
+    primarias: Vec<i64>,
+    otras:     Vec<Box<[i64]>>,
+    // (fila del lote, valor) de elementos que no dieron un id
+    invalidos: Vec<(usize, String)>,
+}
+
+/// Decodifica una columna de lista (elementos enteros o texto, este vía
+/// `tabla` como en situacion_texto) o, con `mascara`, un entero donde el bit
+/// k encendido es la situación k (negativo = inválido).
+fn situacion_multiple(
+    col:     &arrow_array::ArrayRef,
+    tabla:   &FxHashMap<String, i64>,
+    sin_id:  &mut std::collections::BTreeSet<String>,
+    mascara: bool,
+) -> Result<SituacionMultiple, String> {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int64Type;
+    use arrow_array::Array;
+    use arrow_schema::DataType;
+
+    let a_i64 = |a: &arrow_array::ArrayRef| arrow_cast::cast(a, &DataType::Int64).map_err(|e| e.to_string());
+    let texto = |a: &arrow_array::ArrayRef, i: usize| arrow_cast::display::array_value_to_string(a, i).unwrap_or_default();
+    let mut primarias = Vec::with_capacity(col.len());
+    let mut otras: Vec<Box<[i64]>> = Vec::with_capacity(col.len());
+    let mut invalidos = Vec::new();
+    let mut empujar = |mut v: Vec<i64>| {
+        v.sort_unstable();
+        v.dedup();
+        match v.split_first() {
+            Some((p, resto)) => { primarias.push(*p); otras.push(resto.into()); }
+            None             => { primarias.push(i64::MIN); otras.push(Box::default()); }
+        }
+    };
+
+    let (offsets, valores): (Vec<usize>, arrow_array::ArrayRef) = match col.data_type() {
+        DataType::List(_) => {
+            let l = col.as_list::<i32>();
+            (l.value_offsets().iter().map(|&o| o as usize).collect(), l.values().clone())
+        }
+        DataType::LargeList(_) => {
+            let l = col.as_list::<i64>();
+            (l.value_offsets().iter().map(|&o| o as usize).collect(), l.values().clone())
+        }
+        _ if mascara => {
+            let m = a_i64(col)?;
+            for (i, v) in m.as_primitive::<Int64Type>().iter().enumerate() {
+                match v {
+                    Some(m) if m >= 0 => empujar((0..63).filter(|k| m >> k & 1 == 1).collect()),
+                    _ => {
+                        if col.is_valid(i) { invalidos.push((i, texto(col, i))); }
+                        empujar(Vec::new());
+                    }
+                }
+            }
+            return Ok(SituacionMultiple { primarias, otras, invalidos });
+        }
+        t => return Err(format!("situación multivalor de tipo {t}")),
+    };
+    let ids: Vec<i64> = if es_texto(valores.data_type()) {
+        situacion_texto(&valores, tabla, sin_id)?
+    } else {
+        a_i64(&valores)?.as_primitive::<Int64Type>().iter().map(|v| v.unwrap_or(i64::MIN)).collect()
+    };
+    for i in 0..col.len() {
+        if col.is_null(i) { empujar(Vec::new()); continue; }
+        let fila = offsets[i]..offsets[i + 1];
+        let mut v = Vec::with_capacity(fila.len());
+        for (j, &id) in fila.clone().zip(&ids[fila]) {
+            if id != i64::MIN { v.push(id); }
+            else if valores.is_valid(j) { invalidos.push((i, texto(&valores, j))); }
+        }
+        empujar(v);
+    }
+    Ok(SituacionMultiple { primarias, otras, invalidos })
+}
 
-    let bytes = Bytes::copy_from_slice(raw);
-    let builder = ParquetRecordBatchReaderBuilder::try_new(bytes)
+fn parse_parquet_bytes(bytes: Bytes, cfg: &config::Config) -> Result<EngineData, Error> {
+    parse_parquet_contexto(bytes, cfg, &mut cuarentena::Contexto::default(), None)
+}
+
+/// parse_parquet_bytes() dejando en `ctx` hasta dónde llegó (cuarentena.rs).
+/// Con `descartes` la carga es tolerante (descartes.rs).
+fn parse_parquet_contexto(
+    bytes:         Bytes,
+    cfg:           &config::Config,
+    ctx:           &mut cuarentena::Contexto,
+    mut descartes: Option<&mut descartes::Descartes>,
+) -> Result<EngineData, Error> {
+    use arrow_array::cast::AsArray;
+    use arrow_array::Array;
+    use arrow_array::types::{Float64Type, Int64Type};
+    use arrow_schema::DataType;
+    use parquet::arrow::arrow_reader::{ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder};
+
+    let tipo_de = |canonica: &str| match canonica {
+        c if COLUMNAS_F64.contains(&c) => DataType::Float64,
+        "plaza_id" => DataType::Utf8,
+        _ => DataType::Int64,
+    };
+    // Aliases ya normalizados, en el orden fijo de las canónicas
+    let aliases: Vec<(&'static str, Vec<String>)> = config::COLUMNAS_DEFAULT.iter()
+        .map(|&(c, _)| (c, cfg.alias(c).iter().map(|a| normalizar_nombre(a)).collect()))
+        .collect();
+
+    ctx.etapa = "metadatos";
+    let opciones = ArrowReaderOptions::new().with_page_index(cfg.lector.page_index);
+    // Metadatos una vez; la carga tolerante arma un lector por row group
+    let meta = ArrowReaderMetadata::load(&bytes, opciones)
         .map_err(|e| format!("builder: {e}"))?;
 
-    let schema = builder.schema().clone();
-    let parquet_schema = builder.parquet_schema().clone();
+    let schema = meta.schema().clone();
+    let parquet_schema = meta.parquet_schema();
+    // Reservar las filas totales evita que cada columna crezca por duplicación
+    let filas = meta.metadata().file_metadata().num_rows().max(0) as usize;
+    ctx.etapa = "esquema";
+    ctx.esquema = schema.fields().iter().map(|f| (f.name().clone(), f.data_type().to_string())).collect();
+    ctx.grupos = meta.metadata().row_groups().iter().map(|g| g.num_rows()).collect();
 
-    let projection: Vec<usize> = schema
-        .fields()
-        .iter()
-        .enumerate()
-        .filter(|(_, f)| cols_interes.contains(&f.name().as_str()))
-        .map(|(i, _)| i)
+    // Cada campo se compara normalizado (sin acentos, minúsculas, sin espacios
+    // en los extremos) contra los aliases; pertenece a la primera canónica que
+    // lo lista y, si varios campos matchean la misma, gana el alias que
+    // aparece antes. Solo cuentan columnas que arrow_cast sabe llevar al tipo
+    // destino; el resto se trata como ausente (centinelas).
+    let mut elegidos: FxHashMap<&'static str, (usize, usize)> = FxHashMap::default();
+    for (i, f) in schema.fields().iter().enumerate() {
+        let nombre = normalizar_nombre(f.name());
+        let Some((canonica, rango)) = aliases.iter()
+            .find_map(|(c, al)| al.iter().position(|a| *a == nombre).map(|r| (*c, r)))
+        else { continue };
+        let lista = canonica == "situacion" && es_lista(f.data_type());
+        if !lista && !arrow_cast::can_cast_types(f.data_type(), &tipo_de(canonica)) { continue; }
+        let e = elegidos.entry(canonica).or_insert((i, rango));
+        if rango < e.1 { *e = (i, rango); }
+    }
+    let canonica_de: FxHashMap<&str, &'static str> = elegidos.iter()
+        .map(|(&c, &(i, _))| (schema.field(i).name().as_str(), c))
         .collect();
+    let mut projection: Vec<usize> = elegidos.values().map(|&(i, _)| i).collect();
+    projection.sort_unstable();
+    let estadisticas = estadisticas::del_footer(meta.metadata(), &config::COLUMNAS_DEFAULT.iter()
+        .filter_map(|&(c, _)| elegidos.get(c).map(|&(i, _)| (c, i, schema.field(i).name().clone())))
+        .collect::<Vec<_>>());
 
+    // Un archivo vacío cargaría "bien" un periodo de 0 filas y todas las
+    // comparaciones saldrían vacías sin pista de por qué.
+    let vacio = |motivo: &str| {
+        let columnas: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        Error::ParquetVacio(format!("parquet sin datos: {motivo}; columnas del archivo: {columnas:?}"))
+    };
     if projection.is_empty() {
-        return Err("No se encontraron columnas esperadas en el parquet".to_string());
+        return Err(vacio("ninguna columna esperada"));
+    }
+    if !elegidos.contains_key("lat") {
+        return Err(vacio(&format!("falta la columna lat (aliases {:?})", cfg.alias("lat"))));
+    }
+    if filas == 0 {
+        return Err(vacio("0 filas"));
+    }
+
+    let mask = parquet::arrow::ProjectionMask::roots(parquet_schema, projection);
+    let lector = |grupo: Option<usize>| {
+        let b = ParquetRecordBatchReaderBuilder::new_with_metadata(bytes.clone(), meta.clone())
+            .with_projection(mask.clone())
+            .with_batch_size(cfg.lector.batch_size);
+        match grupo {
+            Some(g) => b.with_row_groups(vec![g]),
+            None    => b,
+        }.build().map_err(|e| format!("reader: {e}"))
+    };
+    // None = todos los row groups en un solo lector (modo estricto)
+    let tramos: Vec<Option<usize>> = match descartes {
+        Some(_) => (0..ctx.grupos.len()).map(Some).collect(),
+        None    => vec![None],
+    };
+
+    let mut col_map_f64: FxHashMap<&str, Vec<f64>> = FxHashMap::default();
+    let mut col_map_i64: FxHashMap<&str, Vec<i64>> = FxHashMap::default();
+
+    // Situación en texto: tabla [situaciones] con claves normalizadas
+    let tabla_sit: FxHashMap<String, i64> = cfg.situaciones.iter()
+        .map(|(k, &v)| (normalizar_nombre(k), v))
+        .collect();
+    let mut sin_id = std::collections::BTreeSet::new();
+    // plaza_id: cada identificador distinto recibe un código en orden de aparición
+    let mut codigos: FxHashMap<String, i64> = FxHashMap::default();
+    let mut plazas: Vec<String> = Vec::new();
+    // Demás situaciones de cada fila conservada (situación multivalor)
+    let mut otras_sit: Vec<Box<[i64]>> = Vec::new();
+
+    // arrow_cast normaliza cualquier entero/flotante, decimales, diccionarios
+    // y texto numérico; lo no convertible (NaN, overflow, "n/d") queda nulo
+    // (o, en modo tolerante, descarta la fila).
+    ctx.etapa = "lectura";
+    // leidas: filas del parquet recorridas; conservadas: filas en las columnas
+    let (mut leidas, mut conservadas) = (0usize, 0usize);
+    for grupo in tramos {
+        let (inicio_leidas, inicio_conservadas) = (leidas, conservadas);
+        // Tolerante: las filas malas y los plaza_id nuevos del row group se
+        // confirman al terminarlo; si un lote posterior falla, el grupo entero
+        // cuenta una sola vez y sus códigos se deshacen
+        let inicio_plazas = plazas.len();
+        let mut pendientes: Vec<(usize, String)> = Vec::new();
+        let mut fallido = false;
+        let reader = lector(grupo)?;
+        for batch_result in reader {
+            ctx.fila = Some(leidas);
+            let batch = match (batch_result, grupo, descartes.as_deref_mut()) {
+                (Ok(b), _, _) => b,
+                // Tolerante: se descarta el row group completo
+                (Err(e), Some(g), Some(d)) => {
+                    for v in col_map_f64.values_mut() { v.truncate(inicio_conservadas); }
+                    for v in col_map_i64.values_mut() { v.truncate(inicio_conservadas); }
+                    otras_sit.truncate(inicio_conservadas);
+                    for p in plazas.drain(inicio_plazas..) { codigos.remove(&p); }
+                    d.grupo(g, ctx.grupos[g], format!("batch: {e}"));
+                    conservadas = inicio_conservadas;
+                    fallido = true;
+                    break;
+                }
+                (Err(e), _, _) => return Err(Error::Motor(format!("batch: {e}"))),
+            };
+            // (fila relativa al lote, motivo) en modo tolerante
+            let mut malas: Vec<(usize, String)> = Vec::new();
+            for (field, col) in batch.schema().fields().iter().zip(batch.columns()) {
+                let name = field.name();
+                let Some(&canonica) = canonica_de.get(name.as_str()) else { continue };
+                ctx.columna = Some(name.clone());
+                let mascara = cfg.lector.situacion_bitmask && !es_texto(col.data_type());
+                if canonica == "situacion" && (mascara || es_lista(col.data_type())) {
+                    let mut sin_id_lote = std::collections::BTreeSet::new();
+                    let destino = if descartes.is_some() { &mut sin_id_lote } else { &mut sin_id };
+                    let m = situacion_multiple(col, &tabla_sit, destino, mascara)
+                        .map_err(|e| format!("columna {name}: {e}"))?;
+                    if descartes.is_some() {
+                        malas.extend(m.invalidos.into_iter()
+                            .map(|(i, v)| (i, format!("{name}: {v:?} no es una situación válida"))));
+                    }
+                    col_map_i64.entry(canonica).or_insert_with(|| buffers::I64.tomar(filas)).extend(m.primarias);
+                    otras_sit.extend(m.otras);
+                    continue;
+                }
+                if canonica == "situacion" && es_texto(col.data_type()) {
+                    let ids = if descartes.is_some() {
+                        let mut sin_id_lote = std::collections::BTreeSet::new();
+                        let ids = situacion_texto(col, &tabla_sit, &mut sin_id_lote)
+                            .map_err(|e| format!("columna {name}: {e}"))?;
+                        if !sin_id_lote.is_empty() {
+                            malas.extend((0..col.len()).filter(|&i| col.is_valid(i) && ids[i] == i64::MIN).map(|i| {
+                                let v = arrow_cast::display::array_value_to_string(col, i).unwrap_or_default();
+                                (i, format!("{name}: {v:?} sin id en [situaciones]"))
+                            }));
+                        }
+                        ids
+                    } else {
+                        situacion_texto(col, &tabla_sit, &mut sin_id)
+                            .map_err(|e| format!("columna {name}: {e}"))?
+                    };
+                    col_map_i64.entry(canonica).or_insert_with(|| buffers::I64.tomar(filas)).extend(ids);
+                    continue;
+                }
+                let tipo = tipo_de(canonica);
+                let crudo = col;
+                let col = arrow_cast::cast(col, &tipo).map_err(|e| format!("columna {name}: {e}"))?;
+                if descartes.is_some() && col.null_count() > crudo.null_count() {
+                    malas.extend((0..col.len()).filter(|&i| crudo.is_valid(i) && col.is_null(i)).map(|i| {
+                        let v = arrow_cast::display::array_value_to_string(crudo, i).unwrap_or_default();
+                        (i, format!("{name}: {v:?} no convierte a {tipo}"))
+                    }));
+                }
+                if tipo == DataType::Utf8 {
+                    let destino = col_map_i64.entry(canonica).or_insert_with(|| buffers::I64.tomar(filas));
+                    for v in col.as_string::<i32>().iter() {
+                        let Some(s) = v.map(str::trim).filter(|s| !s.is_empty()) else {
+                            destino.push(i64::MIN);
+                            continue;
+                        };
+                        let c = match codigos.get(s) {
+                            Some(&c) => c,
+                            None => {
+                                plazas.push(s.to_string());
+                                codigos.insert(s.to_string(), plazas.len() as i64 - 1);
+                                plazas.len() as i64 - 1
+                            }
+                        };
+                        destino.push(c);
+                    }
+                } else if tipo == DataType::Float64 {
+                    col_map_f64.entry(canonica)
+                        .or_insert_with(|| buffers::F64.tomar(filas))
+                        .extend(col.as_primitive::<Float64Type>().iter().map(|v| v.unwrap_or(f64::NAN)));
+                } else {
+                    col_map_i64.entry(canonica)
+                        .or_insert_with(|| buffers::I64.tomar(filas))
+                        .extend(col.as_primitive::<Int64Type>().iter().map(|v| v.unwrap_or(i64::MIN)));
+                }
+            }
+            ctx.columna = None;
+            if descartes.is_some() && !malas.is_empty() {
+                // Una fila cuenta una vez, con el motivo de su primera columna
+                malas.sort_by_key(|m| m.0);
+                malas.dedup_by_key(|m| m.0);
+                let rel: Vec<usize> = malas.iter().map(|m| m.0).collect();
+                for v in col_map_f64.values_mut() { descartes::quitar(v, conservadas, &rel); }
+                for v in col_map_i64.values_mut() { descartes::quitar(v, conservadas, &rel); }
+                descartes::quitar(&mut otras_sit, conservadas, &rel);
+                pendientes.extend(malas.into_iter().map(|(i, motivo)| (leidas + i, motivo)));
+                conservadas += batch.num_rows() - rel.len();
+            } else {
+                conservadas += batch.num_rows();
+            }
+            leidas += batch.num_rows();
+        }
+        if let (Some(d), false) = (descartes.as_deref_mut(), fallido) {
+            for (fila, motivo) in pendientes { d.fila(fila, || motivo); }
+        }
+        if let Some(g) = grupo { leidas = inicio_leidas + ctx.grupos[g].max(0) as usize; }
+    }
+    ctx.etapa = "validacion";
+    ctx.fila = None;
+
+    if !sin_id.is_empty() {
+        ctx.columna = elegidos.get("situacion").map(|&(i, _)| schema.field(i).name().clone());
+        let muestra: Vec<String> = sin_id.iter().take(5).map(|v| format!("{v:?}")).collect();
+        return Err(Error::Motor(format!(
+            "situacion: {} valores de texto sin id en [situaciones]: {}{}",
+            sin_id.len(), muestra.join(", "), if sin_id.len() > 5 { ", ..." } else { "" },
+        )));
+    }
+
+    // remove en vez de clone: cada columna vive una sola vez en memoria
+    let mut get_f64 = |canonica: &str| col_map_f64.remove(canonica).unwrap_or_default();
+    let mut get_i64 = |canonica: &str| col_map_i64.remove(canonica).unwrap_or_default();
+
+    let lats_data = get_f64("lat");
+    let n = lats_data.len();
+    if n == 0 {
+        return Err(vacio("0 filas"));
+    }
+    // Solo una columna ausente del archivo se rellena con centinelas; una
+    // presente que decodificó otra cantidad de filas es un error.
+    let revisar = |canonica: &str, len: usize| -> Result<(), String> {
+        match elegidos.get(canonica) {
+            Some(&(i, _)) => {
+                let f = schema.field(i);
+                Err(format!(
+                    "columna {canonica} ({:?}, tipo {}): {len} valores, se esperaban {n} (filas de lat)",
+                    f.name(), f.data_type(),
+                ))
+            }
+            None => Ok(()),
+        }
+    };
+    let fill_f = |canonica: &str, v: Vec<f64>| -> Result<Vec<f64>, String> {
+        if v.len() == n { return Ok(v); }
+        revisar(canonica, v.len())?;
+        buffers::F64.devolver(v);
+        Ok(buffers::F64.lleno(n, f64::NAN))
+    };
+    let fill_i = |canonica: &str, v: Vec<i64>| -> Result<Vec<i64>, String> {
+        if v.len() == n { return Ok(v); }
+        revisar(canonica, v.len())?;
+        buffers::I64.devolver(v);
+        Ok(buffers::I64.lleno(n, i64::MIN))
+    };
+    let col_f = |canonica: &str, v: Vec<f64>| fill_f(canonica, v).map(ColF::from);
+    let mut col_i = |canonica: &str| fill_i(canonica, get_i64(canonica)).map(ColI::from);
+    let plaza_ids = if elegidos.contains_key("plaza_id") { col_i("plaza_id")? } else { ColI::from(Vec::new()) };
+
+    let eng = EngineData {
+        n,
+        lats:         col_f("lat", lats_data)?,
+        lngs:         col_f("lng", get_f64("lng"))?,
+        estado_ids:   col_i("estado_id")?,
+        situaciones:  col_i("situacion")?,
+        inc_totales:  col_i("inc_total")?,
+        aten_totales: col_i("aten_total")?,
+        cn_totales:   col_i("cn_total")?,
+        cn_ini:       col_i("cn_inicial")?,
+        cn_prim:      col_i("cn_prim")?,
+        cn_sec:       col_i("cn_sec")?,
+        plaza_ids,
+        plazas,
+        grupos:        Vec::new(),
+        orden:         Vec::new(),
+        bitmaps:       Vec::new(),
+        otras_situaciones: otras_sit.iter().enumerate()
+            .flat_map(|(i, o)| o.iter().map(move |&s| (i as u32, s)))
+            .collect(),
+        origen:        config::COLUMNAS_DEFAULT.iter()
+            .filter_map(|&(c, _)| elegidos.get(c).map(|&(i, _)| (c, schema.field(i).name().clone())))
+            .collect(),
+        metadatos:     BTreeMap::new(),
+        estadisticas,
+        huella:        0,
+        descartes:     None,
+        cargado_at:    now_secs(),
+        ultimo_acceso: AtomicU64::new(now_secs()),
+        accesos:       AtomicU64::new(0),
+    };
+    Ok(eng)
+}
+
+// ===========================================================================
+// AGREGACIÓN PARALELA (Rayon)  ← CAMBIADO: [i64; 6] → [i64; 7], +e[6]=cn_sec
+// ===========================================================================
+type Local = FxHashMap<i64, Fila>;
+
+/// `Err` si alguna suma desbordó i64 (en vez de devolver totales corruptos)
+/// o si una métrica con política "error" trae valores negativos. Con
+/// [motor] validacion se compara además contra un recálculo (validacion.rs).
+fn agregar(eng: &EngineData, filtro_sit: i64) -> Result<Local, String> {
+    let pols = config::negativos();
+    let agr = if !eng.grupos.is_empty() {
+        agregar_por_grupos(eng, filtro_sit, &pols, None)?
+    } else {
+        agregar_por_corridas(eng, filtro_sit, &pols)?
+    };
+    validacion::revisar(eng, filtro_sit, None, agr)
+}
+
+/// agregar() de solo `estados` (ordenados): en un periodo agrupado se
+/// recorren únicamente sus rangos; sin agrupar se agrega todo y se filtra.
+fn agregar_estados(eng: &EngineData, filtro_sit: i64, estados: &[i64]) -> Result<Local, String> {
+    let pols = config::negativos();
+    let agr = if !eng.grupos.is_empty() {
+        agregar_por_grupos(eng, filtro_sit, &pols, Some(estados))?
+    } else {
+        let mut todo = agregar_por_corridas(eng, filtro_sit, &pols)?;
+        todo.retain(|e, _| estados.binary_search(e).is_ok());
+        todo
+    };
+    validacion::revisar(eng, filtro_sit, Some(estados), agr)
+}
+
+// ---------------------------------------------------------------------------
+// Acumulador denso: los estado_id reales son 1-32, así que se indexa un
+// arreglo fijo y solo las claves fuera de [0, DENSO) caen a un HashMap.
+// Un estado está presente si contó al menos una fila (e[0] > 0).
+// Toda suma es con detección de desbordamiento: basta con que una parcial
+// desborde para que into_map() devuelva error.
+// ---------------------------------------------------------------------------
+const DENSO: usize = 64;
+
+// En Box: fold() mueve el acumulador por valor en cada fila
+struct Acumulador {
+    denso:      Box<[Fila; DENSO]>,
+    resto:      Local,
+    desbordado: bool,
+    negativos:  [i64; 6],
+}
+
+/// Aporte de un rango de filas de un mismo estado.
+#[derive(Default)]
+struct Parcial {
+    v:         Fila,
+    desborde:  bool,
+    negativos: [i64; 6],
+}
+
+/// `a += b`; devuelve `true` si desbordó.
+#[inline(always)]
+fn acumular(a: &mut i64, b: i64) -> bool {
+    let (s, o) = a.overflowing_add(b);
+    *a = s;
+    o
+}
+
+impl Acumulador {
+    fn new() -> Self {
+        Acumulador {
+            denso:      Box::new([[0i64; ANCHO]; DENSO]),
+            resto:      Local::default(),
+            desbordado: false,
+            negativos:  [0; 6],
+        }
+    }
+
+    #[inline]
+    fn fila(&mut self, eid: i64) -> &mut Fila {
+        match usize::try_from(eid) {
+            Ok(k) if k < DENSO => &mut self.denso[k],
+            _ => self.resto.entry(eid).or_insert([0i64; ANCHO]),
+        }
+    }
+
+    /// Suma una parcial al estado `eid`.
+    #[inline]
+    fn sumar(&mut self, eid: i64, v: &Fila) {
+        let mut o = false;
+        for (x, &y) in self.fila(eid).iter_mut().zip(v) { o |= acumular(x, y); }
+        self.desbordado |= o;
+    }
+
+    fn agregar_parcial(&mut self, eid: i64, p: &Parcial) {
+        if p.v[0] > 0 {
+            self.sumar(eid, &p.v);
+        }
+        self.desbordado |= p.desborde;
+        for (a, b) in self.negativos.iter_mut().zip(p.negativos) { *a += b; }
+    }
+
+    fn unir(mut self, otro: Self) -> Self {
+        self.desbordado |= otro.desbordado;
+        for (a, b) in self.negativos.iter_mut().zip(otro.negativos) { *a += b; }
+        for (a, b) in self.denso.iter_mut().zip(otro.denso.iter()) {
+            for (x, &y) in a.iter_mut().zip(b) { self.desbordado |= acumular(x, y); }   // ← FIX: 0..7
+        }
+        for (k, v) in otro.resto {
+            self.sumar(k, &v);
+        }
+        self
+    }
+
+    fn into_map(self) -> Result<Local, String> {
+        if self.desbordado {
+            return Err("desbordamiento i64 al acumular métricas: los totales no son confiables".into());
+        }
+        let negativos: Vec<String> = config::METRICAS_COLUMNA.iter().zip(self.negativos)
+            .filter(|&(_, n)| n > 0)
+            .map(|(m, n)| format!("{m}={n}"))
+            .collect();
+        if !negativos.is_empty() {
+            return Err(format!("valores negativos en métricas con política \"error\": {}", negativos.join(", ")));
+        }
+        let mut out = self.resto;
+        out.retain(|_, v| v[0] > 0);
+        out.extend(self.denso.iter().enumerate()
+            .filter(|(_, v)| v[0] > 0)
+            .map(|(k, v)| (k as i64, *v)));
+        Ok(out)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Por qué no arrow::compute para agregar: arrow-rs no trae group-by (eso es
+// DataFusion), así que "sum by key" serían filter + sum por estado, y cada
+// métrica necesita clamp a 0 (unary → otro array) antes del sum. Sobre los
+// grupos contiguos el kernel fusionado de abajo lee cada columna una vez y
+// no asigna nada. Arrow se usa en la carga (arrow_cast en parse_parquet_bytes).
+// ---------------------------------------------------------------------------
+// Kernels por corrida: el parquet suele llegar en corridas de estado_id
+// (decenas de filas seguidas), así que se suma cada corrida columna por
+// columna con un kernel sin ramas en vez de un lookup al HashMap por fila.
+// 4.8M filas, 1 hilo: 205 ms → 31 ms. Con filtro se usa la misma corrida
+// con suma_filtrada (antes era un fold fila por fila).
+// ---------------------------------------------------------------------------
+const LANES:  usize = 8;
+const BLOQUE: usize = 1 << 14;
+
+// ---------------------------------------------------------------------------
+// Política de negativos por métrica ([negativos] en la config). Cada una es
+// un tipo para que el kernel se monomorfice y el loop interno siga sin ramas.
+// ---------------------------------------------------------------------------
+trait Politica {
+    /// Contar los negativos (no nulos) para reportarlos como error.
+    const CUENTA: bool;
+    /// Lo que aporta `x` a la suma; el nulo aporta 0 en toda política.
+    fn valor<T: columna::Entero>(x: T) -> i64;
+}
+
+struct Recortar;
+struct Incluir;
+struct Reportar;
+
+impl Politica for Recortar {
+    const CUENTA: bool = false;
+    #[inline(always)]
+    fn valor<T: columna::Entero>(x: T) -> i64 { x.into().max(0) }
+}
+
+impl Politica for Incluir {
+    const CUENTA: bool = false;
+    #[inline(always)]
+    fn valor<T: columna::Entero>(x: T) -> i64 { if x == T::NULO { 0 } else { x.into() } }
+}
+
+impl Politica for Reportar {
+    const CUENTA: bool = true;
+    #[inline(always)]
+    fn valor<T: columna::Entero>(x: T) -> i64 { x.into().max(0) }
+}
+
+#[inline(always)]
+fn es_negativo<T: columna::Entero>(x: T) -> bool {
+    x != T::NULO && x.into() < 0
+}
+
+/// Evalúa `$e` con el tipo `$P` de la política `$pol`.
+macro_rules! con_politica {
+    ($pol:expr, $P:ident => $e:expr) => {
+        match $pol {
+            config::Negativos::Recortar => { type $P = Recortar; $e }
+            config::Negativos::Incluir  => { type $P = Incluir; $e }
+            config::Negativos::Error    => { type $P = Reportar; $e }
+        }
+    };
+}
+
+impl config::Negativos {
+    /// Versión por fila (i64 con nulo = i64::MIN) de `Politica::valor`.
+    #[inline]
+    fn valor(self, x: i64) -> i64 {
+        match self {
+            config::Negativos::Incluir => if x == i64::MIN { 0 } else { x },
+            _                          => x.max(0),
+        }
+    }
+}
+
+/// Resultado de un kernel sobre una métrica.
+struct Suma {
+    filas:     i64,
+    total:     i64,
+    desborde:  bool,
+    negativos: i64,
+    no_nulos:  i64,
+}
+
+/// `len` sumandos con |x| <= `max` no pueden desbordar. Los slices casi
+/// siempre son de un bloque o menos: se compara contra una constante en vez
+/// de dividir.
+#[inline(always)]
+fn cabe_sin_desborde(max: i64, len: usize) -> bool {
+    const MAX_POR_BLOQUE: i64 = i64::MAX / BLOQUE as i64;
+    if len <= BLOQUE { max <= MAX_POR_BLOQUE } else { max <= i64::MAX / len as i64 }
+}
+
+/// Σ P::valor(x) con LANES acumuladores independientes: sin ramas ni
+/// dependencia entre iteraciones, LLVM lo vectoriza (SSE2/AVX2/NEON).
+/// Genérico sobre el ancho de la columna; se acumula siempre en i64.
+/// Chequear cada suma rompe la vectorización, así que se suma con wrapping
+/// y se lleva el máximo |x|: si max × len cabe en i64 el resultado es
+/// exacto; si no (rarísimo) se repite con checked_add.
+#[inline]
+fn suma<P: Politica, T: columna::Entero>(xs: &[T]) -> Suma {
+    let mut lanes = [0i64; LANES];
+    let mut maxs  = [0i64; LANES];
+    let mut negs  = [0i64; LANES];
+    let mut datos = [0i64; LANES];
+    let chunks = xs.chunks_exact(LANES);
+    let resto = chunks.remainder();
+    for c in chunks {
+        for k in 0..LANES {
+            let x = P::valor(c[k]);
+            lanes[k] = lanes[k].wrapping_add(x);
+            maxs[k] = maxs[k].max(x.abs());
+            datos[k] += (c[k] != T::NULO) as i64;
+            if P::CUENTA { negs[k] += es_negativo(c[k]) as i64; }
+        }
+    }
+    let mut total = lanes.iter().fold(0i64, |a, &l| a.wrapping_add(l));
+    let mut max = maxs.iter().copied().max().unwrap_or(0);
+    let mut negativos: i64 = negs.iter().sum();
+    let mut no_nulos: i64 = datos.iter().sum();
+    for &x in resto {
+        let v = P::valor(x);
+        total = total.wrapping_add(v);
+        max = max.max(v.abs());
+        no_nulos += (x != T::NULO) as i64;
+        if P::CUENTA { negativos += es_negativo(x) as i64; }
+    }
+    let mut s = Suma { filas: xs.len() as i64, total, desborde: false, negativos, no_nulos };
+    if !cabe_sin_desborde(max, xs.len()) {
+        let exacta = xs.iter().try_fold(0i64, |a, &x| a.checked_add(P::valor(x)));
+        (s.total, s.desborde) = (exacta.unwrap_or(i64::MAX), exacta.is_none());
+    }
+    s
+}
+
+/// Como `suma` pero solo filas con situación == filtro (máscara 0/1
+/// multiplicada en vez de rama); `filas` son las que pasan.
+/// `filtro` >= 0, así que el nulo de cualquier ancho nunca coincide.
+#[inline]
+fn suma_filtrada<P: Politica, T: columna::Entero, S: columna::Entero>(xs: &[T], sits: &[S], filtro: i64) -> Suma {
+    let mut lanes  = [0i64; LANES];
+    let mut cuenta = [0i64; LANES];
+    let mut maxs   = [0i64; LANES];
+    let mut negs   = [0i64; LANES];
+    let mut datos  = [0i64; LANES];
+    let (cx, cs) = (xs.chunks_exact(LANES), sits.chunks_exact(LANES));
+    let (rx, rs) = (cx.remainder(), cs.remainder());
+    for (c, s) in cx.zip(cs) {
+        for k in 0..LANES {
+            let m = (s[k].into() == filtro) as i64;
+            let x = m * P::valor(c[k]);
+            lanes[k] = lanes[k].wrapping_add(x);
+            maxs[k] = maxs[k].max(x.abs());
+            cuenta[k] += m;
+            datos[k] += m & (c[k] != T::NULO) as i64;
+            if P::CUENTA { negs[k] += m & es_negativo(c[k]) as i64; }
+        }
+    }
+    let mut filas = cuenta.iter().sum::<i64>();
+    let mut total = lanes.iter().fold(0i64, |a, &l| a.wrapping_add(l));
+    let mut max = maxs.iter().copied().max().unwrap_or(0);
+    let mut negativos: i64 = negs.iter().sum();
+    let mut no_nulos: i64 = datos.iter().sum();
+    for (&x, &s) in rx.iter().zip(rs) {
+        let m = (s.into() == filtro) as i64;
+        let v = m * P::valor(x);
+        total = total.wrapping_add(v);
+        max = max.max(v.abs());
+        filas += m;
+        no_nulos += m & (x != T::NULO) as i64;
+        if P::CUENTA { negativos += m & es_negativo(x) as i64; }
+    }
+    let mut r = Suma { filas, total, desborde: false, negativos, no_nulos };
+    if !cabe_sin_desborde(max, xs.len()) {
+        let exacta = xs.iter().zip(sits).try_fold(0i64, |a, (&x, &s)| {
+            if s.into() == filtro { a.checked_add(P::valor(x)) } else { Some(a) }
+        });
+        (r.total, r.desborde) = (exacta.unwrap_or(i64::MAX), exacta.is_none());
+    }
+    r
+}
+
+/// Suma fila por fila las `filas` a `p`.
+fn filas_a_parcial(
+    p:        &mut Parcial,
+    columnas: &[&ColI; 6],
+    pols:     &[config::Negativos; 6],
+    filas:    impl Iterator<Item = usize>,
+) {
+    for i in filas {
+        p.v[0] += 1;
+        for (k, col) in columnas.iter().enumerate() {
+            let x = col.get(i);
+            p.desborde |= acumular(&mut p.v[k + 1], pols[k].valor(x));
+            p.v[k + METRICAS.len()] += (x != i64::MIN) as i64;
+            p.negativos[k] += (pols[k] == config::Negativos::Error && x < 0 && x != i64::MIN) as i64;
+        }
+    }
+}
+
+/// Métricas en diccionario: una pasada cuenta filas por código y cada
+/// métrica suma valor × filas una vez por tupla.
+fn parcial_diccionario(
+    codigos: &ColI,
+    valores: [&[i64]; 6],
+    pols:    &[config::Negativos; 6],
+    filas:   impl Iterator<Item = usize>,
+) -> Parcial {
+    let mut cuenta = vec![0i64; valores[0].len()];
+    for i in filas { cuenta[codigos.get(i) as usize] += 1; }
+    let mut p = Parcial::default();
+    p.v[0] = cuenta.iter().sum();
+    for (k, vs) in valores.iter().enumerate() {
+        for (&c, &x) in cuenta.iter().zip(vs.iter()) {
+            if c == 0 || x == i64::MIN { continue; }
+            match pols[k].valor(x).checked_mul(c) {
+                Some(s) => p.desborde |= acumular(&mut p.v[k + 1], s),
+                None    => p.desborde = true,
+            }
+            p.v[k + METRICAS.len()] += c;
+            if pols[k] == config::Negativos::Error && x < 0 { p.negativos[k] += c; }
+        }
+    }
+    p
+}
+
+/// Aporte de las filas [ini, fin), todas del mismo estado. Con `bits` solo
+/// se visitan las filas del bitmap de la situación filtrada.
+fn parcial_rango(
+    eng:        &EngineData,
+    pols:       &[config::Negativos; 6],
+    ini:        usize,
+    fin:        usize,
+    filtro_sit: i64,
+    bits:       Option<&[u64]>,
+) -> Parcial {
+    let columnas = eng.metricas();
+    let mut p = Parcial::default();
+    // Multivalor sin índice: "contiene" se evalúa fila por fila
+    let filas: Option<Box<dyn Iterator<Item = usize> + '_>> = match bits {
+        Some(bits) => Some(Box::new(bits_en_rango(bits, ini, fin))),
+        None if filtro_sit >= 0 && eng.multivalor() =>
+            Some(Box::new((ini..fin).filter(move |&i| eng.tiene_situacion(i, filtro_sit)))),
+        None => None,
+    };
+    // En diccionario no hay slices: por tupla si hay menos tuplas que filas,
+    // si no fila por fila
+    if let Some((codigos, valores)) = eng.diccionario() {
+        let filas = filas.unwrap_or_else(|| if filtro_sit >= 0 {
+            Box::new((ini..fin).filter(move |&i| eng.situaciones.get(i) == filtro_sit))
+        } else {
+            Box::new(ini..fin)
+        });
+        if valores[0].len() <= fin - ini {
+            return parcial_diccionario(codigos, valores, pols, filas);
+        }
+        filas_a_parcial(&mut p, &columnas, pols, filas);
+        return p;
+    }
+    if let Some(filas) = filas {
+        filas_a_parcial(&mut p, &columnas, pols, filas);
+        return p;
+    }
+    for (k, col) in columnas.iter().enumerate() {
+        let s = con_politica!(pols[k], P => if filtro_sit < 0 {
+            despachar!(col, xs => suma::<P, _>(&xs[ini..fin]))
+        } else {
+            despachar!(col, xs => despachar!(&eng.situaciones, ss =>
+                suma_filtrada::<P, _, _>(&xs[ini..fin], &ss[ini..fin], filtro_sit)))
+        });
+        p.v[0] = s.filas;
+        p.v[k + 1] = s.total;
+        p.v[k + METRICAS.len()] = s.no_nulos;
+        p.desborde |= s.desborde;
+        p.negativos[k] = s.negativos;
+    }
+    p
+}
+
+/// Periodo agrupado: cada tarea es un bloque contiguo de un solo estado, sin
+/// HashMap en el loop interno; el mapa solo recibe un vector por bloque.
+/// Con filtro y bitmap solo se visitan las filas que pasan; con `estados`
+/// solo los grupos de esos estados.
+fn agregar_por_grupos(
+    eng:        &EngineData,
+    filtro_sit: i64,
+    pols:       &[config::Negativos; 6],
+    estados:    Option<&[i64]>,
+) -> Result<Local, String> {
+    let bitmap = if filtro_sit < 0 { None } else { eng.bitmap(filtro_sit) };
+    if bitmap == Some(None) {
+        return Ok(Local::default());
+    }
+    let bits = bitmap.flatten();
+    let tareas: Vec<(i64, usize, usize)> = eng.grupos.iter()
+        .filter(|g| g.estado != i64::MIN && estados.is_none_or(|es| es.binary_search(&g.estado).is_ok()))
+        .flat_map(|g| (g.ini..g.fin).step_by(BLOQUE).map(move |s| (g.estado, s, (s + BLOQUE).min(g.fin))))
+        .collect();
+
+    let bloque = |(eid, ini, fin): (i64, usize, usize)| {
+        prioridad::ceder();
+        (eid, parcial_rango(eng, pols, ini, fin, filtro_sit, bits))
+    };
+    let parciales: Vec<(i64, Parcial)> = if config::secuencial(eng.n) {
+        tareas.into_iter().map(bloque).collect()
+    } else {
+        tareas.into_par_iter().map(bloque).collect()
+    };
+
+    let mut acc = Acumulador::new();
+    for (eid, p) in &parciales {
+        acc.agregar_parcial(*eid, p);
+    }
+    acc.into_map()
+}
+
+/// Fin de la corrida de valores iguales a `v[i]` dentro de `[i, fin)`.
+#[inline]
+fn fin_corrida<T: columna::Entero>(v: &[T], i: usize, fin: usize) -> usize {
+    v[i..fin].iter().position(|&x| x != v[i]).map_or(fin, |p| i + p)
+}
+
+/// Periodo sin agrupar (offline): se recorre en bloques y, dentro de cada
+/// bloque, por corridas de estado_id.
+fn agregar_por_corridas(eng: &EngineData, filtro_sit: i64, pols: &[config::Negativos; 6]) -> Result<Local, String> {
+    let paso = |mut acc: Acumulador, b: usize| {
+        prioridad::ceder();
+        let ini = b * BLOQUE;
+        let fin_bloque = (ini + BLOQUE).min(eng.n);
+        let mut i = ini;
+        while i < fin_bloque {
+            let eid = eng.estado_ids.get(i);
+            let fin = despachar!(&eng.estado_ids, v => fin_corrida(v, i, fin_bloque));
+            if eid != i64::MIN {
+                acc.agregar_parcial(eid, &parcial_rango(eng, pols, i, fin, filtro_sit, None));
+            }
+            i = fin;
+        }
+        acc
+    };
+    let bloques = eng.n.div_ceil(BLOQUE);
+    if config::secuencial(eng.n) {
+        return (0..bloques).fold(Acumulador::new(), paso).into_map();
+    }
+    (0..bloques)
+        .into_par_iter()
+        .fold(Acumulador::new, paso)
+        .reduce(Acumulador::new, Acumulador::unir)
+        .into_map()
+}
+
+// ---------------------------------------------------------------------------
+// Versiones del formato de los agregados que se devuelven. Un cambio que
+// altera las claves de salida sube FORMATO_ACTUAL y el anterior se sigue
+// pudiendo pedir con formato=N, para no romper consumidores viejos:
+//   1  hasta v5.1: sin "cn_sec" (6 claves)
+//   2  v5.2+: las 7 de METRICAS
+//   3  + los conteos de no nulos de CONTEOS ("cn_total_n", ...)
+// ---------------------------------------------------------------------------
+const FORMATO_ACTUAL: u32 = 3;
+const FORMATO_MINIMO: u32 = 1;
+
+/// Claves de métricas que lleva el formato `formato` (None = el actual) en
+/// el esquema `nombres` (None = [salida] nombres).
+fn metricas_formato(formato: Option<u32>, nombres: Option<&str>) -> Result<&'static [&'static str], String> {
+    let claves = claves_salida(nombres.map(config::Nombres::parse).transpose()?.unwrap_or_else(config::nombres));
+    match formato.unwrap_or(FORMATO_ACTUAL) {
+        1 => Ok(&claves[..6]),
+        2 => Ok(&claves[..METRICAS.len()]),
+        3 => Ok(claves),
+        f => Err(format!("formato {f} no soportado ({FORMATO_MINIMO}..={FORMATO_ACTUAL})")),
+    }
+}
+
+/// {estado_id: {"plazas": n, ...}} construido directo como PyDict: sin
+/// Strings ni HashMaps intermedios, y las claves se internan una vez por
+/// llamada (en un hit de cache esta conversión es casi toda la latencia).
+/// Estados en orden ascendente y métricas en el orden de CLAVES, para que
+/// dos respuestas iguales se impriman igual (el dict conserva la inserción).
+fn agregado_a_dict<'py>(py: Python<'py>, arr: &AgrMap) -> PyResult<Bound<'py, PyDict>> {
+    agregado_a_dict_formato(py, arr, claves_salida(config::nombres()), None)
+}
+
+/// agregado_a_dict() con solo las primeras `metricas.len()` métricas
+/// (metricas_formato()); con `catalogo`, cada estado lleva primero
+/// "estado_nombre" (catalogo.rs).
+fn agregado_a_dict_formato<'py>(
+    py:       Python<'py>,
+    arr:      &AgrMap,
+    metricas: &[&str],
+    catalogo: Option<&catalogo::Catalogo>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let claves: Vec<Bound<'py, PyString>> = metricas.iter().map(|k| PyString::intern(py, k)).collect();
+    let mut filas: Vec<(&i64, &Fila)> = arr.iter().collect();
+    filas.sort_unstable_by_key(|&(&eid, _)| eid);
+    let out = PyDict::new(py);
+    for (&eid, v) in filas {
+        let m = PyDict::new(py);
+        if let Some(c) = catalogo {
+            m.set_item(pyo3::intern!(py, "estado_nombre"), c.get(&eid))?;
+        }
+        for (k, x) in claves.iter().zip(v) {
+            m.set_item(k, x)?;
+        }
+        out.set_item(eid, m)?;
+    }
+    Ok(out)
+}
+
+/// Huella de una comparación en hex para que los workers de Python detecten
+/// caches divergentes: XXH3-64 (semilla 0) de, por cada lado, la cantidad de
+/// estados como u64 y luego cada estado en orden ascendente como su id i64
+/// seguido de las ANCHO celdas i64 del acumulador, todo en little-endian. El
+/// formato es fijo: la huella es la misma entre procesos, builds y
+/// plataformas, y no depende del formato pedido.
+fn huella_resultado(agr1: &AgrMap, agr2: &AgrMap) -> String {
+    let mut h = xxhash_rust::xxh3::Xxh3::new();
+    for agr in [agr1, agr2] {
+        let mut filas: Vec<(&i64, &Fila)> = agr.iter().collect();
+        filas.sort_unstable_by_key(|&(&eid, _)| eid);
+        h.update(&(filas.len() as u64).to_le_bytes());
+        for (eid, acc) in filas {
+            h.update(&eid.to_le_bytes());
+            for v in acc { h.update(&v.to_le_bytes()); }
+        }
+    }
+    format!("{:016x}", h.digest())
+}
+
+/// `total += parcial` por estado (periodos virtuales); error si desborda.
+fn unir_agregados(total: &mut Local, parcial: Local) -> Result<(), String> {
+    for (eid, v) in parcial {
+        let acc = total.entry(eid).or_insert([0; ANCHO]);
+        for (a, b) in acc.iter_mut().zip(v) {
+            *a = a.checked_add(b)
+                .ok_or("desbordamiento i64 al acumular métricas: los totales no son confiables")?;
+        }
+    }
+    Ok(())
+}
+
+/// Filas de un lado de la comparación que no llegaron al agregado, para
+/// avisos como "N plazas sin estado asignado".
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+struct MetaFilas {
+    // filas del periodo (o de los componentes del virtual)
+    filas:               usize,
+    // estado_id nulo: no se asignan a ningún estado
+    sin_estado:          usize,
+    // con estado pero otra situación que la del filtro
+    excluidas_situacion: usize,
+    // de estados que no se pidieron (solo con `estados`)
+    fuera_de_estados:    usize,
+}
+
+/// Cuenta por estado desde los grupos o, sin agrupar, recorriendo la columna.
+fn filas_por_estado(eng: &EngineData) -> FxHashMap<i64, usize> {
+    let mut m = FxHashMap::default();
+    if !eng.grupos.is_empty() {
+        for g in &eng.grupos { *m.entry(g.estado).or_default() += g.fin - g.ini; }
+        return m;
     }
+    for i in 0..eng.n { *m.entry(eng.estado_ids.get(i)).or_default() += 1; }
+    m
+}
+
+/// `agr` es lo que se agregó del lado resumido en `lado` (con `estados` si
+/// se restringió).
+fn meta_filas(lado: &ResumenLado, agr: &AgrMap, estados: Option<&[i64]>) -> MetaFilas {
+    let mut meta = MetaFilas { filas: lado.filas, ..Default::default() };
+    let mut candidatas = 0;
+    for (&e, &c) in &lado.por_estado {
+        if e == i64::MIN {
+            meta.sin_estado += c;
+        } else if estados.is_some_and(|es| es.binary_search(&e).is_err()) {
+            meta.fuera_de_estados += c;
+        } else {
+            candidatas += c;
+        }
+    }
+    let contadas: i64 = agr.values().map(|v| v[0]).sum();
+    meta.excluidas_situacion = candidatas.saturating_sub(contadas.max(0) as usize);
+    meta
+}
+
+fn meta_a_dict<'py>(py: Python<'py>, m: &MetaFilas) -> PyResult<Bound<'py, PyDict>> {
+    let d = PyDict::new(py);
+    d.set_item("filas", m.filas)?;
+    d.set_item("sin_estado", m.sin_estado)?;
+    d.set_item("excluidas_situacion", m.excluidas_situacion)?;
+    d.set_item("fuera_de_estados", m.fuera_de_estados)?;
+    Ok(d)
+}
+
+// ===========================================================================
+// NÚCLEO DEL CACHE (sin Python)
+// Lo usan los wrappers PyO3 dentro de allow_threads y el servidor HTTP.
+// ===========================================================================
+
+type AgrMap = Local;
+
+// Carga estricta sin opciones (PUT /periodos)
+#[cfg(feature = "http")]
+fn cargar_periodo(raw: Bytes, periodo_key: u32) -> Result<usize, Error> {
+    cargar_periodo_con(raw, periodo_key, OpcionesCarga::default()).map(|c| c.filas)
+}
+
+/// Lo que acompaña a los bytes en una carga (cargar_periodo_parquet).
+#[derive(Default)]
+struct OpcionesCarga {
+    // Máximo de muestras de la carga tolerante (descartes.rs); None = estricta
+    tolerancia: Option<usize>,
+    // Se prueban antes que los configurados, solo en esta carga (validados
+    // con config::validar_aliases)
+    aliases:    Option<HashMap<String, Vec<String>>>,
+    // Validados con metadatos::validar
+    metadatos:  BTreeMap<String, String>,
+    version:    versiones::Version,
+    // Parsear aunque ya esté cargado el mismo contenido
+    forzar:     bool,
+}
+
+struct Carga {
+    filas:     usize,
+    // Solo en carga tolerante
+    descartes: Option<descartes::Descartes>,
+    // Ya estaba cargado el mismo contenido con las mismas opciones: no se
+    // parseó de nuevo
+    identica:  bool,
+}
+
+/// Huella de una carga: los bytes tal como llegaron, todo lo de `opciones`
+/// que cambia el resultado y la generación de la configuración. XXH3 porque
+/// recorre el payload entero (cientos de MB) en cada carga; solo se compara
+/// dentro del proceso.
+fn huella_carga(raw: &[u8], opciones: &OpcionesCarga) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut h = xxhash_rust::xxh3::Xxh3::new();
+    h.update(raw);
+    opciones.tolerancia.hash(&mut h);
+    opciones.aliases.as_ref().map(|a| a.iter().collect::<BTreeMap<_, _>>()).hash(&mut h);
+    opciones.metadatos.hash(&mut h);
+    (opciones.version as u8).hash(&mut h);
+    config::generacion().hash(&mut h);
+    h.finish()
+}
+
+/// El periodo servido para `periodo_key` si se cargó con esta huella.
+fn carga_identica(periodo_key: u32, huella: u64, version: versiones::Version) -> Result<Option<Arc<EngineData>>, Error> {
+    let Ok(eng) = periodo(periodo_key) else { return Ok(None) };
+    let misma = eng.huella == huella && versiones::vigente(periodo_key)? == Some(version);
+    Ok(misma.then_some(eng))
+}
+
+/// Un reintento con los mismos bytes y opciones no se parsea de nuevo:
+/// devuelve lo de la primera carga con `identica`. El permiso de [limites]
+/// se pide antes de recorrer los bytes para la huella.
+fn cargar_periodo_con(
+    raw:         Bytes,
+    periodo_key: u32,
+    opciones:    OpcionesCarga,
+) -> Result<Carga, Error> {
+    let t0 = Instant::now();
+    let grabadas = traza::opciones(&opciones);
+    let _permiso = match limites::permiso_carga(&config::actual().limites) {
+        Ok(p)  => p,
+        Err(e) => {
+            traza::carga(periodo_key, &raw, grabadas, t0.elapsed(), false);
+            return Err(e);
+        }
+    };
+    let huella = huella_carga(&raw, &opciones);
+    if !opciones.forzar {
+        if let Some(eng) = carga_identica(periodo_key, huella, opciones.version)? {
+            eng.tocar();
+            bitacora::anotar("carga_identica", format!("periodo_key={periodo_key} filas={}", eng.n));
+            traza::carga(periodo_key, &raw, grabadas, t0.elapsed(), true);
+            return Ok(Carga { filas: eng.n, descartes: eng.descartes.clone(), identica: true });
+        }
+    }
+    let mut descartes = opciones.tolerancia.map(descartes::Descartes::new);
+    let r = cargar_periodo_sin_traza(raw.clone(), periodo_key, descartes.as_mut(), huella, opciones);
+    traza::carga(periodo_key, &raw, grabadas, t0.elapsed(), r.is_ok());
+    r.map(|filas| Carga { filas, descartes, identica: false })
+}
+
+fn cargar_periodo_sin_traza(
+    raw:         Bytes,
+    periodo_key: u32,
+    mut descartes: Option<&mut descartes::Descartes>,
+    huella:      u64,
+    opciones:    OpcionesCarga,
+) -> Result<usize, Error> {
+    let mut cfg = config::actual();
+    if let Some(a) = &opciones.aliases { Arc::make_mut(&mut cfg).anteponer_aliases(a); }
+    let t0 = Instant::now();
+    let mut ctx = cuarentena::Contexto { etapa: "descompresion", ..Default::default() };
+    let eng = match descomprimir_buffer(raw.clone()).map_err(Error::from)
+        .and_then(|bytes| parse_parquet_contexto(bytes, &cfg, &mut ctx, descartes.as_deref_mut()))
+    {
+        Ok(eng) => eng,
+        Err(e) => {
+            metricas::contar_error(Operacion::Carga);
+            cuarentena::guardar(periodo_key, e.to_string(), ctx, raw);
+            return Err(e);
+        }
+    };
+    let mut eng = config::en_pool(|| {
+        let eng = eng.agrupar_por_estado().indexar_situaciones();
+        let eng = if cfg.compacto { eng.compactar() } else { eng };
+        if cfg.diccionario { eng.codificar_metricas() } else { eng }
+    });
+    eng.metadatos = opciones.metadatos;
+    eng.huella = huella;
+    eng.descartes = descartes.as_deref().cloned();
+    let n = eng.n;
+    versiones::insertar(periodo_key, eng, &cfg, opciones.version)?;
+    metricas::contar_operacion(Operacion::Carga, t0.elapsed());
+    refresco::tras_carga();
+    Ok(n)
+}
+
+/// Inserta en ENGINE_PERIODOS desalojando según la política si hace falta.
+fn insertar_periodo(periodo_key: u32, eng: Arc<EngineData>, cfg: &config::Config) -> Result<(), Error> {
+    if virtuales::componentes_de(periodo_key)?.is_some() {
+        return Err(Error::Motor(format!(
+            "Periodo {periodo_key} es virtual; use eliminar_periodo_virtual antes de cargarlo"
+        )));
+    }
+    let fijados = fijados::actuales()?;
+    let mut guard = ENGINE_PERIODOS.escribir("insertar_periodo")?;
+    let map = guard.get_or_insert_with(FxHashMap::default);
+
+    if map.len() >= cfg.max_periodos && !map.contains_key(&periodo_key) {
+        let candidatos = map.iter().filter(|(k, _)| !fijados.contains(k)).map(|(&k, v)| (
+            k,
+            v.ultimo_acceso.load(Ordering::Relaxed),
+            v.accesos.load(Ordering::Relaxed),
+            v.cargado_at,
+        ));
+        let Some(victima) = elegir_victima(candidatos, cfg.politica_eviccion) else {
+            let mut claves: Vec<PeriodoKey> = map.keys().copied().collect();
+            claves.sort_unstable();
+            return Err(Error::CacheLleno(format!(
+                "Cache de periodos llena ({} de max_periodos={}) y todos fijados: {claves:?}; \
+                 soltar_periodo o evict_periodo antes de cargar {periodo_key}",
+                map.len(), cfg.max_periodos,
+            ), claves));
+        };
+        map.remove(&victima);
+        metricas::contar_eviccion(Cache::Periodos, Motivo::Capacidad, 1);
+        bitacora::anotar("eviccion", format!("cache=periodos clave={victima} motivo=capacidad"));
+        buffers::tras_eviccion();
+    }
+
+    // Recarga: los resultados en cache se calcularon con los datos anteriores
+    let recarga = map.insert(periodo_key, eng).is_some();
+    drop(guard);
+    invalidacion::subir(periodo_key);
+    if recarga { virtuales::invalidar_resultados(periodo_key)?; }
+    Ok(())
+}
+
+fn periodo(key: u32) -> Result<Arc<EngineData>, Error> {
+    let encontrado = ENGINE_PERIODOS.leer("periodo")?.as_ref().and_then(|m| m.get(&key).cloned());
+    if let Some(e) = encontrado { return Ok(e); }
+    if virtuales::componentes_de(key)?.is_some() {
+        return Err(Error::Motor(format!("Periodo {key} es virtual: solo admite comparaciones")));
+    }
+    Err(Error::Motor(format!("Periodo {key} no cargado")))
+}
+
+/// Agregados de ambos lados y sus resúmenes (de la entrada en cache o del
+/// cálculo).
+fn comparar(key1: u32, key2: u32, filtro_situacion: i64) -> Result<(AgrMap, AgrMap, Resumenes), Error> {
+    let result_key: ResultKey = (key1, key2, filtro_situacion);
+
+    // 1. Check RESULT_CACHE (uno vencido cuenta como hit si se revalida en
+    // fondo: revalidacion.rs)
+    let hit = {
+        let mut rcache = RESULT_CACHE.escribir("comparar")?;
+        rcache.as_mut().and_then(|map| map.get_mut(&result_key)).and_then(|hit| {
+            let ahora = now_secs();
+            let vencido = revalidacion::vencido(hit.calculado_at, ahora);
+            if vencido && !revalidacion::servir_vencido() { return None; }
+            hit.ultimo_acceso = ahora;
+            hit.accesos += 1;
+            Some((hit.lados.clone(), Arc::clone(&hit.resumenes), vencido))
+        })
+    };
+    if let Some((lados, resumenes, vencido)) = hit {
+        if vencido { revalidacion::en_fondo(result_key); }
+        metricas::contar_hit(true);
+        let (agr1, agr2) = lados.agregados()?;
+        let agr2 = agr2.unwrap_or_else(|| agr1.clone());
+        return Ok((agr1, agr2, resumenes));
+    }
+
+    // 2. Miss (o vencido)
+    metricas::contar_hit(false);
+    let (agr1, agr2, resumenes) = calcular(key1, key2, filtro_situacion)?;
+    let agr2 = agr2.unwrap_or_else(|| agr1.clone());
+    Ok((agr1, agr2, resumenes))
+}
 
-    let mask = parquet::arrow::ProjectionMask::roots(&parquet_schema, projection);
-    let reader = builder
-        .with_projection(mask)
-        .build()
-        .map_err(|e| format!("reader: {e}"))?;
+/// Agrega la comparación y la guarda en RESULT_CACHE (reemplaza la que
+/// hubiera). agr2 es None si key1 == key2.
+fn calcular(key1: u32, key2: u32, filtro_situacion: i64) -> Result<(AgrMap, Option<AgrMap>, Resumenes), Error> {
+    let result_key: ResultKey = (key1, key2, filtro_situacion);
+    // Clonar los Arc bajo el lock y agregar con Rayon ya sin él
+    let t0 = Instant::now();
+    let mismo = key1 == key2;
+    // Cada lado es un periodo o los componentes de un virtual
+    let (e1, e2) = virtuales::lados(key1)
+        .and_then(|e1| Ok((e1.clone(), if mismo { e1 } else { virtuales::lados(key2)? })))
+        .inspect_err(|_| metricas::contar_error(Operacion::Comparacion))?;
+    e1.iter().chain(if mismo { &[][..] } else { &e2[..] }).for_each(|e| e.tocar());
+    // Un lado sin filas no se agrega; el mismo periodo se agrega una vez
+    let agregar_lado = |lado: &[Arc<EngineData>]| {
+        let mut total = Local::default();
+        for e in lado.iter().filter(|e| e.n > 0) {
+            unir_agregados(&mut total, agregar(e, filtro_situacion)?)?;
+        }
+        Ok::<_, String>(total)
+    };
+    let filas = |lado: &[Arc<EngineData>]| lado.iter().map(|e| e.n).sum::<usize>();
+    lentas::anotar_filas(filas(&e1) + if mismo { 0 } else { filas(&e2) });
+    let (agr1, agr2) = if mismo {
+        (config::en_pool_si(filas(&e1), || agregar_lado(&e1)), Ok(Local::default()))
+    } else if config::secuencial(filas(&e1) + filas(&e2)) {
+        (agregar_lado(&e1), agregar_lado(&e2))
+    } else {
+        config::en_pool(|| rayon::join(|| agregar_lado(&e1), || agregar_lado(&e2)))
+    };
+    let (agr1, agr2) = agr1.and_then(|a1| Ok((a1, agr2?)))
+        .inspect_err(|_| metricas::contar_error(Operacion::Comparacion))?;
+    let agr2 = if mismo { None } else { Some(agr2) };
+    let resumenes = resumenes(&e1, &e2, mismo);
 
-    let mut col_map_f64: HashMap<String, Vec<f64>> = HashMap::new();
-    let mut col_map_i64: HashMap<String, Vec<i64>> = HashMap::new();
+    // Guardar en RESULT_CACHE (comprimido antes de tomar el lock); una
+    // revalidación conserva los accesos de la entrada que reemplaza
+    let mut entrada = ResultadoComp::nuevo(agr1.clone(), agr2.clone(), Arc::clone(&resumenes), now_secs());
+    {
+        let mut rcache = RESULT_CACHE.escribir("comparar")?;
+        let map = rcache.get_or_insert_with(FxHashMap::default);
+        if let Some(anterior) = map.get(&result_key) {
+            entrada.accesos = anterior.accesos;
+            entrada.ultimo_acceso = anterior.ultimo_acceso;
+        }
+        insertar_resultado(map, result_key, entrada);
+    }
 
-    for batch_result in reader {
-        let batch = batch_result.map_err(|e| format!("batch: {e}"))?;
-        for col_idx in 0..batch.num_columns() {
-            let name = batch.schema().field(col_idx).name().clone();
-            if !cols_interes.contains(&name.as_str()) {
-                continue;
-            }
-            let col = batch.column(col_idx);
+    metricas::contar_operacion(Operacion::Comparacion, t0.elapsed());
+    Ok((agr1, agr2, resumenes))
+}
 
-            if let Some(a) = col.as_any().downcast_ref::<Float64Array>() {
-                let entry = col_map_f64.entry(name).or_default();
-                for j in 0..a.len() {
-                    entry.push(if a.is_valid(j) { a.value(j) } else { f64::NAN });
-                }
-            } else if let Some(a) = col.as_any().downcast_ref::<Float32Array>() {
-                let entry = col_map_f64.entry(name).or_default();
-                for j in 0..a.len() {
-                    entry.push(if a.is_valid(j) { a.value(j) as f64 } else { f64::NAN });
-                }
-            } else {
-                let entry = col_map_i64.entry(name).or_default();
-                macro_rules! try_int {
-                    ($ArrayType:ty) => {
-                        if let Some(a) = col.as_any().downcast_ref::<$ArrayType>() {
-                            for j in 0..a.len() {
-                                entry.push(if a.is_valid(j) { a.value(j) as i64 } else { i64::MIN });
-                            }
-                            continue;
-                        }
-                    };
-                }
-                try_int!(Int64Array);
-                try_int!(Int32Array);
-                try_int!(Int16Array);
-                try_int!(Int8Array);
-                try_int!(UInt64Array);
-                try_int!(UInt32Array);
-                try_int!(UInt16Array);
-                try_int!(UInt8Array);
-            }
+/// Inserta en RESULT_CACHE desalojando según la política si hace falta.
+fn insertar_resultado(map: &mut ResultadosMap, result_key: ResultKey, r: ResultadoComp) {
+    let cfg = config::actual();
+    if map.len() >= cfg.max_resultados && !map.contains_key(&result_key) {
+        let candidatos = map.iter()
+            .map(|(&k, v)| (k, v.ultimo_acceso, v.accesos, v.calculado_at));
+        if let Some((k1, k2, filtro)) = elegir_victima(candidatos, cfg.politica_eviccion) {
+            map.remove(&(k1, k2, filtro));
+            metricas::contar_eviccion(Cache::Resultados, Motivo::Capacidad, 1);
+            bitacora::anotar("eviccion", format!(
+                "cache=resultados clave=({k1}, {k2}, {filtro}) motivo=capacidad"
+            ));
         }
     }
+    map.insert(result_key, r);
+}
 
-    let get_f64 = |names: &[&str]| -> Vec<f64> {
-        for n in names {
-            if let Some(v) = col_map_f64.get(*n) { return v.clone(); }
-        }
-        vec![]
+/// Guarda `nueva` con los lados de `base` intercambiados, sin recalcular.
+/// `nueva` tiene que ser `base` con key1 y key2 invertidos (mismo filtro).
+/// False si `base` no está en cache; si `nueva` ya estaba no se toca.
+fn derivar(base: ResultKey, nueva: ResultKey) -> Result<bool, Error> {
+    if nueva != (base.1, base.0, base.2) {
+        return Err(Error::Motor(format!(
+            "derivar_resultado: {nueva:?} no es {base:?} con los periodos invertidos"
+        )));
+    }
+    let (lados, resumenes, calculado_at) = {
+        let rcache = RESULT_CACHE.leer("derivar_resultado")?;
+        let Some(map) = rcache.as_ref() else { return Ok(false) };
+        if map.contains_key(&nueva) { return Ok(map.contains_key(&base)); }
+        let Some(b) = map.get(&base) else { return Ok(false) };
+        (b.lados.clone(), Arc::clone(&b.resumenes), b.calculado_at)
     };
-    let get_i64 = |names: &[&str]| -> Vec<i64> {
-        for n in names {
-            if let Some(v) = col_map_i64.get(*n) { return v.clone(); }
-        }
-        vec![]
+    let [r1, r2] = &*resumenes;
+    let resumenes = Arc::new([r2.clone(), r1.clone()]);
+    // Descomprimir y recomprimir sin el lock
+    let (agr1, agr2) = lados.agregados()?;
+    let invertido = match agr2 {
+        // Los datos tienen la edad de la base
+        Some(agr2) => ResultadoComp::nuevo(agr2, Some(agr1), resumenes, calculado_at),
+        None       => ResultadoComp::nuevo(agr1, None, resumenes, calculado_at),
     };
+    let mut rcache = RESULT_CACHE.escribir("derivar_resultado")?;
+    let map = rcache.get_or_insert_with(FxHashMap::default);
+    // Otro hilo pudo guardarla mientras tanto
+    if !map.contains_key(&nueva) { insertar_resultado(map, nueva, invertido); }
+    Ok(true)
+}
 
-    let lats_data = get_f64(&["lat", "Latitud"]);
-    let n = lats_data.len();
-    let fill_f = |v: Vec<f64>| if v.len() == n { v } else { vec![f64::NAN; n] };
-    let fill_i = |v: Vec<i64>| if v.len() == n { v } else { vec![i64::MIN; n] };
+// Agregados, MetaFilas y metadatos del lado
+type LadoConMeta = (AgrMap, MetaFilas, BTreeMap<String, String>);
 
-    Ok(EngineData {
-        n,
-        lats:         fill_f(lats_data),
-        lngs:         fill_f(get_f64(&["lng",        "Longitud"])),
-        estado_ids:   fill_i(get_i64(&["estado_id",  "Clave_Edo"])),
-        situaciones:  fill_i(get_i64(&["situacion",  "Situación", "Situacion"])),
-        inc_totales:  fill_i(get_i64(&["inc_total",  "Inc_Total"])),
-        aten_totales: fill_i(get_i64(&["aten_total", "Aten_Total"])),
-        cn_totales:   fill_i(get_i64(&["cn_total",   "CN_Tot_Acum"])),
-        cn_ini:       fill_i(get_i64(&["cn_inicial", "CN_Inicial_Acum"])),
-        cn_prim:      fill_i(get_i64(&["cn_prim",    "CN_Prim_Acum"])),
-        cn_sec:       fill_i(get_i64(&["cn_sec",     "CN_Sec_Acum"])),
-        cargado_at:    now_secs(),
-        ultimo_acceso: now_secs(),
-    })
+/// comparar() (comparar_estados() con `estados`) más el MetaFilas de cada
+/// lado. Python y HTTP entran por aquí (y la traza, si se está
+/// grabando: traza.rs).
+fn comparar_con_meta(
+    key1:    u32,
+    key2:    u32,
+    filtro:  i64,
+    estados: Option<&[i64]>,
+) -> Result<(LadoConMeta, LadoConMeta), Error> {
+    let t0 = Instant::now();
+    lentas::iniciar();
+    let r = comparar_con_meta_sin_traza(key1, key2, filtro, estados);
+    traza::comparacion(key1, key2, filtro, estados, t0.elapsed(), r.is_ok());
+    lentas::registrar(key1, key2, filtro, estados, t0.elapsed(), r.is_ok());
+    r
 }
 
-// ===========================================================================
-// AGREGACIÓN PARALELA (Rayon)  ← CAMBIADO: [i64; 6] → [i64; 7], +e[6]=cn_sec
-// ===========================================================================
-fn agregar(eng: &EngineData, filtro_sit: i64) -> HashMap<i64, [i64; 7]> {
-    type Local = HashMap<i64, [i64; 7]>;
+fn comparar_con_meta_sin_traza(
+    key1:    u32,
+    key2:    u32,
+    filtro:  i64,
+    estados: Option<&[i64]>,
+) -> Result<(LadoConMeta, LadoConMeta), Error> {
+    let (agr1, agr2, resumenes) = match estados {
+        Some(es) => particion::comparar_estados(key1, key2, filtro, es),
+        None     => comparar(key1, key2, filtro),
+    }?;
+    let mut es = estados.map(<[i64]>::to_vec);
+    if let Some(es) = es.as_mut() { es.sort_unstable(); }
+    let meta1 = meta_filas(&resumenes[0], &agr1, es.as_deref());
+    let meta2 = if key1 == key2 { meta1 } else { meta_filas(&resumenes[1], &agr2, es.as_deref()) };
+    let [r1, r2] = &*resumenes;
+    Ok(((agr1, meta1, r1.metadatos.clone()), (agr2, meta2, r2.metadatos.clone())))
+}
 
-    (0..eng.n)
-        .into_par_iter()
-        .fold(Local::new, |mut acc, i| {
-            if filtro_sit >= 0 {
-                let sit = eng.situaciones[i];
-                if sit == i64::MIN || sit != filtro_sit { return acc; }
-            }
-            let eid = eng.estado_ids[i];
-            if eid == i64::MIN { return acc; }
-
-            let e = acc.entry(eid).or_insert([0i64; 7]);
-            e[0] += 1;
-            e[1] += eng.inc_totales[i].max(0);
-            e[2] += eng.aten_totales[i].max(0);
-            e[3] += eng.cn_totales[i].max(0);
-            e[4] += eng.cn_ini[i].max(0);
-            e[5] += eng.cn_prim[i].max(0);
-            e[6] += eng.cn_sec[i].max(0);   // ← FIX: CN_Sec_Acum
-            acc
-        })
-        .reduce(Local::new, |mut a, b| {
-            for (k, v) in b {
-                let e = a.entry(k).or_insert([0i64; 7]);
-                for i in 0..7 { e[i] += v[i]; }   // ← FIX: 0..7
-            }
-            a
-        })
+fn quitar_periodo(periodo_key: u32) -> Result<bool, Error> {
+    let mut guard = ENGINE_PERIODOS.escribir("quitar_periodo")?;
+    let quitado = guard.as_mut().is_some_and(|m| m.remove(&periodo_key).is_some());
+    drop(guard);
+    metricas::contar_eviccion(Cache::Periodos, Motivo::Manual, quitado as u64);
+    if quitado {
+        buffers::tras_eviccion();
+        invalidacion::subir(periodo_key);
+    }
+    Ok(quitado)
 }
 
-// ← CAMBIADO: ahora expone cn_sec (v[6])
-fn to_py_map(arr: &HashMap<i64, [i64; 7]>) -> HashMap<i64, HashMap<String, i64>> {
-    arr.iter().map(|(&eid, v)| {
-        let mut m = HashMap::with_capacity(7);
-        m.insert("plazas".into(),     v[0]);
-        m.insert("inc_total".into(),  v[1]);
-        m.insert("aten_total".into(), v[2]);
-        m.insert("cn_total".into(),   v[3]);
-        m.insert("cn_ini".into(),     v[4]);
-        m.insert("cn_prim".into(),    v[5]);
-        m.insert("cn_sec".into(),     v[6]);   // ← FIX: CN_Sec_Acum
-        (eid, m)
-    }).collect()
+fn quitar_resultado(key: ResultKey) -> Result<bool, Error> {
+    let mut guard = RESULT_CACHE.escribir("quitar_resultado")?;
+    let quitado = guard.as_mut().is_some_and(|m| m.remove(&key).is_some());
+    metricas::contar_eviccion(Cache::Resultados, Motivo::Manual, quitado as u64);
+    Ok(quitado)
+}
+
+/// Vacía RESULT_CACHE completo (p. ej. si cambió cómo se agrega).
+fn vaciar_resultados() -> Result<usize, Error> {
+    let mut guard = RESULT_CACHE.escribir("vaciar_resultados")?;
+    let n = guard.take().map_or(0, |m| m.len());
+    metricas::contar_eviccion(Cache::Resultados, Motivo::Manual, n as u64);
+    Ok(n)
+}
+
+fn recursos() -> BTreeMap<String, u64> {
+    let cfg = config::actual();
+    let mut stats = BTreeMap::new();
+    if let Ok(g) = ENGINE_PERIODOS.leer("recursos") {
+        let (n_p, filas, ram) = g.as_ref().map_or((0, 0, 0), |m| {
+            let f: usize = m.values().map(|e| e.n).sum();
+            let b: usize = m.values()
+                .map(|e| e.bytes_por_estructura().iter().map(|&(_, b)| b).sum::<usize>())
+                .sum();
+            (m.len(), f, b / 1024)
+        });
+        stats.insert("periodos_cargados".into(), n_p as u64);
+        stats.insert("filas_totales".into(),     filas as u64);
+        stats.insert("ram_datos_kb".into(),      ram as u64);
+    }
+    if let Ok(g) = RESULT_CACHE.leer("recursos") {
+        let (n_r, hits) = g.as_ref().map_or((0, 0), |m| {
+            let h: u64 = m.values().map(|v| v.accesos).sum();
+            (m.len(), h)
+        });
+        stats.insert("resultados_cacheados".into(), n_r as u64);
+        stats.insert("cache_hits_total".into(),     hits);
+        stats.insert("max_resultados".into(),       cfg.max_resultados as u64);
+    }
+    for (nombre, v) in revalidacion::estadisticas() {
+        stats.insert(nombre.into(), v);
+    }
+    stats.insert("max_periodos".into(), cfg.max_periodos as u64);
+    stats.insert("hilos".into(),        cfg.hilos as u64);
+    stats.insert("formato_resultado".into(), FORMATO_ACTUAL as u64);
+    stats.insert("formato_minimo".into(),    FORMATO_MINIMO as u64);
+    stats
 }
 
 // ===========================================================================
 // FUNCIONES EXPORTADAS A PYTHON
 // ===========================================================================
 
+/// Devuelve las filas cargadas. Con `tolerante` descarta filas y row groups
+/// que no convierten en vez de abortar (descartes.rs) y devuelve {"filas",
+/// "filas_descartadas", "muestras", "grupos_descartados"}, con hasta
+/// `max_muestras` (índice, motivo). `aliases` ({canónica: [nombres]}) vale
+/// solo para este parquet y se prueba antes que los aliases configurados.
+/// `metadatos` ({str: str}: archivo fuente, job, "preliminar") queda con el
+/// periodo: listar_periodos() y comparar_periodos() lo devuelven.
+/// `version="preliminar"` no reemplaza una definitiva ya cargada
+/// (versiones.rs). Los mismos bytes con las mismas opciones sobre la misma
+/// clave no se vuelven a parsear; con `detalle` (o `tolerante`) se devuelve
+/// un dict y "identica" dice si fue así. `forzar` parsea igual.
 #[pyfunction]
+#[pyo3(signature = (
+    data, periodo_key, request_id = None, tolerante = false, max_muestras = 100, aliases = None,
+    metadatos = None, version = "definitivo", detalle = false, forzar = false,
+))]
+#[allow(clippy::too_many_arguments)]
 fn cargar_periodo_parquet(
-    py:          Python<'_>,
-    data:        &Bound<'_, PyBytes>,
-    periodo_key: u32,
-) -> PyResult<usize> {
-    let raw = data.as_bytes().to_vec();
-
-    let eng = py.allow_threads(|| -> Result<EngineData, String> {
-        let bytes = decompress_bytes(&raw)?;
-        parse_parquet_bytes(&bytes)
-    }).map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e))?;
-
-    let n = eng.n;
-
-    let mut guard = ENGINE_PERIODOS.write()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    let map = guard.get_or_insert_with(HashMap::new);
-
-    if map.len() >= MAX_PERIODOS && !map.contains_key(&periodo_key) {
-        if let Some(&lru_key) = map.iter()
-            .min_by_key(|(_, v)| v.ultimo_acceso)
-            .map(|(k, _)| k)
-        {
-            map.remove(&lru_key);
-        }
+    py:           Python<'_>,
+    data:         PyBackedBytes,
+    periodo_key:  ArgClave,
+    request_id:   Option<&str>,
+    tolerante:    bool,
+    max_muestras: usize,
+    aliases:      Option<HashMap<String, Vec<String>>>,
+    metadatos:    Option<BTreeMap<String, String>>,
+    version:      &str,
+    detalle:      bool,
+    forzar:       bool,
+) -> PyResult<PyObject> {
+    // Bytes sobre el mismo buffer del objeto bytes (lo mantiene vivo): no se
+    // copia el payload, que puede pesar cientos de MB.
+    let periodo_key = clave(periodo_key)?;
+    let raw = Bytes::from_owner(data);
+    let mut params = format!("periodo_key={periodo_key} bytes={}", raw.len());
+    if tolerante { params += " tolerante"; }
+    if let Some(a) = &aliases {
+        config::validar_aliases(a).map_err(pyo3::exceptions::PyValueError::new_err)?;
+        let mut cs: Vec<&str> = a.keys().map(String::as_str).collect();
+        cs.sort_unstable();
+        params += &format!(" aliases={}", cs.join(","));
     }
+    let metadatos = metadatos.unwrap_or_default();
+    metadatos::validar(&metadatos).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    if !metadatos.is_empty() {
+        params += &format!(" metadatos={}", metadatos.keys().cloned().collect::<Vec<_>>().join(","));
+    }
+    let version = versiones::Version::parse(version).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    if version != versiones::Version::Definitivo {
+        params += &format!(" version={}", version.nombre());
+    }
+    if forzar { params += " forzar"; }
+    let opciones = OpcionesCarga { tolerancia: tolerante.then_some(max_muestras), aliases, metadatos, version, forzar };
 
-    map.insert(periodo_key, eng);
-    Ok(n)
+    // Todo (parseo + inserción) ocurre sin el GIL: si otro hilo sostiene
+    // ENGINE_PERIODOS mientras espera el GIL, bloquearnos aquí con el GIL
+    // tomado sería un deadlock.
+    let carga = py.allow_threads(|| bitacora::auditar("carga", params, request_id, || {
+        cargar_periodo_con(raw, periodo_key, opciones)
+    }))?;
+    if !detalle && carga.descartes.is_none() {
+        return Ok(carga.filas.into_pyobject(py)?.into_any().unbind());
+    }
+    let out = match &carga.descartes {
+        Some(d) => {
+            if d.filas() > 0 && !carga.identica {
+                bitacora::anotar("descartes", format!("periodo_key={periodo_key} filas={}", d.filas()));
+            }
+            d.a_dict(py)?
+        }
+        None => PyDict::new(py),
+    };
+    out.set_item("filas", carga.filas)?;
+    out.set_item("identica", carga.identica)?;
+    Ok(out.into_any().unbind())
 }
 
 #[pyfunction]
-fn periodo_en_cache(periodo_key: u32) -> PyResult<bool> {
-    let guard = ENGINE_PERIODOS.read()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    Ok(guard.as_ref().map_or(false, |m| m.contains_key(&periodo_key)))
+fn periodo_en_cache(periodo_key: ArgClave) -> PyResult<bool> {
+    let periodo_key = clave(periodo_key)?;
+    let guard = ENGINE_PERIODOS.leer("periodo_en_cache")?;
+    Ok(guard.as_ref().is_some_and(|m| m.contains_key(&periodo_key)))
 }
 
+/// {canónica: nombre de la columna en el parquet} de un periodo cargado;
+/// las canónicas que no se encontraron no aparecen.
 #[pyfunction]
-fn comparar_periodos(
-    py:               Python<'_>,
-    key1:             u32,
-    key2:             u32,
-    filtro_situacion: i64,
-) -> PyResult<HashMap<String, HashMap<i64, HashMap<String, i64>>>> {
-    let result_key: ResultKey = (key1, key2, filtro_situacion);
+fn columnas_periodo(py: Python<'_>, periodo_key: ArgClave) -> PyResult<BTreeMap<&'static str, String>> {
+    let periodo_key = clave(periodo_key)?;
+    let eng = py.allow_threads(|| periodo(periodo_key))?;
+    Ok(eng.origen.iter().cloned().collect())
+}
 
-    // 1. Check RESULT_CACHE
-    {
-        let mut rcache = RESULT_CACHE.write()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-        if let Some(map) = rcache.as_mut() {
-            if let Some(hit) = map.get_mut(&result_key) {
-                hit.ultimo_acceso = now_secs();
-                hit.accesos += 1;
-                let mut out = HashMap::new();
-                out.insert("periodo1".to_string(), to_py_map(&hit.agr1));
-                out.insert("periodo2".to_string(), to_py_map(&hit.agr2));
-                return Ok(out);
-            }
-        }
+#[pyfunction]
+#[pyo3(signature = (
+    key1, key2, filtro_situacion, request_id = None, estados = None, formato = None,
+    prioridad = "normal", comprimido = false, nombres = None, con_estado_nombre = false,
+    binario = false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn comparar_periodos<'py>(
+    py:                Python<'py>,
+    key1:              ArgClave,
+    key2:              ArgClave,
+    filtro_situacion:  i64,
+    request_id:        Option<&str>,
+    estados:           Option<Vec<i64>>,
+    formato:           Option<u32>,
+    prioridad:         &str,
+    comprimido:        bool,
+    nombres:           Option<&str>,
+    con_estado_nombre: bool,
+    binario:           bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    let (key1, key2) = (clave(key1)?, clave(key2)?);
+    if comprimido && binario {
+        return Err(pyo3::exceptions::PyValueError::new_err("comprimido y binario son excluyentes"));
     }
+    // nombres: esquema de las claves de métricas ("es" | "en") solo para esta llamada
+    let metricas = metricas_formato(formato, nombres).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let prioridad = prioridad::Prioridad::parse(prioridad).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let mut params = format!("key1={key1} key2={key2} filtro={filtro_situacion}");
+    if let Some(es) = &estados { params += &format!(" estados={es:?}"); }
+    // Con `estados` solo se agregan esos estados (particion.rs)
+    let ((agr1, meta1, datos1), (agr2, meta2, datos2)) = py.allow_threads(|| {
+        bitacora::auditar("comparacion", params, request_id, || prioridad::ejecutar(prioridad, || {
+            comparar_con_meta(key1, key2, filtro_situacion, estados.as_deref())
+        }))
+    })?;
 
-    // 2. Miss: calcular con Rayon
-    let (agr1, agr2) = {
-        let guard = ENGINE_PERIODOS.read()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-        let map = guard.as_ref().ok_or_else(|| {
-            pyo3::exceptions::PyRuntimeError::new_err("No hay periodos cargados")
-        })?;
-        let e1 = map.get(&key1).ok_or_else(|| {
-            pyo3::exceptions::PyRuntimeError::new_err(format!("Periodo {key1} no cargado"))
-        })?;
-        let e2 = map.get(&key2).ok_or_else(|| {
-            pyo3::exceptions::PyRuntimeError::new_err(format!("Periodo {key2} no cargado"))
-        })?;
-        py.allow_threads(|| {
-            rayon::join(
-                || agregar(e1, filtro_situacion),
-                || agregar(e2, filtro_situacion),
-            )
-        })
-    };
-
-    // 3. Guardar en RESULT_CACHE
-    {
-        let mut rcache = RESULT_CACHE.write()
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-        let map = rcache.get_or_insert_with(HashMap::new);
-
-        if map.len() >= MAX_RESULTADOS && !map.contains_key(&result_key) {
-            if let Some(&lru_key) = map.iter()
-                .min_by_key(|(_, v)| v.ultimo_acceso)
-                .map(|(k, _)| k)
-            {
-                map.remove(&lru_key);
+    // comprimido / binario: solo los agregados, como bytes con o sin zstd
+    // (layout en empaquetado.rs), armados sin el GIL
+    if comprimido || binario {
+        let blob = py.allow_threads(|| {
+            let lado2 = (key1 != key2).then_some(&agr2);
+            if binario {
+                Ok(empaquetado::serializar(&agr1, lado2, metricas.len()))
+            } else {
+                empaquetado::empaquetar(&agr1, lado2, metricas.len())
             }
-        }
-
-        map.insert(result_key, ResultadoComp {
-            agr1: agr1.clone(),
-            agr2: agr2.clone(),
-            calculado_at:  now_secs(),
-            ultimo_acceso: now_secs(),
-            accesos:       1,
-        });
+        }).map_err(pyo3::exceptions::PyRuntimeError::new_err)?;
+        return Ok(PyBytes::new(py, &blob).into_any());
     }
 
-    let mut out = HashMap::new();
-    out.insert("periodo1".to_string(), to_py_map(&agr1));
-    out.insert("periodo2".to_string(), to_py_map(&agr2));
-    Ok(out)
+    // periodoN_vacio: ese lado no aportó filas (periodo vacío o ninguna con
+    // la situación pedida); distingue "sin datos" de "sin cambios".
+    // periodoN_meta: cuántas filas quedaron fuera y por qué (MetaFilas)
+    // huella: huella_resultado(), para comparar entre workers
+    // con_estado_nombre: cada estado lleva su nombre del catálogo (catalogo.rs)
+    let cat = if con_estado_nombre { Some(catalogo::copia()?) } else { None };
+    let out = PyDict::new(py);
+    out.set_item(pyo3::intern!(py, "periodo1"), agregado_a_dict_formato(py, &agr1, metricas, cat.as_ref())?)?;
+    out.set_item(pyo3::intern!(py, "periodo2"), agregado_a_dict_formato(py, &agr2, metricas, cat.as_ref())?)?;
+    out.set_item(pyo3::intern!(py, "periodo1_vacio"), agr1.is_empty())?;
+    out.set_item(pyo3::intern!(py, "periodo2_vacio"), agr2.is_empty())?;
+    out.set_item(pyo3::intern!(py, "periodo1_meta"), meta_a_dict(py, &meta1)?)?;
+    out.set_item(pyo3::intern!(py, "periodo2_meta"), meta_a_dict(py, &meta2)?)?;
+    out.set_item(pyo3::intern!(py, "periodo1_metadatos"), datos1)?;
+    out.set_item(pyo3::intern!(py, "periodo2_metadatos"), datos2)?;
+    out.set_item(pyo3::intern!(py, "huella"), huella_resultado(&agr1, &agr2))?;
+    Ok(out.into_any())
 }
 
 #[pyfunction]
-fn resultado_en_cache(key1: u32, key2: u32, filtro_situacion: i64) -> PyResult<bool> {
-    let guard = RESULT_CACHE.read()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    Ok(guard.as_ref().map_or(false, |m| m.contains_key(&(key1, key2, filtro_situacion))))
+fn resultado_en_cache(key1: ArgClave, key2: ArgClave, filtro_situacion: i64) -> PyResult<bool> {
+    let (key1, key2) = (clave(key1)?, clave(key2)?);
+    let guard = RESULT_CACHE.leer("resultado_en_cache")?;
+    Ok(guard.as_ref().is_some_and(|m| m.contains_key(&(key1, key2, filtro_situacion))))
+}
+
+/// Respuesta de las funciones limpiar_*: {"n", "bytes", "desalojados":
+/// [(clave..., bytes, edad_s)], "dry_run"}. edad_s cuenta desde la carga
+/// (periodos) o el cálculo (resultados); con dry_run es lo que se quitaría.
+fn desalojo<'py, T: IntoPyObject<'py>>(
+    py:          Python<'py>,
+    desalojados: Vec<T>,
+    bytes:       usize,
+    dry_run:     bool,
+) -> PyResult<PyObject> {
+    let d = PyDict::new(py);
+    d.set_item("n", desalojados.len())?;
+    d.set_item("bytes", bytes)?;
+    d.set_item("desalojados", desalojados)?;
+    d.set_item("dry_run", dry_run)?;
+    Ok(d.into_any().unbind())
 }
 
+/// Borra los resultados sin acceso en los últimos `ttl_segundos` (desalojo()).
 #[pyfunction]
-fn limpiar_resultados_expirados(ttl_segundos: u64) -> PyResult<usize> {
-    let ahora = now_secs();
-    let mut guard = RESULT_CACHE.write()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    let eliminados = if let Some(map) = guard.as_mut() {
-        let antes = map.len();
-        map.retain(|_, v| ahora.saturating_sub(v.ultimo_acceso) < ttl_segundos);
-        antes - map.len()
-    } else { 0 };
-    Ok(eliminados)
+#[pyo3(signature = (ttl_segundos, request_id = None, dry_run = false))]
+fn limpiar_resultados_expirados(
+    py:           Python<'_>,
+    ttl_segundos: u64,
+    request_id:   Option<&str>,
+    dry_run:      bool,
+) -> PyResult<PyObject> {
+    let params = format!("ttl_segundos={ttl_segundos} dry_run={dry_run}");
+    let mut expirados = bitacora::auditar("limpiar_resultados_expirados", params, request_id, || {
+        let ahora = now_secs();
+        let expirado = |v: &ResultadoComp| ahora.saturating_sub(v.ultimo_acceso) >= ttl_segundos;
+        let entrada = |&(k1, k2, f): &ResultKey, v: &ResultadoComp| {
+            (k1, k2, f, v.bytes(), ahora.saturating_sub(v.calculado_at))
+        };
+        if dry_run {
+            let guard = RESULT_CACHE.leer("limpiar_resultados_expirados")?;
+            return Ok::<_, Error>(guard.iter().flatten()
+                .filter(|(_, v)| expirado(v))
+                .map(|(k, v)| entrada(k, v))
+                .collect::<Vec<_>>());
+        }
+        let mut guard = RESULT_CACHE.escribir("limpiar_resultados_expirados")?;
+        let mut quitados = Vec::new();
+        if let Some(map) = guard.as_mut() {
+            map.retain(|k, v| {
+                let sale = expirado(v);
+                if sale { quitados.push(entrada(k, v)); }
+                !sale
+            });
+        }
+        metricas::contar_eviccion(Cache::Resultados, Motivo::Ttl, quitados.len() as u64);
+        Ok(quitados)
+    })?;
+    expirados.sort_unstable();
+    let bytes = expirados.iter().map(|e| e.3).sum();
+    desalojo(py, expirados, bytes, dry_run)
 }
 
+/// Deja los `mantener` periodos históricos (año != año_actual) más recién
+/// usados y desaloja el resto. Los del año actual no se tocan salvo que se
+/// pase `mantener_actual`: entonces también se desalojan los que excedan esa
+/// cuota propia. desalojo() con los periodos, el menos reciente primero;
+/// con `dry_run` solo los calcula, sin quitar nada.
 #[pyfunction]
-fn limpiar_periodos_lru(mantener: usize, año_actual: u32) -> PyResult<usize> {
-    let mut guard = ENGINE_PERIODOS.write()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    let eliminados = if let Some(map) = guard.as_mut() {
-        let mut historicos: Vec<(PeriodoKey, u64)> = map.iter()
-            .filter(|(&k, _)| k / 100 != año_actual)
-            .map(|(&k, v)| (k, v.ultimo_acceso))
+#[pyo3(signature = (mantener, año_actual, mantener_actual = None, dry_run = false, request_id = None))]
+fn limpiar_periodos_lru(
+    py:              Python<'_>,
+    mantener:        usize,
+    año_actual:      u32,
+    mantener_actual: Option<usize>,
+    dry_run:         bool,
+    request_id:      Option<&str>,
+) -> PyResult<PyObject> {
+    let params = format!(
+        "mantener={mantener} año_actual={año_actual} mantener_actual={mantener_actual:?} dry_run={dry_run}"
+    );
+    let victimas = bitacora::auditar("limpiar_periodos_lru", params, request_id, || {
+        lru_periodos(mantener, año_actual, mantener_actual, dry_run)
+    })?;
+    let bytes = victimas.iter().map(|v| v.1).sum();
+    desalojo(py, victimas, bytes, dry_run)
+}
+
+fn lru_periodos(
+    mantener:        usize,
+    año_actual:      u32,
+    mantener_actual: Option<usize>,
+    dry_run:         bool,
+) -> PyResult<Vec<(PeriodoKey, usize, u64)>> {
+    if config::claves_calendario() && !AÑOS_VALIDOS.contains(&año_actual) {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "año_actual inválido: {año_actual} (se espera {}..={})", AÑOS_VALIDOS.start(), AÑOS_VALIDOS.end(),
+        )));
+    }
+    let fijados = fijados::actuales()?;
+    let mut guard = ENGINE_PERIODOS.escribir("limpiar_periodos_lru")?;
+    let Some(map) = guard.as_mut() else { return Ok(Vec::new()) };
+
+    // Sobrantes de un grupo: los menos recientes más allá de su cuota. Los
+    // fijados ocupan cuota pero no salen.
+    let sobrantes = |actual: bool, cuota: usize| {
+        let mut grupo: Vec<(u64, PeriodoKey)> = map.iter()
+            .filter(|(&k, _)| (k / 100 == año_actual) == actual)
+            .map(|(&k, v)| (v.ultimo_acceso.load(Ordering::Relaxed), k))
             .collect();
-        historicos.sort_by_key(|&(_, ts)| ts);
-        let a_eliminar = historicos.len().saturating_sub(mantener);
-        for (k, _) in historicos.iter().take(a_eliminar) {
+        grupo.sort_unstable();
+        let n = grupo.len().saturating_sub(cuota);
+        grupo.truncate(n);
+        grupo.retain(|(_, k)| !fijados.contains(k));
+        grupo
+    };
+    let mut victimas = sobrantes(false, mantener);
+    if let Some(cuota) = mantener_actual {
+        victimas.extend(sobrantes(true, cuota));
+        victimas.sort_unstable();
+    }
+    let ahora = now_secs();
+    let victimas: Vec<(PeriodoKey, usize, u64)> = victimas.into_iter()
+        .map(|(_, k)| (k, map[&k].bytes(), ahora.saturating_sub(map[&k].cargado_at)))
+        .collect();
+
+    if !dry_run {
+        for (k, _, _) in &victimas {
             map.remove(k);
         }
-        a_eliminar
-    } else { 0 };
-    Ok(eliminados)
+        metricas::contar_eviccion(Cache::Periodos, Motivo::Lru, victimas.len() as u64);
+        if !victimas.is_empty() { buffers::tras_eviccion(); }
+    }
+    Ok(victimas)
 }
 
 #[pyfunction]
-fn evict_periodo(periodo_key: u32) -> PyResult<bool> {
-    let mut guard = ENGINE_PERIODOS.write()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    Ok(guard.as_mut().map_or(false, |m| m.remove(&periodo_key).is_some()))
+#[pyo3(signature = (periodo_key, request_id = None))]
+fn evict_periodo(periodo_key: ArgClave, request_id: Option<&str>) -> PyResult<bool> {
+    let periodo_key = clave(periodo_key)?;
+    Ok(bitacora::auditar("evict_periodo", format!("periodo_key={periodo_key}"), request_id, || {
+        quitar_periodo(periodo_key)
+    })?)
 }
 
 #[pyfunction]
-fn evict_resultado(key1: u32, key2: u32, filtro_situacion: i64) -> PyResult<bool> {
-    let mut guard = RESULT_CACHE.write()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    Ok(guard.as_mut().map_or(false, |m| m.remove(&(key1, key2, filtro_situacion)).is_some()))
+#[pyo3(signature = (key1, key2, filtro_situacion, request_id = None))]
+fn evict_resultado(key1: ArgClave, key2: ArgClave, filtro_situacion: i64, request_id: Option<&str>) -> PyResult<bool> {
+    let (key1, key2) = (clave(key1)?, clave(key2)?);
+    let params = format!("key1={key1} key2={key2} filtro={filtro_situacion}");
+    Ok(bitacora::auditar("evict_resultado", params, request_id, || {
+        quitar_resultado((key1, key2, filtro_situacion))
+    })?)
 }
 
+/// Pone en cache `nueva_clave` = `key_base` con los periodos invertidos,
+/// copiando el resultado ya calculado (botón "invertir comparación"). Las
+/// claves son (key1, key2, filtro_situacion). False si `key_base` no está en
+/// cache: hay que compararla primero.
 #[pyfunction]
-fn engine_recursos() -> PyResult<HashMap<String, u64>> {
-    let mut stats = HashMap::new();
-    if let Ok(g) = ENGINE_PERIODOS.read() {
-        let (n_p, filas, ram) = g.as_ref().map_or((0, 0, 0), |m| {
-            let f: usize = m.values().map(|e| e.n).sum();
-            (m.len(), f, f * 96 / 1024)  // 96 bytes por fila (7 i64 + coords)
-        });
-        stats.insert("periodos_cargados".into(), n_p as u64);
-        stats.insert("filas_totales".into(),     filas as u64);
-        stats.insert("ram_datos_kb".into(),      ram as u64);
-    }
-    if let Ok(g) = RESULT_CACHE.read() {
-        let (n_r, hits) = g.as_ref().map_or((0, 0), |m| {
-            let h: u64 = m.values().map(|v| v.accesos).sum();
-            (m.len(), h)
-        });
-        stats.insert("resultados_cacheados".into(), n_r as u64);
-        stats.insert("cache_hits_total".into(),     hits);
-        stats.insert("max_resultados".into(),       MAX_RESULTADOS as u64);
-    }
-    stats.insert("max_periodos".into(), MAX_PERIODOS as u64);
-    Ok(stats)
+#[pyo3(signature = (key_base, nueva_clave, request_id = None))]
+fn derivar_resultado(
+    key_base:    (ArgClave, ArgClave, i64),
+    nueva_clave: (ArgClave, ArgClave, i64),
+    request_id:  Option<&str>,
+) -> PyResult<bool> {
+    let base  = (clave(key_base.0)?, clave(key_base.1)?, key_base.2);
+    let nueva = (clave(nueva_clave.0)?, clave(nueva_clave.1)?, nueva_clave.2);
+    let params = format!("base={base:?} nueva={nueva:?}");
+    Ok(bitacora::auditar("derivar_resultado", params, request_id, || derivar(base, nueva))?)
 }
 
 #[pyfunction]
-fn cache_info() -> PyResult<Vec<HashMap<String, u64>>> {
-    let guard = RESULT_CACHE.read()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
+fn engine_recursos() -> PyResult<BTreeMap<String, u64>> {
+    Ok(recursos())
+}
+
+/// Entradas de RESULT_CACHE, las más accedidas primero. Bajo el lock solo se
+/// copian los números de cada entrada; filtrar, ordenar y armar los dicts
+/// va sin él. `periodo_key` deja las que lo mencionan en cualquier lado,
+/// `min_accesos` las que tienen al menos esos accesos; `offset`/`limite`
+/// paginan después de ordenar.
+#[pyfunction]
+#[pyo3(signature = (periodo_key = None, min_accesos = 0, offset = 0, limite = None))]
+fn cache_info(
+    periodo_key: Option<ArgClave>,
+    min_accesos: u64,
+    offset:      usize,
+    limite:      Option<usize>,
+) -> PyResult<Vec<BTreeMap<String, u64>>> {
+    let periodo_key = periodo_key.map(clave).transpose()?;
+    // (clave, accesos, calculado_at, ultimo_acceso, bytes, comprimido)
+    let mut filas: Vec<(ResultKey, u64, u64, u64, usize, bool)> = {
+        let guard = RESULT_CACHE.leer("cache_info")?;
+        guard.iter().flatten()
+            .filter(|(&(k1, k2, _), v)| {
+                v.accesos >= min_accesos && periodo_key.is_none_or(|k| k == k1 || k == k2)
+            })
+            .map(|(&k, v)| (k, v.accesos, v.calculado_at, v.ultimo_acceso, v.bytes(), v.comprimido()))
+            .collect()
+    };
+    // Más accedidos primero; empates por clave para que el orden sea estable
+    let orden = |&((k1, k2, f), ..): &(ResultKey, u64, u64, u64, usize, bool)| (k1, k2, f as u64);
+    filas.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| orden(a).cmp(&orden(b))));
     let ahora = now_secs();
-    let mut infos = Vec::new();
-    if let Some(map) = guard.as_ref() {
-        for (&(k1, k2, filtro), v) in map.iter() {
-            let mut info = HashMap::new();
-            info.insert("key1".into(),       k1 as u64);
-            info.insert("key2".into(),       k2 as u64);
-            info.insert("filtro".into(),     filtro as u64);
-            info.insert("accesos".into(),    v.accesos);
-            info.insert("edad_s".into(),     ahora.saturating_sub(v.calculado_at));
-            info.insert("inactivo_s".into(), ahora.saturating_sub(v.ultimo_acceso));
-            infos.push(info);
-        }
-    }
-    infos.sort_by(|a, b| b["accesos"].cmp(&a["accesos"]));
-    Ok(infos)
+    Ok(filas.into_iter()
+        .skip(offset)
+        .take(limite.unwrap_or(usize::MAX))
+        .map(|((k1, k2, filtro), accesos, calculado_at, ultimo_acceso, bytes, comprimido)| {
+            BTreeMap::from([
+                ("key1".into(),       k1 as u64),
+                ("key2".into(),       k2 as u64),
+                ("filtro".into(),     filtro as u64),
+                ("accesos".into(),    accesos),
+                ("edad_s".into(),     ahora.saturating_sub(calculado_at)),
+                ("inactivo_s".into(), ahora.saturating_sub(ultimo_acceso)),
+                ("bytes".into(),      bytes as u64),
+                ("comprimido".into(), comprimido as u64),
+            ])
+        })
+        .collect())
 }
 
 // ===========================================================================
-// FUNCIONES LEGACY
+// FUNCIONES LEGACY (sin periodo_key usan ENGINE: legado.rs)
 // ===========================================================================
 
 fn extract_f64(list: &Bound<'_, PyList>) -> PyResult<Vec<f64>> {
@@ -543,13 +2609,113 @@ fn haversine(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
     R * 2.0 * a.sqrt().atan2((1.0 - a).sqrt())
 }
 
+/// Rumbo inicial (radianes, desde el norte en sentido horario, en (-π, π])
+/// del camino más corto de (lat1, lng1) a (lat2, lng2).
+fn rumbo(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    let (p1, p2) = (lat1.to_radians(), lat2.to_radians());
+    let dl = (lng2 - lng1).to_radians();
+    (dl.sin() * p2.cos()).atan2(p1.cos() * p2.sin() - p1.sin() * p2.cos() * dl.cos())
+}
+
+/// Caja lat/lng que contiene todo punto a <= dist_km de un centro: filtro
+/// barato antes de haversine. En lng usa el ancho exacto del círculo sobre
+/// la esfera, asin(sin r / cos lat); cerca de los polos (o con un radio de
+/// medio planeta) no filtra por lng. Respeta el antimeridiano.
+#[derive(Clone, Copy)]
+struct CajaRadio {
+    lat:  f64,
+    lng:  f64,
+    dlat: f64,
+    // None = cualquier lng
+    dlng: Option<f64>,
+}
+
+impl CajaRadio {
+    fn new(lat: f64, lng: f64, dist_km: f64) -> Self {
+        const R: f64 = 6_371.0;
+        // margen relativo contra el redondeo de haversine en el borde
+        const HOLGURA: f64 = 1.0 + 1e-9;
+        let r = (dist_km / R * HOLGURA).max(0.0);
+        if r.is_nan() || r >= std::f64::consts::FRAC_PI_2 {
+            return CajaRadio { lat, lng, dlat: f64::INFINITY, dlng: None };
+        }
+        let cos_lat = lat.to_radians().cos();
+        let dlng = (r.sin() < cos_lat).then(|| (r.sin() / cos_lat).asin().to_degrees() * HOLGURA);
+        CajaRadio { lat, lng, dlat: r.to_degrees(), dlng }
+    }
+
+    #[inline(always)]
+    fn contiene(&self, lat: f64, lng: f64) -> bool {
+        if lat.is_nan() || lng.is_nan() || (lat - self.lat).abs() > self.dlat { return false; }
+        let Some(dlng) = self.dlng else { return true };
+        let d = (lng - self.lng).abs() % 360.0;
+        d.min(360.0 - d) <= dlng
+    }
+}
+
+/// Distancias en km (sin redondear) desde (lat, lng) a cada punto de los
+/// arrays. Coordenadas NaN producen NaN en la posición correspondiente.
+#[pyfunction]
+fn haversine_batch<'py>(
+    py:   Python<'py>,
+    lat:  f64,
+    lng:  f64,
+    lats: PyReadonlyArray1<'py, f64>,
+    lngs: PyReadonlyArray1<'py, f64>,
+) -> PyResult<Bound<'py, PyArray1<f64>>> {
+    if lats.len()? != lngs.len()? {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            format!("Arrays distinta longitud. lats={} lngs={}", lats.len()?, lngs.len()?)
+        ));
+    }
+    // as_slice() falla con arrays no contiguos (vistas con stride): copiar
+    let lv = lats.as_array().to_vec();
+    let gv = lngs.as_array().to_vec();
+    let dist = |(&la, &lo): (&f64, &f64)| {
+        if la.is_nan() || lo.is_nan() { f64::NAN } else { haversine(lat, lng, la, lo) }
+    };
+    let out: Vec<f64> = if config::secuencial(lv.len()) {
+        lv.iter().zip(&gv).map(dist).collect()
+    } else {
+        py.allow_threads(|| config::en_pool(|| lv.par_iter().zip(gv.par_iter()).map(dist).collect()))
+    };
+    Ok(PyArray1::from_vec(py, out))
+}
+
+/// Obsoleta (legado.rs): cargar el periodo con cargar_periodo_parquet.
+/// cn_ini / cn_prim / cn_sec son opcionales (sin ellos quedan nulas y
+/// suman 0); todas las listas dadas deben tener el largo de `lats`.
 #[pyfunction]
+#[pyo3(signature = (
+    lats, lngs, estado_ids, situaciones, inc_totales, aten_totales, cn_totales,
+    cn_ini = None, cn_prim = None, cn_sec = None,
+))]
+#[allow(clippy::too_many_arguments)]
 fn init_engine(
+    py: Python<'_>,
     lats: &Bound<'_, PyList>, lngs: &Bound<'_, PyList>,
     estado_ids: &Bound<'_, PyList>, situaciones: &Bound<'_, PyList>,
     inc_totales: &Bound<'_, PyList>, aten_totales: &Bound<'_, PyList>,
     cn_totales: &Bound<'_, PyList>,
+    cn_ini: Option<&Bound<'_, PyList>>, cn_prim: Option<&Bound<'_, PyList>>,
+    cn_sec: Option<&Bound<'_, PyList>>,
 ) -> PyResult<usize> {
+    legado::avisar(py, "init_engine", "cargar el periodo con cargar_periodo_parquet")?;
+    let n = lats.len();
+    // Todas las diferencias en un solo error
+    let largos = [
+        ("lngs", Some(lngs)), ("estado_ids", Some(estado_ids)), ("situaciones", Some(situaciones)),
+        ("inc_totales", Some(inc_totales)), ("aten_totales", Some(aten_totales)),
+        ("cn_totales", Some(cn_totales)), ("cn_ini", cn_ini), ("cn_prim", cn_prim), ("cn_sec", cn_sec),
+    ];
+    let distintas: Vec<String> = largos.iter()
+        .filter_map(|(nombre, l)| l.filter(|l| l.len() != n).map(|l| format!("{nombre}={}", l.len())))
+        .collect();
+    if !distintas.is_empty() {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            format!("Arrays distinta longitud. lats={n}, {}", distintas.join(", "))
+        ));
+    }
     let lv  = extract_f64(lats)?;
     let gnv = extract_f64(lngs)?;
     let ev  = extract_i64(estado_ids)?;
@@ -557,83 +2723,360 @@ fn init_engine(
     let iv  = extract_i64(inc_totales)?;
     let av  = extract_i64(aten_totales)?;
     let cv  = extract_i64(cn_totales)?;
-    let n   = lv.len();
-    if [gnv.len(), ev.len(), sv.len(), iv.len(), av.len(), cv.len()].iter().any(|&l| l != n) {
-        return Err(pyo3::exceptions::PyValueError::new_err(
-            format!("Arrays distinta longitud. lats={n}")
-        ));
-    }
+    let opcional = |l: Option<&Bound<'_, PyList>>| l.map_or_else(|| Ok(vec![i64::MIN; n]), extract_i64);
+    let (ini, prim, sec) = (opcional(cn_ini)?, opcional(cn_prim)?, opcional(cn_sec)?);
     let now = now_secs();
-    *ENGINE.write().map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))? =
-        Some(EngineData {
-            n, lats: lv, lngs: gnv, estado_ids: ev, situaciones: sv,
-            inc_totales: iv, aten_totales: av, cn_totales: cv,
-            cn_ini:  vec![i64::MIN; n],
-            cn_prim: vec![i64::MIN; n],
-            cn_sec:  vec![i64::MIN; n],
-            cargado_at: now, ultimo_acceso: now,
-        });
+    *ENGINE.escribir("init_engine")? =
+        Some(Arc::new(EngineData {
+            n, lats: lv.into(), lngs: gnv.into(), estado_ids: ev.into(), situaciones: sv.into(),
+            inc_totales: iv.into(), aten_totales: av.into(), cn_totales: cv.into(),
+            cn_ini:  ini.into(),
+            cn_prim: prim.into(),
+            cn_sec:  sec.into(),
+            plaza_ids: Vec::new().into(),
+            plazas:  Vec::new(),
+            grupos:  Vec::new(),
+            orden:   Vec::new(),
+            bitmaps: Vec::new(),
+            otras_situaciones: Vec::new(),
+            origen:  Vec::new(),
+            metadatos: BTreeMap::new(),
+            estadisticas: Vec::new(),
+            huella: 0,
+            descartes: None,
+            cargado_at: now,
+            ultimo_acceso: AtomicU64::new(now),
+            accesos:       AtomicU64::new(0),
+        }.agrupar_por_estado().indexar_situaciones()));
     Ok(n)
 }
 
+/// Apunta ENGINE al periodo ya cargado `periodo_key`, compartiendo su Arc
+/// (sin copiar columnas): distancias_cercanas, filtrar_indices y
+/// agregaciones_por_estado responden sobre ese periodo. Si después se
+/// desaloja de la cache, ENGINE lo sigue reteniendo hasta el próximo init.
+/// Devuelve las filas.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, request_id = None))]
+fn init_engine_desde_periodo(periodo_key: ArgClave, request_id: Option<&str>) -> PyResult<usize> {
+    let key = clave(periodo_key)?;
+    bitacora::auditar("init_engine_desde_periodo", format!("periodo_key={key}"), request_id, || {
+        let eng = periodo(key)?;
+        eng.tocar();
+        let n = eng.n;
+        *ENGINE.escribir("init_engine_desde_periodo")? = Some(eng);
+        Ok::<_, Error>(n)
+    }).map_err(Into::into)
+}
+
+/// Filtros de las consultas de cercanía sobre el motor (< 0 = sin filtro).
+#[derive(Clone, Copy)]
+struct FiltroCercania {
+    situacion: i64,
+    estado_id: i64,
+    sector:    Option<Sector>,
+}
+
+/// Sector de rumbos desde el punto de consulta, en grados desde el norte en
+/// sentido horario: de `desde` a `hasta` girando a la derecha, así que
+/// (315, 45) es "hacia el norte" y (0, 90) "al noreste".
+#[derive(Clone, Copy)]
+struct Sector {
+    desde: f64,
+    hasta: f64,
+}
+
+impl Sector {
+    /// None si el sector cubre la vuelta completa.
+    fn new((desde, hasta): (f64, f64)) -> PyResult<Option<Self>> {
+        if !desde.is_finite() || !hasta.is_finite() {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!("sector inválido: ({desde}, {hasta})")));
+        }
+        if hasta - desde >= 360.0 { return Ok(None); }
+        Ok(Some(Sector { desde: desde.rem_euclid(360.0), hasta: hasta.rem_euclid(360.0) }))
+    }
+
+    /// El propio punto de consulta (sin rumbo) siempre está en el sector.
+    fn contiene(self, (lat_u, lng_u): (f64, f64), lat: f64, lng: f64) -> bool {
+        if lat == lat_u && lng == lng_u { return true; }
+        let r = rumbo(lat_u, lng_u, lat, lng).to_degrees().rem_euclid(360.0);
+        if self.desde <= self.hasta {
+            self.desde <= r && r <= self.hasta
+        } else {
+            r >= self.desde || r <= self.hasta
+        }
+    }
+}
+
+/// (fila original, km, fila interna) de hasta `limite` filas a <= dist_max
+/// que pasan `filtro`, de la más cercana a la más lejana (empate: la fila
+/// original menor). Sin redondear.
+fn cercanas(
+    eng:      &EngineData,
+    (lat_u, lng_u): (f64, f64),
+    dist_max: f64,
+    limite:   usize,
+    filtro:   FiltroCercania,
+) -> Vec<(usize, f64, usize)> {
+    // Motor agrupado: con estado_id solo se recorre su rango
+    let rango = match (filtro.estado_id >= 0, eng.grupos.is_empty()) {
+        (true, false) => eng.rango_estado(filtro.estado_id).unwrap_or(0..0),
+        _             => 0..eng.n,
+    };
+    // La caja descarta casi todas las filas sin trigonometría (NaN no pasa)
+    let caja = CajaRadio::new(lat_u, lng_u, dist_max);
+    let cerca = |i: usize| {
+        if filtro.estado_id >= 0 && eng.estado_ids.get(i) != filtro.estado_id { return None; }
+        if filtro.situacion >= 0 && !eng.tiene_situacion(i, filtro.situacion) { return None; }
+        let lat = eng.lats.get(i);
+        let lng = eng.lngs.get(i);
+        if !caja.contiene(lat, lng) { return None; }
+        let d = haversine(lat_u, lng_u, lat, lng);
+        // Negado a propósito: con dist_max NaN la caja queda en radio 0 y
+        // `d > NaN` dejaría pasar el punto exacto de la consulta
+        #[allow(clippy::neg_cmp_op_on_partial_ord)]
+        if !(d <= dist_max) { return None; }
+        if filtro.sector.is_some_and(|s| !s.contiene((lat_u, lng_u), lat, lng)) { return None; }
+        Some((eng.fila_original(i), d, i))
+    };
+    let mut res: Vec<(usize, f64, usize)> = if config::secuencial(rango.len()) {
+        rango.filter_map(cerca).collect()
+    } else {
+        config::en_pool(|| rango.into_par_iter().filter_map(cerca).collect())
+    };
+    // Top-k: select_nth deja los `limite` más cercanos al frente en O(n) y
+    // solo esos se ordenan
+    let orden = |a: &(usize, f64, usize), b: &(usize, f64, usize)| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0));
+    if limite == 0 {
+        return Vec::new();
+    }
+    if res.len() > limite {
+        res.select_nth_unstable_by(limite - 1, orden);
+        res.truncate(limite);
+    }
+    res.sort_unstable_by(orden);
+    res
+}
+
+/// Unidad de las distancias que entran y salen de las consultas de cercanía.
+#[derive(Clone, Copy)]
+enum Unidad {
+    Km,
+    M,
+    Mi,
+}
+
+impl Unidad {
+    fn parse(s: &str) -> PyResult<Self> {
+        match s {
+            "km" => Ok(Unidad::Km),
+            "m"  => Ok(Unidad::M),
+            "mi" => Ok(Unidad::Mi),
+            otro => Err(pyo3::exceptions::PyValueError::new_err(format!("unidad desconocida: {otro:?} (km | m | mi)"))),
+        }
+    }
+
+    /// Unidades por km.
+    fn por_km(self) -> f64 {
+        match self {
+            Unidad::Km => 1.0,
+            Unidad::M  => 1_000.0,
+            Unidad::Mi => 1.0 / 1.609_344,
+        }
+    }
+}
+
+/// Resultado de cercanas() para Python en `unidad`, redondeado a `decimales`
+/// (None = sin redondear) después de ordenar y truncar: [(fila, distancia)]
+/// o, con `incluir`, [{"indice", "distancia", <columna>: valor, ...}]
+/// (nombres de columna como en calidad.rs).
+fn cercanas_a_python(
+    py:        Python<'_>,
+    eng:       &EngineData,
+    res:       Vec<(usize, f64, usize)>,
+    incluir:   Option<Vec<String>>,
+    unidad:    Unidad,
+    decimales: Option<i32>,
+) -> PyResult<PyObject> {
+    let campos: Vec<(String, calidad::Campo)> = incluir.unwrap_or_default().into_iter()
+        .map(|c| calidad::Campo::parse(&c).map(|k| (c, k)))
+        .collect::<Result<_, _>>()
+        .map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let escala = decimales.map(|d| 10f64.powi(d));
+    let redondear = |km: f64| {
+        let d = km * unidad.por_km();
+        escala.map_or(d, |e| (d * e).round() / e)
+    };
+    if campos.is_empty() {
+        let pares: Vec<(usize, f64)> = res.into_iter().map(|(o, d, _)| (o, redondear(d))).collect();
+        return Ok(pares.into_pyobject(py)?.into_any().unbind());
+    }
+    let filas = res.into_iter().map(|(o, d, i)| {
+        let f = PyDict::new(py);
+        f.set_item("indice", o)?;
+        f.set_item("distancia", redondear(d))?;
+        for (nombre, campo) in &campos {
+            f.set_item(nombre, campo.a_python(py, eng, i)?)?;
+        }
+        Ok(f)
+    }).collect::<PyResult<Vec<_>>>()?;
+    Ok(PyList::new(py, filas)?.into_any().unbind())
+}
+
+/// [(fila, distancia)] de hasta `limite` filas a <= dist_max con la
+/// `situacion` y el `estado_id` pedidos (< 0 = todos), de la más cercana a
+/// la más lejana. `dist_max` y las distancias van en `unidad` (km | m | mi)
+/// redondeadas a `decimales` (None = sin redondear). Con `incluir` cada
+/// resultado es un dict (cercanas_a_python). `sector=(desde, hasta)` en
+/// grados desde el norte restringe el rumbo ("a 50 km al noreste": (0, 90)).
+/// Sobre el periodo `periodo_key` (sin él, el motor legado: legado.rs).
 #[pyfunction]
-fn distancias_cercanas(lat_u: f64, lng_u: f64, dist_max: f64, limite: usize) -> PyResult<Vec<(usize, f64)>> {
-    let guard = ENGINE.read()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    let eng = guard.as_ref()
-        .ok_or_else(|| pyo3::exceptions::PyRuntimeError::new_err("Motor no init."))?;
+#[pyo3(signature = (
+    lat_u, lng_u, dist_max, limite, incluir = None, situacion = -1, estado_id = -1,
+    unidad = "km", decimales = Some(2), sector = None, periodo_key = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn distancias_cercanas(
+    py:        Python<'_>,
+    lat_u:     f64,
+    lng_u:     f64,
+    dist_max:  f64,
+    limite:    usize,
+    incluir:   Option<Vec<String>>,
+    situacion: i64,
+    estado_id: i64,
+    unidad:    &str,
+    decimales: Option<i32>,
+    sector:    Option<(f64, f64)>,
+    periodo_key: Option<ArgClave>,
+) -> PyResult<PyObject> {
+    let unidad = Unidad::parse(unidad)?;
+    let sector = sector.map(Sector::new).transpose()?.flatten();
+    let eng = legado::motor(py, "distancias_cercanas", periodo_key)?;
     if lat_u.is_nan() || lng_u.is_nan() {
         return Err(pyo3::exceptions::PyValueError::new_err("lat/lng no pueden ser NaN"));
     }
-    let mut res: Vec<(usize, f64)> = (0..eng.n).into_par_iter().filter_map(|i| {
-        let lat = eng.lats[i];
-        let lng = eng.lngs[i];
-        if lat.is_nan() || lng.is_nan() { return None; }
-        let d = haversine(lat_u, lng_u, lat, lng);
-        if d <= dist_max { Some((i, (d * 100.0).round() / 100.0)) } else { None }
-    }).collect();
-    res.sort_unstable_by(|a, b| {
-        a.1.partial_cmp(&b.1)
-            .unwrap_or(std::cmp::Ordering::Equal)
-            .then_with(|| a.0.cmp(&b.0))
-    });
-    res.truncate(limite);
-    Ok(res)
+    let filtro = FiltroCercania { situacion, estado_id, sector };
+    let res = cercanas(&eng, (lat_u, lng_u), dist_max / unidad.por_km(), limite, filtro);
+    cercanas_a_python(py, &eng, res, incluir, unidad, decimales)
+}
+
+/// Las `k` filas más cercanas sin límite de distancia ("la plaza ACTIVA más
+/// cercana": k=1, situacion=ACTIVA). Mismo formato que distancias_cercanas.
+#[pyfunction]
+#[pyo3(signature = (
+    lat_u, lng_u, k, incluir = None, situacion = -1, estado_id = -1, unidad = "km", decimales = Some(2),
+    sector = None, periodo_key = None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn k_mas_cercanas(
+    py:        Python<'_>,
+    lat_u:     f64,
+    lng_u:     f64,
+    k:         usize,
+    incluir:   Option<Vec<String>>,
+    situacion: i64,
+    estado_id: i64,
+    unidad:    &str,
+    decimales: Option<i32>,
+    sector:    Option<(f64, f64)>,
+    periodo_key: Option<ArgClave>,
+) -> PyResult<PyObject> {
+    distancias_cercanas(
+        py, lat_u, lng_u, f64::INFINITY, k, incluir, situacion, estado_id, unidad, decimales, sector, periodo_key,
+    )
 }
 
+/// Agregado por estado de un periodo sin pasar por RESULT_CACHE; sin
+/// `periodo_key`, del motor legado (legado.rs).
 #[pyfunction]
-fn agregaciones_por_estado(filtro_situacion: i64) -> PyResult<HashMap<i64, HashMap<String, i64>>> {
-    let guard = ENGINE.read()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    let eng = guard.as_ref()
-        .ok_or_else(|| pyo3::exceptions::PyRuntimeError::new_err("Motor no init."))?;
-    Ok(to_py_map(&agregar(eng, filtro_situacion)))
+#[pyo3(signature = (
+    filtro_situacion, formato = None, meta = false, periodo_key = None, nombres = None,
+    con_estado_nombre = false,
+))]
+fn agregaciones_por_estado<'py>(
+    py:                Python<'py>,
+    filtro_situacion:  i64,
+    formato:           Option<u32>,
+    meta:              bool,
+    periodo_key:       Option<ArgClave>,
+    nombres:           Option<&str>,
+    con_estado_nombre: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let metricas = metricas_formato(formato, nombres).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let cat = if con_estado_nombre { Some(catalogo::copia()?) } else { None };
+    let eng = legado::motor(py, "agregaciones_por_estado", periodo_key)?;
+    let agr = config::en_pool_si(eng.n, || agregar(&eng, filtro_situacion))
+        .map_err(pyo3::exceptions::PyRuntimeError::new_err)?;
+    let estados = agregado_a_dict_formato(py, &agr, metricas, cat.as_ref())?;
+    if !meta { return Ok(estados); }
+    // meta=True: {"estados": {...}, "meta": MetaFilas}
+    let m = meta_filas(&ResumenLado::de(std::slice::from_ref(&eng)), &agr, None);
+    let out = PyDict::new(py);
+    out.set_item("estados", estados)?;
+    out.set_item("meta", meta_a_dict(py, &m)?)?;
+    Ok(out)
 }
 
+/// Filas (índice original) del estado y la situación pedidos (-1 = todos)
+/// que además cumplen `donde` (predicados.rs), p. ej. "aten_total == 0".
+/// Sobre el periodo `periodo_key` (sin él, el motor legado: legado.rs).
 #[pyfunction]
-fn filtrar_indices(estado_id: i64, situacion: i64) -> PyResult<Vec<usize>> {
-    let guard = ENGINE.read()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    let eng = guard.as_ref()
-        .ok_or_else(|| pyo3::exceptions::PyRuntimeError::new_err("Motor no init."))?;
-    let mut v: Vec<usize> = (0..eng.n).into_par_iter().filter(|&i| {
+#[pyo3(signature = (estado_id, situacion, donde = None, periodo_key = None))]
+fn filtrar_indices(
+    py:          Python<'_>,
+    estado_id:   i64,
+    situacion:   i64,
+    donde:       Option<&str>,
+    periodo_key: Option<ArgClave>,
+) -> PyResult<Vec<usize>> {
+    let donde = predicados::Predicado::parse(donde.unwrap_or(""))
+        .map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let eng = legado::motor(py, "filtrar_indices", periodo_key)?;
+    let eng = eng.as_ref();
+    // Motor agrupado: un filtro por estado recorre solo el rango de ese estado
+    let rango = match (estado_id >= 0, eng.grupos.is_empty()) {
+        (true, false) => eng.rango_estado(estado_id).unwrap_or(0..0),
+        _             => 0..eng.n,
+    };
+    // Bitmap de situación: AND con el rango del estado sin evaluar fila por fila
+    if situacion >= 0 {
+        if let Some(bitmap) = eng.bitmap(situacion) {
+            let mut v: Vec<usize> = bitmap
+                .map(|bits| bits_en_rango(bits, rango.start, rango.end)
+                    .filter(|&i| estado_id < 0 || eng.estado_ids.get(i) == estado_id)
+                    .filter(|&i| donde.cumple(eng, i))
+                    .map(|i| eng.fila_original(i))
+                    .collect())
+                .unwrap_or_default();
+            v.sort_unstable();
+            return Ok(v);
+        }
+    }
+    let pasa = |i: &usize| {
         let ok_e = if estado_id < 0 { true } else {
-            eng.estado_ids[i] != i64::MIN && eng.estado_ids[i] == estado_id
+            eng.estado_ids.get(*i) != i64::MIN && eng.estado_ids.get(*i) == estado_id
         };
         let ok_s = if situacion < 0 { true } else {
-            eng.situaciones[i] != i64::MIN && eng.situaciones[i] == situacion
+            eng.tiene_situacion(*i, situacion)
         };
-        ok_e && ok_s
-    }).collect();
+        ok_e && ok_s && donde.cumple(eng, *i)
+    };
+    let mut v: Vec<usize> = if config::secuencial(rango.len()) {
+        rango.filter(pasa).map(|i| eng.fila_original(i)).collect()
+    } else {
+        config::en_pool(|| rango.into_par_iter().filter(pasa).map(|i| eng.fila_original(i)).collect())
+    };
     v.sort_unstable();
     Ok(v)
 }
 
+/// Obsoleta (legado.rs): listar_periodos() describe los periodos cargados.
 #[pyfunction]
-fn engine_stats() -> PyResult<HashMap<String, usize>> {
-    let guard = ENGINE.read()
-        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("RwLock: {e}")))?;
-    let mut s = HashMap::new();
+fn engine_stats(py: Python<'_>) -> PyResult<BTreeMap<String, usize>> {
+    legado::avisar(py, "engine_stats", "usar listar_periodos()")?;
+    let guard = ENGINE.leer("engine_stats")?;
+    let mut s = BTreeMap::new();
     match guard.as_ref() {
         Some(e) => { s.insert("inicializado".into(), 1); s.insert("n_filas".into(), e.n); }
         None    => { s.insert("inicializado".into(), 0); s.insert("n_filas".into(), 0); }
@@ -644,22 +3087,334 @@ fn engine_stats() -> PyResult<HashMap<String, usize>> {
 // ===========================================================================
 // MÓDULO PyO3
 // ===========================================================================
-#[pymodule]
-fn plaza_rust(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
+// gil_used = false: todo el estado compartido vive detrás de RwLock y los
+// datos de periodo son Arc inmutables, así que el módulo es seguro en 3.13t.
+#[pymodule(gil_used = false)]
+fn plaza_rust(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    errores::registrar(m)?;
+    esquema::registrar(m)?;
+    m.add_function(wrap_pyfunction!(descomprimir,                 m)?)?;
     m.add_function(wrap_pyfunction!(cargar_periodo_parquet,       m)?)?;
     m.add_function(wrap_pyfunction!(periodo_en_cache,             m)?)?;
+    m.add_function(wrap_pyfunction!(alias::registrar_alias,       m)?)?;
+    m.add_function(wrap_pyfunction!(alias::quitar_alias,          m)?)?;
+    m.add_function(wrap_pyfunction!(alias::aliases,               m)?)?;
+    m.add_function(wrap_pyfunction!(virtuales::crear_periodo_virtual, m)?)?;
+    m.add_function(wrap_pyfunction!(virtuales::eliminar_periodo_virtual, m)?)?;
+    m.add_function(wrap_pyfunction!(virtuales::periodos_virtuales, m)?)?;
+    m.add_function(wrap_pyfunction!(columnas_periodo,             m)?)?;
+    m.add_function(wrap_pyfunction!(resumen::resumen_periodo,     m)?)?;
+    m.add_function(wrap_pyfunction!(calidad::definir_reglas,      m)?)?;
+    m.add_function(wrap_pyfunction!(calidad::evaluar_calidad,     m)?)?;
+    m.add_function(wrap_pyfunction!(esquema::esquema_resultado,   m)?)?;
     m.add_function(wrap_pyfunction!(comparar_periodos,            m)?)?;
+    m.add_function(wrap_pyfunction!(filas::comparar_filas,        m)?)?;
+    m.add_function(wrap_pyfunction!(paginado::pagina_filas,       m)?)?;
+    m.add_function(wrap_pyfunction!(busqueda::buscar,             m)?)?;
+    m.add_function(wrap_pyfunction!(cambios::reporte_cambios,     m)?)?;
+    m.add_function(wrap_pyfunction!(plazas::historial_plaza,      m)?)?;
+    m.add_function(wrap_pyfunction!(plazas::altas_bajas,          m)?)?;
+    m.add_function(wrap_pyfunction!(plazas::cohortes,             m)?)?;
+    m.add_function(wrap_pyfunction!(proyeccion::proyectar,        m)?)?;
+    m.add_function(wrap_pyfunction!(indicadores::indice_concentracion, m)?)?;
+    m.add_function(wrap_pyfunction!(indicadores::puntaje_compuesto, m)?)?;
+    m.add_function(wrap_pyfunction!(escenarios::simular_remocion, m)?)?;
+    m.add_function(wrap_pyfunction!(demanda::asignar_demanda,     m)?)?;
+    m.add_function(wrap_pyfunction!(aislamiento::plazas_aisladas, m)?)?;
+    m.add_function(wrap_pyfunction!(poligonos::agregar_por_poligonos, m)?)?;
+    m.add_function(wrap_pyfunction!(corredor::filtrar_por_corredor, m)?)?;
+    m.add_function(wrap_pyfunction!(instantaneas::exportar_snapshot, m)?)?;
+    m.add_function(wrap_pyfunction!(instantaneas::diff_snapshots, m)?)?;
+    m.add_function(wrap_pyfunction!(traza::grabar_traza, m)?)?;
+    m.add_function(wrap_pyfunction!(traza::detener_traza, m)?)?;
+    m.add_function(wrap_pyfunction!(traza::reproducir_traza, m)?)?;
+    m.add_function(wrap_pyfunction!(cuarentena::ultimo_error_carga, m)?)?;
+    m.add_function(wrap_pyfunction!(cuarentena::vaciar_cuarentena, m)?)?;
+    m.add_function(wrap_pyfunction!(validacion::violaciones_validacion, m)?)?;
+    m.add_function(wrap_pyfunction!(metadatos::listar_periodos, m)?)?;
+    m.add_function(wrap_pyfunction!(prioridad::estado_prioridades, m)?)?;
+    m.add_function(wrap_pyfunction!(lentas::consultas_lentas, m)?)?;
+    m.add_function(wrap_pyfunction!(fijados::fijar_periodo, m)?)?;
+    m.add_function(wrap_pyfunction!(fijados::soltar_periodo, m)?)?;
+    m.add_function(wrap_pyfunction!(fijados::periodos_fijados, m)?)?;
+    m.add_function(wrap_pyfunction!(estadisticas::estadisticas_columnas, m)?)?;
+    m.add_function(wrap_pyfunction!(versiones::promover_preliminar, m)?)?;
+    m.add_function(wrap_pyfunction!(versiones::descartar_preliminar, m)?)?;
+    m.add_function(wrap_pyfunction!(versiones::versiones_periodo, m)?)?;
+    m.add_function(wrap_pyfunction!(mapa::muestrear_para_mapa,    m)?)?;
+    m.add_function(wrap_pyfunction!(multifiltro::comparar_periodos_filtros, m)?)?;
+    m.add_function(wrap_pyfunction!(iteradores::iterar_filas,     m)?)?;
+    m.add_class::<iteradores::IteradorFilas>()?;
+    m.add_function(wrap_pyfunction!(vistas::columna,              m)?)?;
+    m.add_class::<vistas::SostenPeriodo>()?;
+    m.add_function(wrap_pyfunction!(refresco::programar_refresco, m)?)?;
+    m.add_function(wrap_pyfunction!(refresco::quitar_refresco,    m)?)?;
+    m.add_function(wrap_pyfunction!(refresco::refrescos_programados, m)?)?;
+    m.add_function(wrap_pyfunction!(invalidacion::version_datos,  m)?)?;
+    m.add_function(wrap_pyfunction!(invalidacion::invalidar_si_version, m)?)?;
     m.add_function(wrap_pyfunction!(resultado_en_cache,           m)?)?;
     m.add_function(wrap_pyfunction!(limpiar_resultados_expirados, m)?)?;
     m.add_function(wrap_pyfunction!(limpiar_periodos_lru,         m)?)?;
+    m.add_function(wrap_pyfunction!(memoria::limpiar_por_memoria, m)?)?;
     m.add_function(wrap_pyfunction!(evict_periodo,                m)?)?;
     m.add_function(wrap_pyfunction!(evict_resultado,              m)?)?;
+    m.add_function(wrap_pyfunction!(derivar_resultado,            m)?)?;
     m.add_function(wrap_pyfunction!(engine_recursos,              m)?)?;
     m.add_function(wrap_pyfunction!(cache_info,                   m)?)?;
+    m.add_function(wrap_pyfunction!(memoria::reporte_memoria,     m)?)?;
+    m.add_function(wrap_pyfunction!(buffers::liberar_buffers,     m)?)?;
+    m.add_function(wrap_pyfunction!(config::cargar_configuracion, m)?)?;
+    m.add_function(wrap_pyfunction!(config::registrar_alias_columna, m)?)?;
+    m.add_function(wrap_pyfunction!(config::quitar_alias_columna, m)?)?;
+    m.add_function(wrap_pyfunction!(config::aliases_columnas, m)?)?;
+    m.add_function(wrap_pyfunction!(catalogo::registrar_catalogo_estados, m)?)?;
+    m.add_function(wrap_pyfunction!(catalogo::catalogo_estados, m)?)?;
+    m.add_function(wrap_pyfunction!(metricas::metricas_prometheus, m)?)?;
+    m.add_function(wrap_pyfunction!(bitacora::log_operaciones,    m)?)?;
+    m.add_function(wrap_pyfunction!(salud::healthcheck,           m)?)?;
+    #[cfg(feature = "http")]
+    {
+        m.add_function(wrap_pyfunction!(http::iniciar_servidor,   m)?)?;
+        m.add_function(wrap_pyfunction!(http::detener_servidor,   m)?)?;
+    }
     m.add_function(wrap_pyfunction!(init_engine,                  m)?)?;
+    m.add_function(wrap_pyfunction!(init_engine_desde_periodo,    m)?)?;
     m.add_function(wrap_pyfunction!(distancias_cercanas,          m)?)?;
+    m.add_function(wrap_pyfunction!(k_mas_cercanas,               m)?)?;
+    m.add_function(wrap_pyfunction!(haversine_batch,              m)?)?;
     m.add_function(wrap_pyfunction!(agregaciones_por_estado,      m)?)?;
     m.add_function(wrap_pyfunction!(filtrar_indices,              m)?)?;
     m.add_function(wrap_pyfunction!(engine_stats,                 m)?)?;
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLITICAS: [config::Negativos; 3] =
+        [config::Negativos::Recortar, config::Negativos::Incluir, config::Negativos::Error];
+
+    /// Referencia fila por fila: (filas, total exacto o None, negativos, no_nulos).
+    fn ingenua<T: columna::Entero>(
+        pol: config::Negativos, xs: &[T], pasa: impl Fn(usize) -> bool,
+    ) -> (i64, Option<i64>, i64, i64) {
+        let (mut filas, mut total, mut negs, mut datos) = (0, Some(0i64), 0, 0);
+        for (i, &x) in xs.iter().enumerate() {
+            if !pasa(i) { continue; }
+            filas += 1;
+            if x == T::NULO { continue; }
+            let v: i64 = x.into();
+            datos += 1;
+            if v < 0 && pol == config::Negativos::Error { negs += 1; }
+            let v = if pol == config::Negativos::Incluir { v } else { v.max(0) };
+            total = total.and_then(|t| t.checked_add(v));
+        }
+        (filas, total, negs, datos)
+    }
+
+    fn verificar(s: Suma, esperado: (i64, Option<i64>, i64, i64)) {
+        let (filas, total, negativos, no_nulos) = esperado;
+        assert_eq!(s.filas, filas);
+        assert_eq!(s.desborde, total.is_none());
+        assert_eq!(s.total, total.unwrap_or(i64::MAX));
+        assert_eq!(s.negativos, negativos);
+        assert_eq!(s.no_nulos, no_nulos);
+    }
+
+    fn probar_suma<T: columna::Entero>(xs: &[T]) {
+        for pol in POLITICAS {
+            let s = con_politica!(pol, P => suma::<P, T>(xs));
+            verificar(s, ingenua(pol, xs, |_| true));
+        }
+    }
+
+    fn probar_filtrada<T: columna::Entero, S: columna::Entero>(xs: &[T], sits: &[S], filtro: i64) {
+        for pol in POLITICAS {
+            let s = con_politica!(pol, P => suma_filtrada::<P, T, S>(xs, sits, filtro));
+            verificar(s, ingenua(pol, xs, |i| sits[i].into() == filtro));
+        }
+    }
+
+    /// Generador congruencial: datos reproducibles sin dependencias.
+    fn pseudoaleatorios(n: usize, semilla: u64) -> Vec<i64> {
+        let mut x = semilla;
+        (0..n).map(|_| {
+            x = x.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (x >> 33) as i64 % 2001 - 1000
+        }).collect()
+    }
+
+    #[test]
+    fn suma_coincide_con_la_referencia_en_las_tres_politicas() {
+        // Largos sin resto, con resto y menores que LANES
+        for n in [0, 1, LANES - 1, LANES, 3 * LANES, 3 * LANES + 5, 1000] {
+            let mut xs = pseudoaleatorios(n, n as u64);
+            for i in (0..n).step_by(7) { xs[i] = i64::MIN; }
+            probar_suma(&xs);
+        }
+    }
+
+    #[test]
+    fn nulo_de_cada_ancho_no_suma_ni_cuenta() {
+        // Nulo en los bloques de LANES y en el resto
+        let base: Vec<i64> = vec![5, -3, 0, 7, -1, 2, 9, -8, 4, 6, -2];
+        fn con_nulos<T: columna::Entero + TryFrom<i64>>(base: &[i64]) -> Vec<T> {
+            base.iter().enumerate()
+                .map(|(i, &v)| if i % 4 == 1 { T::NULO } else { T::try_from(v).ok().unwrap() })
+                .collect()
+        }
+        let (a, b, c) = (con_nulos::<i8>(&base), con_nulos::<i16>(&base), con_nulos::<i32>(&base));
+        probar_suma(&a);
+        probar_suma(&b);
+        probar_suma(&c);
+        for pol in POLITICAS {
+            let s8  = con_politica!(pol, P => suma::<P, i8>(&a));
+            let s16 = con_politica!(pol, P => suma::<P, i16>(&b));
+            let s32 = con_politica!(pol, P => suma::<P, i32>(&c));
+            assert_eq!((s8.total, s8.no_nulos), (s16.total, s16.no_nulos));
+            assert_eq!((s8.total, s8.no_nulos), (s32.total, s32.no_nulos));
+            assert_eq!(s8.no_nulos, 8);
+        }
+        // Todo nulo: aporta 0 incluso con Incluir (i8::MIN no es -128)
+        let nulos = [i8::MIN; LANES + 3];
+        let s = suma::<Incluir, i8>(&nulos);
+        assert_eq!((s.total, s.no_nulos, s.filas), (0, 0, LANES as i64 + 3));
+    }
+
+    #[test]
+    fn suma_filtrada_en_el_resto() {
+        let n = 2 * LANES + 5;
+        let mut xs = pseudoaleatorios(n, 42);
+        xs[n - 2] = i64::MIN;
+        // Solo pasan filas del resto, y con situación nula de por medio
+        let sits: Vec<i8> = (0..n)
+            .map(|i| if i >= 2 * LANES && i % 2 == 0 { 3 } else if i % 3 == 0 { i8::MIN } else { 1 })
+            .collect();
+        probar_filtrada(&xs, &sits, 3);
+        let s = suma_filtrada::<Recortar, i64, i8>(&xs, &sits, 3);
+        assert_eq!(s.filas, 3);
+        // Filas que pasan en bloques y en el resto, situación en otro ancho
+        let sits16: Vec<i16> = (0..n as i16).map(|i| i % 3).collect();
+        for filtro in 0..3 { probar_filtrada(&xs, &sits16, filtro); }
+        // Ninguna pasa
+        probar_filtrada(&xs, &sits16, 7);
+    }
+
+    #[test]
+    fn desborde_recurre_a_la_suma_exacta() {
+        // max × len no cabe pero la suma sí: la exacta corrige el wrapping
+        let grandes = [i64::MAX / 2, i64::MAX / 2, 1];
+        assert!(!cabe_sin_desborde(i64::MAX / 2, grandes.len()));
+        probar_suma(&grandes);
+        let s = suma::<Recortar, i64>(&grandes);
+        assert_eq!((s.total, s.desborde), (i64::MAX, false));
+
+        // Con Incluir se cancelan: wrapping y exacta dan 0
+        let opuestos = [i64::MAX - 10, -(i64::MAX - 10)];
+        let s = suma::<Incluir, i64>(&opuestos);
+        assert_eq!((s.total, s.desborde), (0, false));
+
+        // Desborda de verdad: total saturado y marcado
+        let mut muchos = vec![i64::MAX / 4; 2 * LANES + 3];
+        muchos[LANES + 1] = i64::MIN;
+        probar_suma(&muchos);
+        let s = suma::<Reportar, i64>(&muchos);
+        assert_eq!((s.total, s.desborde), (i64::MAX, true));
+
+        // Igual con filtro, sólo si las filas que pasan desbordan
+        let sits: Vec<i32> = (0..muchos.len() as i32).map(|i| (i < 4) as i32).collect();
+        probar_filtrada(&muchos, &sits, 1);
+        probar_filtrada(&muchos, &sits, 0);
+        assert!(!suma_filtrada::<Incluir, i64, i32>(&muchos, &sits, 1).desborde);
+        assert!(suma_filtrada::<Incluir, i64, i32>(&muchos, &sits, 0).desborde);
+    }
+
+    #[test]
+    fn hit_no_necesita_los_periodos_cargados() {
+        // estado 9 con otra situación, uno sin estado y el 15
+        pruebas::cargar(230_001, &[(Some(9), 1, 10), (Some(9), 2, 5), (None, 1, 7), (Some(15), 1, 3)]);
+        let metadatos = BTreeMap::from([("fuente".to_string(), "prueba".to_string())]);
+        let opciones = OpcionesCarga { metadatos: metadatos.clone(), forzar: true, ..Default::default() };
+        cargar_periodo_con(pruebas::parquet(&[(Some(9), 1, 20)]), 230_002, opciones).unwrap();
+        let calculado = comparar_con_meta(230_001, 230_002, 1, None).unwrap();
+        let ((a1, m1, _), _) = &calculado;
+        assert_eq!(a1[&9][1], 10);
+        assert_eq!(*m1, MetaFilas { filas: 4, sin_estado: 1, excluidas_situacion: 1, fuera_de_estados: 0 });
+
+        quitar_periodo(230_001).unwrap();
+        quitar_periodo(230_002).unwrap();
+        // Del cache, con el MetaFilas de cuando se calculó
+        assert_eq!(comparar_con_meta(230_001, 230_002, 1, None).unwrap(), calculado);
+        let ((a1, m1, d1), (a2, m2, d2)) = comparar_con_meta(230_001, 230_002, 1, Some(&[9])).unwrap();
+        assert_eq!((d1, d2), (BTreeMap::new(), metadatos));
+        assert_eq!((a1.len(), a2.len()), (1, 1));
+        assert_eq!(m1, MetaFilas { filas: 4, sin_estado: 1, excluidas_situacion: 1, fuera_de_estados: 1 });
+        assert_eq!(m2, MetaFilas { filas: 1, ..Default::default() });
+    }
+
+    #[test]
+    fn recargar_un_periodo_invalida_sus_resultados() {
+        pruebas::cargar(230_101, &[(Some(9), 1, 10)]);
+        pruebas::cargar(230_102, &[(Some(9), 1, 20)]);
+        let (a1, a2, _) = comparar(230_101, 230_102, -1).unwrap();
+        assert_eq!((a1[&9][1], a2[&9][1]), (10, 20));
+
+        pruebas::cargar(230_101, &[(Some(9), 1, 11), (Some(15), 1, 4)]);
+        assert!(!RESULT_CACHE.leer("test").unwrap().as_ref().unwrap().contains_key(&(230_101, 230_102, -1)));
+        let (a1, a2, r) = comparar(230_101, 230_102, -1).unwrap();
+        assert_eq!((a1[&9][1], a1[&15][1], a2[&9][1]), (11, 4, 20));
+        assert_eq!(r[0].filas, 2);
+    }
+
+    #[test]
+    fn carga_identica_no_reparsea_salvo_que_cambien_opciones_o_config() {
+        let raw = pruebas::parquet(&[(Some(9), 1, 10), (Some(15), 2, 3)]);
+        let cargar = |opciones: OpcionesCarga| {
+            let c = cargar_periodo_con(raw.clone(), 230_201, opciones).unwrap();
+            (c.identica, periodo(230_201).unwrap())
+        };
+        let (identica, primera) = cargar(OpcionesCarga::default());
+        assert!(!identica);
+        // Mismos bytes y opciones: el mismo EngineData, sin parsear
+        let (identica, segunda) = cargar(OpcionesCarga::default());
+        assert!(identica);
+        assert!(Arc::ptr_eq(&primera, &segunda));
+
+        let (identica, tolerante) = cargar(OpcionesCarga { tolerancia: Some(5), ..Default::default() });
+        assert!(!identica && !Arc::ptr_eq(&segunda, &tolerante));
+        assert!(cargar(OpcionesCarga { tolerancia: Some(5), ..Default::default() }).0);
+
+        let aliases = HashMap::from([("cn_total".to_string(), vec!["CN_Otra".to_string()])]);
+        let (identica, con_aliases) = cargar(OpcionesCarga {
+            tolerancia: Some(5), aliases: Some(aliases), ..Default::default()
+        });
+        assert!(!identica && !Arc::ptr_eq(&tolerante, &con_aliases));
+
+        // Otra generación de la configuración (aunque no cambie nada)
+        let (_, antes) = cargar(OpcionesCarga::default());
+        config::aplicar_toml("").unwrap();
+        let (identica, despues) = cargar(OpcionesCarga::default());
+        assert!(!identica && !Arc::ptr_eq(&antes, &despues));
+    }
+
+    #[test]
+    fn cercanas_con_radio_nan_no_devuelve_nada() {
+        // pruebas::parquet pone todas las filas en (19.4, -99.1)
+        pruebas::cargar(230_301, &[(Some(9), 1, 1), (Some(9), 1, 2)]);
+        let eng = periodo(230_301).unwrap();
+        let filtro = FiltroCercania { situacion: -1, estado_id: -1, sector: None };
+        assert_eq!(cercanas(&eng, (19.4, -99.1), 0.0, 10, filtro).len(), 2);
+        assert!(cercanas(&eng, (19.4, -99.1), f64::NAN, 10, filtro).is_empty());
+    }
+
+    #[test]
+    fn cabe_sin_desborde_en_los_bordes() {
+        let por_bloque = i64::MAX / BLOQUE as i64;
+        assert!(cabe_sin_desborde(por_bloque, 1));
+        assert!(cabe_sin_desborde(por_bloque, BLOQUE));
+        assert!(!cabe_sin_desborde(por_bloque + 1, BLOQUE));
+        assert!(cabe_sin_desborde(i64::MAX / (BLOQUE as i64 + 1), BLOQUE + 1));
+        assert!(!cabe_sin_desborde(por_bloque, BLOQUE + 1));
+        assert!(cabe_sin_desborde(0, usize::MAX >> 1));
+    }
+}
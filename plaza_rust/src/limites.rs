@@ -0,0 +1,67 @@
+// ==============================================================================
+// plaza_rust/src/limites.rs
+//
+// Límites de ritmo para las cargas de periodos ([limites] en config.rs). Un
+// cliente que martilla subidas no debe dejar sin CPU ni memoria a las
+// comparaciones:
+//
+//   cargas_por_segundo   cubeta de fichas: ráfaga de hasta N cargas y luego
+//                        N por segundo
+//   cargas_concurrentes  cargas en curso a la vez
+//
+// Excederlos falla de inmediato con DemasiadasSolicitudes (no se encola).
+// 0 = sin límite, el default. Un reintento con el mismo contenido también
+// pide permiso: la huella recorre el payload entero antes de saber si se
+// puede saltear el parseo.
+// ==============================================================================
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::config::Limites;
+use crate::errores::Error;
+
+// (fichas disponibles, última recarga); None hasta la primera carga
+static CUBETA: Mutex<Option<(f64, Instant)>> = Mutex::new(None);
+static EN_CURSO: AtomicUsize = AtomicUsize::new(0);
+
+/// Cupo de una carga en curso; al soltarse libera su lugar.
+pub(crate) struct Permiso(());
+
+impl Drop for Permiso {
+    fn drop(&mut self) {
+        EN_CURSO.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn tomar_ficha(por_segundo: u32) -> Result<(), Error> {
+    if por_segundo == 0 { return Ok(()); }
+    let tope = por_segundo as f64;
+    let mut cubeta = CUBETA.lock().map_err(|_| "Mutex: CUBETA envenenado".to_string())?;
+    let ahora = Instant::now();
+    let (fichas, antes) = cubeta.unwrap_or((tope, ahora));
+    let fichas = (fichas + ahora.duration_since(antes).as_secs_f64() * tope).min(tope);
+    if fichas < 1.0 {
+        *cubeta = Some((fichas, ahora));
+        return Err(Error::DemasiadasSolicitudes(format!(
+            "cargar_periodo: límite de {por_segundo} cargas por segundo ([limites] cargas_por_segundo)"
+        )));
+    }
+    *cubeta = Some((fichas - 1.0, ahora));
+    Ok(())
+}
+
+/// Permiso para una carga, o DemasiadasSolicitudes si excede algún límite.
+pub(crate) fn permiso_carga(l: &Limites) -> Result<Permiso, Error> {
+    let en_curso = EN_CURSO.fetch_add(1, Ordering::AcqRel);
+    let permiso = Permiso(());
+    if l.cargas_concurrentes > 0 && en_curso >= l.cargas_concurrentes {
+        return Err(Error::DemasiadasSolicitudes(format!(
+            "cargar_periodo: ya hay {en_curso} cargas en curso (máximo {}, [limites] cargas_concurrentes)",
+            l.cargas_concurrentes,
+        )));
+    }
+    tomar_ficha(l.cargas_por_segundo)?;
+    Ok(permiso)
+}
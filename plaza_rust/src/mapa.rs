@@ -0,0 +1,123 @@
+// ==============================================================================
+// plaza_rust/src/mapa.rs
+//
+// Muestra de puntos para pintar el mapa sin mandar millones de filas al
+// frontend. Muestreo estratificado por rejilla: el bbox se divide en una
+// rejilla de ~max_puntos celdas y cada celda recibe una cuota proporcional
+// a cuántos puntos tiene (resto mayor), así la densidad visual se conserva.
+// Dentro de la celda se toman filas equiespaciadas en el orden del parquet:
+// la misma petición devuelve siempre la misma muestra.
+// ==============================================================================
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rustc_hash::FxHashMap;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{clave, periodo, EngineData};
+
+/// (lat_min, lng_min, lat_max, lng_max)
+type Bbox = (f64, f64, f64, f64);
+
+/// Filas originales (ascendentes) dentro del bbox con la situación pedida.
+fn candidatos(eng: &EngineData, bbox: Bbox, filtro_sit: i64) -> Vec<(usize, f64, f64)> {
+    let (la0, lo0, la1, lo1) = bbox;
+    let mut v: Vec<_> = (0..eng.n)
+        .filter(|&i| filtro_sit < 0 || eng.tiene_situacion(i, filtro_sit))
+        .filter_map(|i| {
+            let (la, lo) = (eng.lats.get(i), eng.lngs.get(i));
+            (la >= la0 && la <= la1 && lo >= lo0 && lo <= lo1).then(|| (eng.fila_original(i), la, lo))
+        })
+        .collect();
+    v.sort_unstable_by_key(|&(o, _, _)| o);
+    v
+}
+
+/// Cuota por celda proporcional a su tamaño; el sobrante de redondear va a
+/// las de mayor resto (empate: la celda con más puntos, luego la primera).
+fn cuotas(tamaños: &[usize], total: usize, max_puntos: usize) -> Vec<usize> {
+    let exactas: Vec<f64> = tamaños.iter().map(|&t| t as f64 * max_puntos as f64 / total as f64).collect();
+    let mut q: Vec<usize> = exactas.iter().map(|&e| e.floor() as usize).collect();
+    let mut faltan = max_puntos - q.iter().sum::<usize>();
+    let mut orden: Vec<usize> = (0..tamaños.len()).collect();
+    orden.sort_by(|&a, &b| {
+        let (ra, rb) = (exactas[a] - q[a] as f64, exactas[b] - q[b] as f64);
+        rb.total_cmp(&ra).then(tamaños[b].cmp(&tamaños[a])).then(a.cmp(&b))
+    });
+    for c in orden {
+        if faltan == 0 { break; }
+        if q[c] < tamaños[c] { q[c] += 1; faltan -= 1; }
+    }
+    q
+}
+
+fn muestrear(pts: Vec<(usize, f64, f64)>, bbox: Bbox, max_puntos: usize) -> Vec<(usize, f64, f64)> {
+    if pts.len() <= max_puntos { return pts; }
+    let (la0, lo0, la1, lo1) = bbox;
+    let lado = (max_puntos as f64).sqrt().ceil().max(1.0) as usize;
+    let celda_de = |x: f64, ini: f64, fin: f64| {
+        if fin <= ini { 0 } else { (((x - ini) / (fin - ini)) * lado as f64).floor().clamp(0.0, (lado - 1) as f64) as usize }
+    };
+    // celda → filas en orden de aparición; las celdas se recorren en orden fijo
+    let mut celdas: FxHashMap<usize, Vec<(usize, f64, f64)>> = FxHashMap::default();
+    for p in &pts {
+        celdas.entry(celda_de(p.1, la0, la1) * lado + celda_de(p.2, lo0, lo1)).or_default().push(*p);
+    }
+    let mut celdas: Vec<_> = celdas.into_iter().collect();
+    celdas.sort_unstable_by_key(|&(c, _)| c);
+
+    let tamaños: Vec<usize> = celdas.iter().map(|(_, v)| v.len()).collect();
+    let q = cuotas(&tamaños, pts.len(), max_puntos);
+    let mut out: Vec<(usize, f64, f64)> = celdas.iter().zip(q)
+        .flat_map(|((_, v), q)| (0..q).map(move |k| v[(2 * k + 1) * v.len() / (2 * q)]))
+        .collect();
+    out.sort_unstable_by_key(|&(o, _, _)| o);
+    out
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"indices", "lat", "lng", "total"}: hasta `max_puntos` filas (en el orden
+/// del parquet) dentro de `bbox` = (lat_min, lng_min, lat_max, lng_max)
+/// (default: todo) con la situación `filtro` (< 0 = todas); "total" es
+/// cuántas había antes de muestrear.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, bbox = None, max_puntos = 5000, filtro = -1))]
+pub(crate) fn muestrear_para_mapa<'py>(
+    py:          Python<'py>,
+    periodo_key: ArgClave,
+    bbox:        Option<Bbox>,
+    max_puntos:  usize,
+    filtro:      i64,
+) -> PyResult<Bound<'py, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    if let Some((la0, lo0, la1, lo1)) = bbox {
+        if [la0, lo0, la1, lo1].iter().any(|x| x.is_nan()) || la0 > la1 || lo0 > lo1 {
+            return Err(PyValueError::new_err(format!("bbox inválido: {:?}", (la0, lo0, la1, lo1))));
+        }
+    }
+    let (total, muestra) = py.allow_threads(|| -> Result<_, Error> {
+        let eng = periodo(periodo_key)?;
+        eng.tocar();
+        let todo = (f64::NEG_INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::INFINITY);
+        let pts = candidatos(&eng, bbox.unwrap_or(todo), filtro);
+        // La rejilla se arma sobre la extensión real de los puntos, no la del
+        // bbox pedido (que puede ser infinita o mucho más grande)
+        let ext = pts.iter().fold(
+            (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            |(a, b, c, d), p| (a.min(p.1), b.min(p.2), c.max(p.1), d.max(p.2)),
+        );
+        Ok((pts.len(), muestrear(pts, ext, max_puntos)))
+    })?;
+
+    let out = PyDict::new(py);
+    out.set_item("indices", muestra.iter().map(|p| p.0).collect::<Vec<_>>())?;
+    out.set_item("lat", muestra.iter().map(|p| p.1).collect::<Vec<_>>())?;
+    out.set_item("lng", muestra.iter().map(|p| p.2).collect::<Vec<_>>())?;
+    out.set_item("total", total)?;
+    Ok(out)
+}
@@ -0,0 +1,146 @@
+// ==============================================================================
+// plaza_rust/src/memoria.rs
+//
+// reporte_memoria(): desglose en bytes de lo que retiene el módulo, para ver
+// qué ocupa la RAM de un worker sin adjuntar un profiler.
+//
+//   {
+//     "periodos":   {periodo_key: {"lats": b, ..., "total": b}},
+//     "engine":     {"lats": b, ..., "total": b}          (vacío sin init_engine;
+//                   {"compartido_con": key, "total": 0} si apunta a un periodo)
+//     "resultados": {(key1, key2, filtro): b},
+//     "totales":    {"periodos": b, "engine": b, "resultados": b,
+//                    "buffers": b, "total": b},
+//   }
+//
+// Los vectores cuentan su capacidad reservada; los BTreeMap de resultados son
+// una estimación (buckets × tamaño de entrada). "buffers" son las columnas
+// libres que retiene el pool de buffers.rs.
+//
+// limpiar_por_memoria(max_mb) desaloja periodos, el menos recién usado
+// primero, hasta que los periodos ocupen <= max_mb según esta misma medida.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::errores::Error;
+use crate::metricas::{self, Cache, Motivo};
+use crate::{bitacora, desalojo, EngineData, PeriodoKey, ResultKey, ENGINE, ENGINE_PERIODOS, RESULT_CACHE};
+
+type Desglose = BTreeMap<&'static str, usize>;
+
+struct Reporte {
+    periodos:   BTreeMap<PeriodoKey, Desglose>,
+    engine:     Desglose,
+    resultados: BTreeMap<ResultKey, usize>,
+}
+
+fn desglose(eng: &EngineData) -> Desglose {
+    let mut d: Desglose = eng.bytes_por_estructura().into_iter().collect();
+    d.insert("total", d.values().sum());
+    d
+}
+
+fn medir() -> Result<Reporte, Error> {
+    // Se clonan los Arc bajo el lock y se mide sin él
+    let periodos: Vec<(PeriodoKey, Arc<EngineData>)> = ENGINE_PERIODOS.leer("reporte_memoria")
+        ?
+        .as_ref()
+        .map(|m| m.iter().map(|(&k, v)| (k, Arc::clone(v))).collect())
+        .unwrap_or_default();
+    let engine = ENGINE.leer("reporte_memoria")?.clone();
+    let resultados = RESULT_CACHE.leer("reporte_memoria")
+        ?
+        .as_ref()
+        .map(|m| m.iter().map(|(&k, v)| (k, v.bytes())).collect())
+        .unwrap_or_default();
+
+    Ok(Reporte {
+        periodos: periodos.iter().map(|(k, e)| (*k, desglose(e))).collect(),
+        engine:   engine.map(|e| match periodos.iter().find(|(_, p)| Arc::ptr_eq(p, &e)) {
+            // init_engine_desde_periodo: ya se contó con su periodo
+            Some(&(k, _)) => BTreeMap::from([("compartido_con", k as usize), ("total", 0)]),
+            None          => desglose(&e),
+        }).unwrap_or_default(),
+        resultados,
+    })
+}
+
+/// (clave, bytes, edad_s) de los periodos a desalojar para bajar a
+/// `max_bytes`, el menos recién usado primero; los quita salvo con `dry_run`.
+fn desalojar_hasta(max_bytes: usize, dry_run: bool) -> Result<Vec<(PeriodoKey, usize, u64)>, Error> {
+    let fijados = crate::fijados::actuales()?;
+    let mut guard = ENGINE_PERIODOS.escribir("limpiar_por_memoria")?;
+    let Some(map) = guard.as_mut() else { return Ok(Vec::new()) };
+    let ahora = crate::now_secs();
+    let mut por_uso: Vec<(u64, PeriodoKey, usize, u64)> = map.iter()
+        .map(|(&k, v)| (v.ultimo_acceso.load(Ordering::Relaxed), k, v.bytes(), ahora.saturating_sub(v.cargado_at)))
+        .collect();
+    por_uso.sort_unstable();
+    let mut total: usize = por_uso.iter().map(|p| p.2).sum();
+    // Los fijados cuentan en el total pero no salen
+    let victimas: Vec<(PeriodoKey, usize, u64)> = por_uso.into_iter()
+        .filter(|(_, k, _, _)| !fijados.contains(k))
+        .take_while(|&(_, _, b, _)| {
+            let sale = total > max_bytes;
+            if sale { total -= b; }
+            sale
+        })
+        .map(|(_, k, b, edad)| (k, b, edad))
+        .collect();
+    if !dry_run {
+        for (k, _, _) in &victimas {
+            map.remove(k);
+        }
+        metricas::contar_eviccion(Cache::Periodos, Motivo::Memoria, victimas.len() as u64);
+        if !victimas.is_empty() { crate::buffers::tras_eviccion(); }
+    }
+    Ok(victimas)
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Desaloja periodos (LRU) hasta que ocupen <= max_mb; devuelve desalojo()
+/// (con `dry_run`, sin quitar nada).
+#[pyfunction]
+#[pyo3(signature = (max_mb, dry_run = false, request_id = None))]
+pub(crate) fn limpiar_por_memoria(py: Python<'_>, max_mb: u64, dry_run: bool, request_id: Option<&str>) -> PyResult<PyObject> {
+    let params = format!("max_mb={max_mb} dry_run={dry_run}");
+    let max_bytes = usize::try_from(max_mb.saturating_mul(1 << 20)).unwrap_or(usize::MAX);
+    let victimas = py.allow_threads(|| {
+        bitacora::auditar("limpiar_por_memoria", params, request_id, || desalojar_hasta(max_bytes, dry_run))
+    })?;
+    let bytes = victimas.iter().map(|v| v.1).sum();
+    desalojo(py, victimas, bytes, dry_run)
+}
+
+#[pyfunction]
+pub(crate) fn reporte_memoria(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let r = py.allow_threads(medir)?;
+
+    let t_periodos: usize = r.periodos.values().map(|d| d["total"]).sum();
+    let t_engine = r.engine.get("total").copied().unwrap_or(0);
+    let t_resultados: usize = r.resultados.values().sum();
+    let t_buffers = crate::buffers::bytes_retenidos();
+    let totales: Desglose = BTreeMap::from([
+        ("periodos",   t_periodos),
+        ("engine",     t_engine),
+        ("resultados", t_resultados),
+        ("buffers",    t_buffers),
+        ("total",      t_periodos + t_engine + t_resultados + t_buffers),
+    ]);
+
+    let out = PyDict::new(py);
+    out.set_item("periodos",   r.periodos)?;
+    out.set_item("engine",     r.engine)?;
+    out.set_item("resultados", r.resultados)?;
+    out.set_item("totales",    totales)?;
+    Ok(out)
+}
@@ -0,0 +1,91 @@
+// ==============================================================================
+// plaza_rust/src/metadatos.rs
+//
+// Metadatos de periodo: etiquetas {str: str} que el llamador adjunta en la
+// carga (cargar_periodo_parquet(..., metadatos={"fuente": "...",
+// "estado": "preliminar"})). El motor no las interpreta: se guardan con el
+// EngineData, las lista listar_periodos() y las repite comparar_periodos()
+// como "periodo1_metadatos" / "periodo2_metadatos" (también GET /comparar),
+// así la UI puede marcar datos preliminares sin otra consulta.
+//
+// Recargar un periodo reemplaza sus metadatos. Un periodo virtual reúne los
+// de sus componentes: una clave con el mismo valor en todos queda igual; con
+// valores distintos, los distintos ordenados y unidos por ",". Un resultado
+// en cache guarda los metadatos de cuando se calculó (ResumenLado), así que
+// un hit los repite aunque el periodo ya no esté cargado.
+// ==============================================================================
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::{fijados, versiones, EngineData, ENGINE_PERIODOS};
+
+const MAX_CLAVES: usize = 64;
+const MAX_CLAVE: usize = 64;
+const MAX_VALOR: usize = 1024;
+
+pub(crate) fn validar(m: &BTreeMap<String, String>) -> Result<(), String> {
+    if m.len() > MAX_CLAVES {
+        return Err(format!("metadatos: {} claves (máximo {MAX_CLAVES})", m.len()));
+    }
+    for (k, v) in m {
+        if k.trim().is_empty() {
+            return Err("metadatos: clave vacía".into());
+        }
+        if k.len() > MAX_CLAVE {
+            return Err(format!("metadatos: clave '{k}' de más de {MAX_CLAVE} bytes"));
+        }
+        if v.len() > MAX_VALOR {
+            return Err(format!("metadatos['{k}']: valor de más de {MAX_VALOR} bytes"));
+        }
+    }
+    Ok(())
+}
+
+/// Metadatos de un lado de la comparación (virtuales::lados: el periodo o
+/// los componentes del virtual).
+pub(crate) fn de_lados(lados: &[Arc<EngineData>]) -> BTreeMap<String, String> {
+    if let [eng] = lados {
+        return eng.metadatos.clone();
+    }
+    let mut valores: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for eng in lados {
+        for (k, v) in &eng.metadatos {
+            valores.entry(k).or_default().insert(v);
+        }
+    }
+    valores.into_iter()
+        .map(|(k, vs)| (k.to_string(), vs.into_iter().collect::<Vec<_>>().join(",")))
+        .collect()
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// Periodos físicos cargados, por clave: [{"periodo_key", "filas",
+/// "cargado_at", "bytes", "version", "fijado", "metadatos"}]; "version" es
+/// la que se sirve (versiones.rs).
+#[pyfunction]
+pub(crate) fn listar_periodos(py: Python<'_>) -> PyResult<Vec<Bound<'_, PyDict>>> {
+    let mut periodos: Vec<_> = ENGINE_PERIODOS.leer("listar_periodos")?
+        .as_ref()
+        .map(|m| m.iter().map(|(&k, e)| (k, e.clone())).collect())
+        .unwrap_or_default();
+    periodos.sort_unstable_by_key(|&(k, _)| k);
+    let fijados = fijados::actuales()?;
+    periodos.iter().map(|(k, e)| {
+        let d = PyDict::new(py);
+        d.set_item("periodo_key", k)?;
+        d.set_item("filas", e.n)?;
+        d.set_item("cargado_at", e.cargado_at)?;
+        d.set_item("bytes", e.bytes())?;
+        d.set_item("version", versiones::vigente(*k)?.map(versiones::Version::nombre))?;
+        d.set_item("fijado", fijados.contains(k))?;
+        d.set_item("metadatos", &e.metadatos)?;
+        Ok(d)
+    }).collect()
+}
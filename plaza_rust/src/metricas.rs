@@ -0,0 +1,151 @@
+// ==============================================================================
+// plaza_rust/src/metricas.rs
+//
+// Contadores de proceso (atómicos, sin locks) y su exposición en formato de
+// texto Prometheus. Los gauges de tamaño (periodos, filas, RAM) se leen de
+// recursos() al momento de exportar.
+// ==============================================================================
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use pyo3::prelude::*;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Cache {
+    Periodos   = 0,
+    Resultados = 1,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Motivo {
+    Capacidad = 0,
+    Ttl       = 1,
+    Lru       = 2,
+    Manual    = 3,
+    Memoria   = 4,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum Operacion {
+    Carga       = 0,
+    Comparacion = 1,
+}
+
+const CACHES:      [&str; 2] = ["periodos", "resultados"];
+const MOTIVOS:     [&str; 5] = ["capacidad", "ttl", "lru", "manual", "memoria"];
+const OPERACIONES: [&str; 2] = ["carga", "comparacion"];
+
+// Constantes solo para inicializar los arrays estáticos (AtomicU64 no es Copy)
+#[allow(clippy::declare_interior_mutable_const)]
+const CERO: AtomicU64 = AtomicU64::new(0);
+#[allow(clippy::declare_interior_mutable_const)]
+const FILA_MOTIVOS: [AtomicU64; 5] = [CERO; 5];
+
+static EVICCIONES:       [[AtomicU64; 5]; 2] = [FILA_MOTIVOS; 2];
+static ERRORES:          [AtomicU64; 2] = [CERO; 2];
+static OPERACIONES_N:    [AtomicU64; 2] = [CERO; 2];
+static OPERACIONES_US:   [AtomicU64; 2] = [CERO; 2];
+static RESULTADOS_HIT:   AtomicU64 = AtomicU64::new(0);
+static RESULTADOS_MISS:  AtomicU64 = AtomicU64::new(0);
+
+// Timestamps (s) de los últimos errores, para tasas por ventana
+const MAX_ERRORES_RECIENTES: usize = 1024;
+static ERRORES_RECIENTES: Mutex<VecDeque<u64>> = Mutex::new(VecDeque::new());
+
+pub(crate) fn contar_eviccion(cache: Cache, motivo: Motivo, n: u64) {
+    if n > 0 {
+        EVICCIONES[cache as usize][motivo as usize].fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn contar_error(op: Operacion) {
+    ERRORES[op as usize].fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut q) = ERRORES_RECIENTES.lock() {
+        if q.len() == MAX_ERRORES_RECIENTES { q.pop_front(); }
+        q.push_back(crate::now_secs());
+    }
+}
+
+/// Errores registrados en los últimos `ventana_s` segundos (tope 1024).
+pub(crate) fn errores_recientes(ventana_s: u64) -> u64 {
+    let desde = crate::now_secs().saturating_sub(ventana_s);
+    ERRORES_RECIENTES.lock()
+        .map_or(0, |q| q.iter().rev().take_while(|&&t| t >= desde).count() as u64)
+}
+
+pub(crate) fn contar_operacion(op: Operacion, duracion: Duration) {
+    OPERACIONES_N[op as usize].fetch_add(1, Ordering::Relaxed);
+    OPERACIONES_US[op as usize].fetch_add(duracion.as_micros() as u64, Ordering::Relaxed);
+}
+
+pub(crate) fn contar_hit(hit: bool) {
+    if hit { &RESULTADOS_HIT } else { &RESULTADOS_MISS }.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Texto listo para servir en /metrics (text/plain; version=0.0.4).
+pub(crate) fn texto_prometheus() -> String {
+    let mut out = String::with_capacity(2048);
+    let mut metrica = |nombre: &str, tipo: &str, ayuda: &str, muestras: &[(String, u64)]| {
+        let _ = writeln!(out, "# HELP {nombre} {ayuda}");
+        let _ = writeln!(out, "# TYPE {nombre} {tipo}");
+        for (labels, v) in muestras {
+            let _ = writeln!(out, "{nombre}{labels} {v}");
+        }
+    };
+    let sin_labels = |v: u64| vec![(String::new(), v)];
+
+    let hits = RESULTADOS_HIT.load(Ordering::Relaxed);
+    let miss = RESULTADOS_MISS.load(Ordering::Relaxed);
+    metrica("plaza_resultados_consultas_total", "counter",
+        "Consultas a RESULT_CACHE por resultado",
+        &[("{resultado=\"hit\"}".into(), hits), ("{resultado=\"miss\"}".into(), miss)]);
+
+    let mut ev = Vec::new();
+    for (c, cache) in CACHES.iter().enumerate() {
+        for (m, motivo) in MOTIVOS.iter().enumerate() {
+            ev.push((format!("{{cache=\"{cache}\",motivo=\"{motivo}\"}}"),
+                     EVICCIONES[c][m].load(Ordering::Relaxed)));
+        }
+    }
+    metrica("plaza_evicciones_total", "counter", "Entradas desalojadas de los caches", &ev);
+
+    let por_op = |arr: &[AtomicU64; 2]| -> Vec<(String, u64)> {
+        OPERACIONES.iter().zip(arr)
+            .map(|(op, a)| (format!("{{operacion=\"{op}\"}}"), a.load(Ordering::Relaxed)))
+            .collect()
+    };
+    metrica("plaza_errores_total", "counter", "Operaciones fallidas", &por_op(&ERRORES));
+    metrica("plaza_operaciones_total", "counter",
+        "Cargas de periodo y comparaciones calculadas (miss)", &por_op(&OPERACIONES_N));
+    metrica("plaza_operaciones_microsegundos_total", "counter",
+        "Tiempo acumulado en cargas y comparaciones calculadas", &por_op(&OPERACIONES_US));
+
+    let r = crate::recursos();
+    let g = |k: &str| r.get(k).copied().unwrap_or(0);
+    metrica("plaza_periodos_cargados", "gauge", "Periodos en ENGINE_PERIODOS",
+        &sin_labels(g("periodos_cargados")));
+    metrica("plaza_filas_totales", "gauge", "Filas sumando todos los periodos",
+        &sin_labels(g("filas_totales")));
+    metrica("plaza_ram_datos_bytes", "gauge", "RAM estimada de los datos de periodo",
+        &sin_labels(g("ram_datos_kb") * 1024));
+    metrica("plaza_resultados_cacheados", "gauge", "Entradas en RESULT_CACHE",
+        &sin_labels(g("resultados_cacheados")));
+    metrica("plaza_max_periodos", "gauge", "Límite configurado de periodos",
+        &sin_labels(g("max_periodos")));
+    metrica("plaza_max_resultados", "gauge", "Límite configurado de resultados",
+        &sin_labels(g("max_resultados")));
+    out
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+#[pyfunction]
+pub(crate) fn metricas_prometheus() -> String {
+    texto_prometheus()
+}
@@ -0,0 +1,266 @@
+// ==============================================================================
+// plaza_rust/src/multifiltro.rs
+//
+// Varios filtros de situación en un solo recorrido: el tablero pide siempre
+// "plazas activas" junto a "todas las plazas", y en frío eso eran dos
+// agregaciones completas de cada lado.
+//
+//   comparar_periodos_filtros(202401, 202402, [-1, 1])
+//     → {-1: {"periodo1", "periodo2", ...}, 1: {...}}
+//
+// Cada bloque de BLOQUE filas de un estado se lee de memoria una vez y se
+// suma para todos los filtros mientras sigue en cache, con un Acumulador por
+// filtro. Cada filtro queda en RESULT_CACHE con su propia clave, igual que
+// si se hubiera pedido con comparar_periodos(); los que ya estaban en cache
+// no se recalculan.
+// ==============================================================================
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::alias::ArgClave;
+use crate::columna::despachar;
+use crate::errores::Error;
+use crate::metricas::Operacion;
+use crate::{
+    agregado_a_dict_formato, bitacora, catalogo, clave, config, fin_corrida, huella_resultado,
+    insertar_resultado, metricas, metricas_formato, now_secs, parcial_rango, prioridad, resumenes, revalidacion,
+    unir_agregados, validacion, virtuales, Acumulador, AgrMap, EngineData, Lados, Local, Parcial,
+    PeriodoKey, ResultadoComp, BLOQUE, RESULT_CACHE,
+};
+
+fn acumuladores(n: usize) -> Vec<Acumulador> {
+    (0..n).map(|_| Acumulador::new()).collect()
+}
+
+/// Periodo agrupado: por cada bloque, una parcial por filtro. Un filtro sin
+/// filas en el bitmap no aporta nada.
+fn por_grupos(eng: &EngineData, filtros: &[i64], pols: &[config::Negativos; 6]) -> Vec<Acumulador> {
+    let bitmaps: Vec<Option<Option<&[u64]>>> = filtros.iter()
+        .map(|&f| if f < 0 { None } else { eng.bitmap(f) })
+        .collect();
+    let tareas: Vec<(i64, usize, usize)> = eng.grupos.iter()
+        .filter(|g| g.estado != i64::MIN)
+        .flat_map(|g| (g.ini..g.fin).step_by(BLOQUE).map(move |s| (g.estado, s, (s + BLOQUE).min(g.fin))))
+        .collect();
+
+    let bloque = |(eid, ini, fin): (i64, usize, usize)| {
+        prioridad::ceder();
+        let parciales: Vec<Option<Parcial>> = filtros.iter().zip(&bitmaps)
+            .map(|(&f, &bitmap)| match bitmap {
+                Some(None) => None,
+                bits       => Some(parcial_rango(eng, pols, ini, fin, f, bits.flatten())),
+            })
+            .collect();
+        (eid, parciales)
+    };
+    let parciales: Vec<(i64, Vec<Option<Parcial>>)> = if config::secuencial(eng.n) {
+        tareas.into_iter().map(bloque).collect()
+    } else {
+        tareas.into_par_iter().map(bloque).collect()
+    };
+
+    let mut accs = acumuladores(filtros.len());
+    for (eid, ps) in &parciales {
+        for (acc, p) in accs.iter_mut().zip(ps) {
+            if let Some(p) = p { acc.agregar_parcial(*eid, p); }
+        }
+    }
+    accs
+}
+
+/// Periodo sin agrupar: cada corrida de estado_id se suma para todos los
+/// filtros antes de pasar a la siguiente.
+fn por_corridas(eng: &EngineData, filtros: &[i64], pols: &[config::Negativos; 6]) -> Vec<Acumulador> {
+    let paso = |mut accs: Vec<Acumulador>, b: usize| {
+        prioridad::ceder();
+        let ini = b * BLOQUE;
+        let fin_bloque = (ini + BLOQUE).min(eng.n);
+        let mut i = ini;
+        while i < fin_bloque {
+            let eid = eng.estado_ids.get(i);
+            let fin = despachar!(&eng.estado_ids, v => fin_corrida(v, i, fin_bloque));
+            if eid != i64::MIN {
+                for (acc, &f) in accs.iter_mut().zip(filtros) {
+                    acc.agregar_parcial(eid, &parcial_rango(eng, pols, i, fin, f, None));
+                }
+            }
+            i = fin;
+        }
+        accs
+    };
+    let bloques = eng.n.div_ceil(BLOQUE);
+    if config::secuencial(eng.n) {
+        return (0..bloques).fold(acumuladores(filtros.len()), paso);
+    }
+    (0..bloques)
+        .into_par_iter()
+        .fold(|| acumuladores(filtros.len()), paso)
+        .reduce(|| acumuladores(filtros.len()), |a, b| a.into_iter().zip(b).map(|(x, y)| x.unir(y)).collect())
+}
+
+/// agregar() de cada filtro de `filtros`, en el mismo orden, con un solo
+/// recorrido del periodo.
+pub(crate) fn agregar_filtros(eng: &EngineData, filtros: &[i64]) -> Result<Vec<Local>, String> {
+    let pols = config::negativos();
+    let accs = if !eng.grupos.is_empty() {
+        por_grupos(eng, filtros, &pols)
+    } else {
+        por_corridas(eng, filtros, &pols)
+    };
+    accs.into_iter().zip(filtros)
+        .map(|(acc, &f)| validacion::revisar(eng, f, None, acc.into_map()?))
+        .collect()
+}
+
+/// comparar() de cada filtro (ordenados y sin repetir): los que están en
+/// RESULT_CACHE salen de ahí y el resto se agrega junto, un recorrido por
+/// periodo, y se guarda filtro por filtro.
+pub(crate) fn comparar_filtros(
+    key1:    PeriodoKey,
+    key2:    PeriodoKey,
+    filtros: &[i64],
+) -> Result<Vec<(i64, AgrMap, AgrMap)>, Error> {
+    let mut filtros = filtros.to_vec();
+    filtros.sort_unstable();
+    filtros.dedup();
+    let mut out: Vec<Option<(AgrMap, AgrMap)>> = vec![None; filtros.len()];
+
+    // 1. Los que ya están en RESULT_CACHE
+    let hits: Vec<(usize, Lados, bool)> = {
+        let mut rcache = RESULT_CACHE.escribir("comparar_filtros")?;
+        let mut hits = Vec::new();
+        if let Some(map) = rcache.as_mut() {
+            for (k, &f) in filtros.iter().enumerate() {
+                let Some(hit) = map.get_mut(&(key1, key2, f)) else { continue };
+                let ahora = now_secs();
+                let vencido = revalidacion::vencido(hit.calculado_at, ahora);
+                if vencido && !revalidacion::servir_vencido() { continue; }
+                hit.ultimo_acceso = ahora;
+                hit.accesos += 1;
+                hits.push((k, hit.lados.clone(), vencido));
+            }
+        }
+        hits
+    };
+    // Descomprimir y encolar revalidaciones ya sin el lock
+    for (k, lados, vencido) in hits {
+        if vencido { revalidacion::en_fondo((key1, key2, filtros[k])); }
+        metricas::contar_hit(true);
+        let (agr1, agr2) = lados.agregados()?;
+        let agr2 = agr2.unwrap_or_else(|| agr1.clone());
+        out[k] = Some((agr1, agr2));
+    }
+
+    // 2. Los que faltan, juntos
+    let faltan: Vec<usize> = (0..filtros.len()).filter(|&k| out[k].is_none()).collect();
+    if !faltan.is_empty() {
+        faltan.iter().for_each(|_| metricas::contar_hit(false));
+        let t0 = Instant::now();
+        let fs: Vec<i64> = faltan.iter().map(|&k| filtros[k]).collect();
+        let mismo = key1 == key2;
+        let (e1, e2) = virtuales::lados(key1)
+            .and_then(|e1| Ok((e1.clone(), if mismo { e1 } else { virtuales::lados(key2)? })))
+            .inspect_err(|_| metricas::contar_error(Operacion::Comparacion))?;
+        e1.iter().chain(if mismo { &[][..] } else { &e2[..] }).for_each(|e| e.tocar());
+        let agregar_lado = |lado: &[Arc<EngineData>]| {
+            let mut totales = vec![Local::default(); fs.len()];
+            for e in lado.iter().filter(|e| e.n > 0) {
+                for (total, parcial) in totales.iter_mut().zip(agregar_filtros(e, &fs)?) {
+                    unir_agregados(total, parcial)?;
+                }
+            }
+            Ok::<_, String>(totales)
+        };
+        let filas = |lado: &[Arc<EngineData>]| lado.iter().map(|e| e.n).sum::<usize>();
+        let (l1, l2) = if mismo {
+            (config::en_pool_si(filas(&e1), || agregar_lado(&e1)), Ok(Vec::new()))
+        } else if config::secuencial(filas(&e1) + filas(&e2)) {
+            (agregar_lado(&e1), agregar_lado(&e2))
+        } else {
+            config::en_pool(|| rayon::join(|| agregar_lado(&e1), || agregar_lado(&e2)))
+        };
+        let (l1, l2) = l1.and_then(|a1| Ok((a1, l2?)))
+            .inspect_err(|_| metricas::contar_error(Operacion::Comparacion))?;
+        let mut l2 = l2.into_iter();
+        let resumenes = resumenes(&e1, &e2, mismo);
+
+        // 3. Cada filtro con su clave (comprimidos antes de tomar el lock)
+        let calculado_at = now_secs();
+        let mut entradas = Vec::with_capacity(faltan.len());
+        for (&k, agr1) in faltan.iter().zip(l1) {
+            let agr2 = if mismo { None } else { l2.next() };
+            entradas.push(((key1, key2, filtros[k]), ResultadoComp::nuevo(agr1.clone(), agr2.clone(), Arc::clone(&resumenes), calculado_at)));
+            out[k] = Some((agr1.clone(), agr2.unwrap_or(agr1)));
+        }
+        {
+            let mut rcache = RESULT_CACHE.escribir("comparar_filtros")?;
+            let map = rcache.get_or_insert_with(FxHashMap::default);
+            for (result_key, mut entrada) in entradas {
+                // Uno vencido que se reemplaza conserva sus accesos
+                if let Some(anterior) = map.get(&result_key) {
+                    entrada.accesos = anterior.accesos;
+                    entrada.ultimo_acceso = anterior.ultimo_acceso;
+                }
+                insertar_resultado(map, result_key, entrada);
+            }
+        }
+        metricas::contar_operacion(Operacion::Comparacion, t0.elapsed());
+    }
+
+    Ok(filtros.into_iter().zip(out)
+        .filter_map(|(f, r)| r.map(|(agr1, agr2)| (f, agr1, agr2)))
+        .collect())
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {filtro: {"periodo1", "periodo2", "periodo1_vacio", "periodo2_vacio",
+/// "huella"}} de cada filtro de situación de `filtros` (-1 = todas), con
+/// los mismos `formato`, `nombres` y `con_estado_nombre` que
+/// comparar_periodos().
+#[pyfunction]
+#[pyo3(signature = (
+    key1, key2, filtros, request_id = None, formato = None, nombres = None,
+    con_estado_nombre = false,
+))]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn comparar_periodos_filtros<'py>(
+    py:                Python<'py>,
+    key1:              ArgClave,
+    key2:              ArgClave,
+    filtros:           Vec<i64>,
+    request_id:        Option<&str>,
+    formato:           Option<u32>,
+    nombres:           Option<&str>,
+    con_estado_nombre: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let (key1, key2) = (clave(key1)?, clave(key2)?);
+    let metricas = metricas_formato(formato, nombres).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    if filtros.is_empty() {
+        return Err(pyo3::exceptions::PyValueError::new_err("comparar_periodos_filtros: filtros vacío"));
+    }
+    let params = format!("key1={key1} key2={key2} filtros={filtros:?}");
+    let resultados = py.allow_threads(|| {
+        bitacora::auditar("comparacion_filtros", params, request_id, || comparar_filtros(key1, key2, &filtros))
+    })?;
+    let cat = if con_estado_nombre { Some(catalogo::copia()?) } else { None };
+    let out = PyDict::new(py);
+    for (f, agr1, agr2) in &resultados {
+        let d = PyDict::new(py);
+        d.set_item(pyo3::intern!(py, "periodo1"), agregado_a_dict_formato(py, agr1, metricas, cat.as_ref())?)?;
+        d.set_item(pyo3::intern!(py, "periodo2"), agregado_a_dict_formato(py, agr2, metricas, cat.as_ref())?)?;
+        d.set_item(pyo3::intern!(py, "periodo1_vacio"), agr1.is_empty())?;
+        d.set_item(pyo3::intern!(py, "periodo2_vacio"), agr2.is_empty())?;
+        d.set_item(pyo3::intern!(py, "huella"), huella_resultado(agr1, agr2))?;
+        out.set_item(f, d)?;
+    }
+    Ok(out)
+}
@@ -0,0 +1,119 @@
+// ==============================================================================
+// plaza_rust/src/offline.rs
+//
+// API nativa (sin Python) sobre el mismo parseo y agregación del motor.
+// La usa el binario plazas-cli para reportes batch; no toca los caches
+// globales (ENGINE_PERIODOS / RESULT_CACHE).
+// ==============================================================================
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use bytes::Bytes;
+
+use crate::columna::ColI;
+use crate::{agregar, config, descomprimir_buffer, parse_parquet_bytes, EngineData, CLAVES};
+
+/// Un periodo cargado en memoria, independiente del cache del módulo Python.
+pub struct Periodo {
+    eng: EngineData,
+}
+
+/// Agregación por estado: estado_id → métricas en el orden de [`metricas`].
+pub type Agregado = BTreeMap<i64, [i64; 13]>;
+
+/// Nombres de las métricas en el orden en que aparecen en [`Agregado`]: las
+/// 7 sumas y luego los conteos de no nulos ("cn_total_n", ...).
+pub fn metricas() -> &'static [&'static str; 13] {
+    &CLAVES
+}
+
+/// Aplica un documento TOML (mismo formato que `cargar_configuracion`).
+pub fn configurar(texto_toml: &str) -> Result<(), String> {
+    config::aplicar_toml(texto_toml)
+}
+
+impl Periodo {
+    /// Parquet en bytes, opcionalmente comprimido con gzip/zstd.
+    pub fn desde_bytes(raw: &[u8]) -> Result<Self, String> {
+        Self::desde_buffer(Bytes::copy_from_slice(raw))
+    }
+
+    pub fn desde_archivo(path: &Path) -> Result<Self, String> {
+        let raw = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::desde_buffer(raw.into()).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    fn desde_buffer(raw: Bytes) -> Result<Self, String> {
+        let bytes = descomprimir_buffer(raw)?;
+        let eng = parse_parquet_bytes(bytes, &config::actual())?;
+        Ok(Periodo { eng })
+    }
+
+    pub fn filas(&self) -> usize {
+        self.eng.n
+    }
+
+    /// `filtro_situacion < 0` agrega todas las filas. `Err` si alguna suma
+    /// desborda i64.
+    pub fn agregar(&self, filtro_situacion: i64) -> Result<Agregado, String> {
+        config::en_pool_si(self.eng.n, || agregar(&self.eng, filtro_situacion))
+            .map(|m| m.into_iter().collect())
+    }
+
+    /// Filas crudas con las columnas canónicas; nulos como campo vacío.
+    pub fn exportar_csv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "lat,lng,estado_id,situacion,inc_total,aten_total,cn_total,cn_inicial,cn_prim,cn_sec")?;
+        for i in 0..self.eng.n {
+            let (lat, lng) = (self.eng.lats.get(i), self.eng.lngs.get(i));
+            write_f64(w, lat, "")?;
+            w.write_all(b",")?;
+            write_f64(w, lng, "")?;
+            for col in self.columnas_int() {
+                w.write_all(b",")?;
+                write_i64(w, col.get(i), "")?;
+            }
+            w.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Filas crudas como un arreglo JSON de objetos; nulos como `null`.
+    pub fn exportar_json<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        const NOMBRES: [&str; 8] = [
+            "estado_id", "situacion", "inc_total", "aten_total",
+            "cn_total", "cn_inicial", "cn_prim", "cn_sec",
+        ];
+        w.write_all(b"[")?;
+        for i in 0..self.eng.n {
+            if i > 0 { w.write_all(b",")?; }
+            w.write_all(b"\n{\"lat\":")?;
+            write_f64(w, self.eng.lats.get(i), "null")?;
+            w.write_all(b",\"lng\":")?;
+            write_f64(w, self.eng.lngs.get(i), "null")?;
+            for (nombre, col) in NOMBRES.iter().zip(self.columnas_int()) {
+                write!(w, ",\"{nombre}\":")?;
+                write_i64(w, col.get(i), "null")?;
+            }
+            w.write_all(b"}")?;
+        }
+        w.write_all(b"\n]\n")
+    }
+
+    fn columnas_int(&self) -> [&ColI; 8] {
+        let e = &self.eng;
+        [
+            &e.estado_ids, &e.situaciones, &e.inc_totales, &e.aten_totales,
+            &e.cn_totales, &e.cn_ini, &e.cn_prim, &e.cn_sec,
+        ]
+    }
+}
+
+fn write_f64<W: Write>(w: &mut W, v: f64, nulo: &str) -> io::Result<()> {
+    if v.is_finite() { write!(w, "{v}") } else { w.write_all(nulo.as_bytes()) }
+}
+
+fn write_i64<W: Write>(w: &mut W, v: i64, nulo: &str) -> io::Result<()> {
+    if v == i64::MIN { w.write_all(nulo.as_bytes()) } else { write!(w, "{v}") }
+}
@@ -0,0 +1,148 @@
+// ==============================================================================
+// plaza_rust/src/paginado.rs
+//
+// Filas de un periodo ordenadas por una clave compuesta y paginadas, para la
+// tabla de datos: pagina_filas(key, [("estado_id", "asc"), ("cn_total",
+// "desc")], offset=0, limite=50).
+//
+// Columnas ordenables: estado_id, situacion, lat, lng, plaza_id y las seis
+// métricas (nombre de salida o canónico). Los nulos van al final en
+// cualquier sentido. Los empates de la clave completa se rompen por la fila
+// original, así que dos páginas consecutivas nunca repiten ni saltan filas.
+// Solo se ordena lo que cae hasta la página pedida (select_nth + sort).
+// ==============================================================================
+
+use std::cmp::Ordering;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rayon::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::columna::{ColF, ColI};
+use crate::config::METRICAS_COLUMNA;
+use crate::predicados::Predicado;
+use crate::{bitacora, clave, config, periodo, EngineData, METRICAS};
+
+enum Columna<'a> {
+    Entera(&'a ColI),
+    Real(&'a ColF),
+    Plaza,
+}
+
+struct Clave<'a> {
+    columna: Columna<'a>,
+    desc:    bool,
+}
+
+fn columna<'a>(eng: &'a EngineData, nombre: &str) -> Result<Columna<'a>, String> {
+    let n = nombre.trim().to_ascii_lowercase();
+    let metrica = METRICAS[1..].iter().position(|&m| m == n)
+        .or_else(|| METRICAS_COLUMNA.iter().position(|&m| m == n));
+    Ok(match (n.as_str(), metrica) {
+        (_, Some(k))     => Columna::Entera(eng.metricas()[k]),
+        ("estado_id", _) => Columna::Entera(&eng.estado_ids),
+        ("situacion", _) => Columna::Entera(&eng.situaciones),
+        ("lat", _)       => Columna::Real(&eng.lats),
+        ("lng", _)       => Columna::Real(&eng.lngs),
+        ("plaza_id", _) if eng.tiene_plazas() => Columna::Plaza,
+        ("plaza_id", _)  => return Err("orden: el periodo no trae plaza_id".into()),
+        _ => return Err(format!("orden: columna desconocida {nombre:?}")),
+    })
+}
+
+fn claves<'a>(eng: &'a EngineData, orden: &[(String, String)]) -> Result<Vec<Clave<'a>>, String> {
+    orden.iter().map(|(nombre, sentido)| {
+        let desc = match sentido.trim().to_ascii_lowercase().as_str() {
+            "asc"  => false,
+            "desc" => true,
+            otro   => return Err(format!("orden: sentido {otro:?} en {nombre:?} (asc | desc)")),
+        };
+        Ok(Clave { columna: columna(eng, nombre)?, desc })
+    }).collect()
+}
+
+/// Compara dos valores con nulos al final sin importar el sentido.
+fn con_nulos<T: PartialOrd>(a: Option<T>, b: Option<T>, desc: bool) -> Ordering {
+    match (a, b) {
+        (None, None)    => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => {
+            let o = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+            if desc { o.reverse() } else { o }
+        }
+    }
+}
+
+/// Orden total de dos filas internas: las claves y luego la fila original.
+fn comparar(eng: &EngineData, claves: &[Clave], i: usize, j: usize) -> Ordering {
+    let entero = |c: &ColI, k: usize| Some(c.get(k)).filter(|&x| x != i64::MIN);
+    let real = |c: &ColF, k: usize| Some(c.get(k)).filter(|x| !x.is_nan());
+    claves.iter()
+        .map(|c| match c.columna {
+            Columna::Entera(col) => con_nulos(entero(col, i), entero(col, j), c.desc),
+            Columna::Real(col)   => con_nulos(real(col, i), real(col, j), c.desc),
+            Columna::Plaza       => con_nulos(eng.plaza(i), eng.plaza(j), c.desc),
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or_else(|| eng.fila_original(i).cmp(&eng.fila_original(j)))
+}
+
+/// (filas que cumplen `donde`, filas originales de [offset, offset+limite)).
+fn pagina(eng: &EngineData, claves: &[Clave], donde: &Predicado, offset: usize, limite: usize) -> (usize, Vec<usize>) {
+    let mut filas: Vec<usize> = if config::secuencial(eng.n) {
+        (0..eng.n).filter(|&i| donde.cumple(eng, i)).collect()
+    } else {
+        config::en_pool(|| (0..eng.n).into_par_iter().filter(|&i| donde.cumple(eng, i)).collect())
+    };
+    let total = filas.len();
+    let fin = offset.saturating_add(limite).min(total);
+    if offset >= fin { return (total, Vec::new()); }
+    let cmp = |a: &usize, b: &usize| comparar(eng, claves, *a, *b);
+    if fin < total {
+        filas.select_nth_unstable_by(fin, cmp);
+        filas.truncate(fin);
+    }
+    // Orden total: sort_unstable da el mismo resultado que uno estable
+    if config::secuencial(fin) {
+        filas.sort_unstable_by(cmp);
+    } else {
+        config::en_pool(|| filas.par_sort_unstable_by(cmp));
+    }
+    (total, filas[offset..].iter().map(|&i| eng.fila_original(i)).collect())
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"total": filas que cumplen `donde` (predicados.rs), "filas": índices
+/// originales de la página}. `orden` es una lista de (columna, "asc" |
+/// "desc"), la primera manda; vacía = orden del parquet.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, orden, offset = 0, limite = 50, donde = None, request_id = None))]
+pub(crate) fn pagina_filas<'py>(
+    py:          Python<'py>,
+    periodo_key: ArgClave,
+    orden:       Vec<(String, String)>,
+    offset:      usize,
+    limite:      usize,
+    donde:       Option<&str>,
+    request_id:  Option<&str>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let key = clave(periodo_key)?;
+    let params = format!("periodo_key={key} offset={offset} limite={limite} donde={donde:?}");
+    let donde = Predicado::parse(donde.unwrap_or(""))
+        .map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let (total, filas) = py.allow_threads(|| bitacora::auditar("pagina_filas", params, request_id, || {
+        let eng = periodo(key)?;
+        eng.tocar();
+        let claves = claves(&eng, &orden)?;
+        Ok::<_, crate::errores::Error>(pagina(&eng, &claves, &donde, offset, limite))
+    }))?;
+    let out = PyDict::new(py);
+    out.set_item("total", total)?;
+    out.set_item("filas", filas)?;
+    Ok(out)
+}
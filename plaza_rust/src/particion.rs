@@ -0,0 +1,146 @@
+// ==============================================================================
+// plaza_rust/src/particion.rs
+//
+// Consultas restringidas a unos estados (comparar_periodos(..., estados=[9])).
+// Los periodos ya vienen particionados por estado (agrupar_por_estado: un
+// rango contiguo por estado), así que solo se recorren los rangos pedidos.
+//
+// Cada periodo guarda además sus sub-resultados por (filtro, estado): una
+// consulta por [9, 15] después de otra por [9] solo agrega el 15, y
+// cualquier subconjunto ya visto se compone sin recorrer filas. Si
+// RESULT_CACHE tiene la comparación completa, se filtra esa.
+//
+// Los sub-resultados se atan a la instancia del periodo (Weak al Arc): al
+// recargarlo o desalojarlo dejan de valer solos, sin hooks de invalidación.
+// Un periodo sin agrupar (offline) se agrega completo y se filtra. Los
+// resúmenes de filas (MetaFilas) salen de la entrada de RESULT_CACHE en un
+// hit y de los periodos en uso en un cálculo.
+// ==============================================================================
+
+use std::sync::{Arc, Weak};
+use std::time::Instant;
+
+use rustc_hash::FxHashMap;
+
+use crate::cerrojos::Cerrojo;
+use crate::errores::Error;
+use crate::lentas;
+use crate::metricas::{self, Operacion};
+use crate::{
+    agregar_estados, config, resumenes, unir_agregados, virtuales, AgrMap, EngineData, Fila, Local, PeriodoKey,
+    Resumenes, RESULT_CACHE,
+};
+
+struct Parciales {
+    datos:      Weak<EngineData>,
+    // estado → totales; None = el estado no tiene filas que pasen el filtro
+    por_estado: FxHashMap<i64, Option<Fila>>,
+}
+
+// (dirección del EngineData, filtro). El Weak mantiene viva la dirección,
+// así que no se reutiliza mientras la entrada exista.
+static PARCIALES: Cerrojo<Option<FxHashMap<(usize, i64), Parciales>>> = Cerrojo::new("PARCIALES", None);
+
+fn id(eng: &Arc<EngineData>) -> usize {
+    Arc::as_ptr(eng) as usize
+}
+
+/// Descarta todos los sub-resultados (p. ej. cambió la política de negativos).
+pub(crate) fn vaciar() -> Result<(), Error> {
+    *PARCIALES.escribir("particion::vaciar")? = None;
+    Ok(())
+}
+
+/// Totales de `eng` para `estados` (ordenados, sin repetir), usando y
+/// completando los sub-resultados por estado.
+fn agregado(eng: &Arc<EngineData>, filtro: i64, estados: &[i64]) -> Result<Local, Error> {
+    let clave = (id(eng), filtro);
+    let mut conocidos: Vec<(i64, Option<Fila>)> = Vec::with_capacity(estados.len());
+    let mut faltan: Vec<i64> = Vec::new();
+    {
+        let guard = PARCIALES.leer("particion::agregado")?;
+        let p = guard.as_ref().and_then(|m| m.get(&clave)).filter(|p| p.datos.strong_count() > 0);
+        for &e in estados {
+            match p.and_then(|p| p.por_estado.get(&e)) {
+                Some(&v) => conocidos.push((e, v)),
+                None     => faltan.push(e),
+            }
+        }
+    }
+
+    if !faltan.is_empty() && eng.n > 0 {
+        // Agrupado: solo se recorren los rangos de los estados que faltan
+        lentas::anotar_filas(if eng.grupos.is_empty() { eng.n } else {
+            faltan.iter().map(|&e| eng.rango_estado(e).map_or(0, |r| r.len())).sum()
+        });
+        let nuevo = config::en_pool_si(eng.n, || agregar_estados(eng, filtro, &faltan))?;
+        let mut guard = PARCIALES.escribir("particion::agregado")?;
+        let m = guard.get_or_insert_with(FxHashMap::default);
+        m.retain(|_, p| p.datos.strong_count() > 0);
+        let p = m.entry(clave).or_insert_with(|| Parciales {
+            datos: Arc::downgrade(eng), por_estado: FxHashMap::default(),
+        });
+        for &e in &faltan {
+            let v = nuevo.get(&e).copied();
+            p.por_estado.insert(e, v);
+            conocidos.push((e, v));
+        }
+    }
+    Ok(conocidos.into_iter().filter_map(|(e, v)| Some((e, v?))).collect())
+}
+
+fn lado(engs: &[Arc<EngineData>], filtro: i64, estados: &[i64]) -> Result<AgrMap, Error> {
+    let mut total = Local::default();
+    for eng in engs {
+        eng.tocar();
+        unir_agregados(&mut total, agregado(eng, filtro, estados)?)?;
+    }
+    Ok(total)
+}
+
+/// comparar() restringido a `estados`. No escribe en RESULT_CACHE: lo que
+/// se reutiliza son los sub-resultados por estado.
+pub(crate) fn comparar_estados(
+    key1:    PeriodoKey,
+    key2:    PeriodoKey,
+    filtro:  i64,
+    estados: &[i64],
+) -> Result<(AgrMap, AgrMap, Resumenes), Error> {
+    let mut estados = estados.to_vec();
+    estados.sort_unstable();
+    estados.dedup();
+    let filtrar = |a: &AgrMap| -> AgrMap {
+        a.iter().filter(|(e, _)| estados.binary_search(e).is_ok()).map(|(&e, &v)| (e, v)).collect()
+    };
+
+    let lados = {
+        let mut rcache = RESULT_CACHE.escribir("comparar_estados")?;
+        rcache.as_mut().and_then(|m| m.get_mut(&(key1, key2, filtro))).map(|hit| {
+            hit.ultimo_acceso = crate::now_secs();
+            hit.accesos += 1;
+            (hit.lados.clone(), Arc::clone(&hit.resumenes))
+        })
+    };
+    // Descomprimir ya sin el lock
+    if let Some((lados, resumenes)) = lados {
+        metricas::contar_hit(true);
+        let (a1, a2) = lados.agregados()?;
+        let agr1 = filtrar(&a1);
+        let agr2 = a2.as_ref().map_or_else(|| agr1.clone(), filtrar);
+        return Ok((agr1, agr2, resumenes));
+    }
+
+    metricas::contar_hit(false);
+    let t0 = Instant::now();
+    let mismo = key1 == key2;
+    let (a1, a2, resumenes) = virtuales::lados(key1)
+        .and_then(|e1| {
+            let e2 = if mismo { Vec::new() } else { virtuales::lados(key2)? };
+            let a1 = lado(&e1, filtro, &estados)?;
+            let a2 = if mismo { a1.clone() } else { lado(&e2, filtro, &estados)? };
+            Ok((a1, a2, resumenes(&e1, &e2, mismo)))
+        })
+        .inspect_err(|_| metricas::contar_error(Operacion::Comparacion))?;
+    metricas::contar_operacion(Operacion::Comparacion, t0.elapsed());
+    Ok((a1, a2, resumenes))
+}
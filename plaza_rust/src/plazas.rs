@@ -0,0 +1,201 @@
+// ==============================================================================
+// plaza_rust/src/plazas.rs
+//
+// Seguimiento de plazas individuales entre periodos por su identificador
+// (columna canónica plaza_id; alias CURP_Plaza). Cada periodo guarda el
+// identificador como código por fila más su diccionario (EngineData.plazas),
+// así que comparar dos periodos es comparar diccionarios, sin tocar filas.
+//
+//   historial_plaza(id, keys)   métricas de una plaza en cada periodo
+//   altas_bajas(key1, key2)     identificadores que aparecen / desaparecen
+//   cohortes(keys)              plazas agrupadas por el periodo en que
+//                               aparecen por primera vez, y su evolución
+// ==============================================================================
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{clave, config, periodo, EngineData, PeriodoKey, ENGINE_PERIODOS, METRICAS};
+
+/// Periodos pedidos (o todos los cargados, ordenados) que traen plaza_id.
+fn periodos_con_plazas(keys: Option<Vec<PeriodoKey>>) -> Result<Vec<(PeriodoKey, Arc<EngineData>)>, Error> {
+    let periodos = match keys {
+        Some(keys) => keys.into_iter()
+            .map(|k| Ok((k, periodo(k)?)))
+            .collect::<Result<Vec<_>, Error>>()?,
+        None => {
+            let guard = ENGINE_PERIODOS.leer("historial_plaza")?;
+            let mut v: Vec<_> = guard.as_ref()
+                .map(|m| m.iter().map(|(&k, e)| (k, Arc::clone(e))).collect())
+                .unwrap_or_default();
+            v.sort_unstable_by_key(|&(k, _)| k);
+            v
+        }
+    };
+    Ok(periodos.into_iter().filter(|(_, e)| e.tiene_plazas()).collect())
+}
+
+/// Fila interna de la primera aparición (en el orden del parquet) de cada
+/// código de plaza; un identificador repetido usa solo esa fila.
+fn primeras_filas(eng: &EngineData) -> Vec<usize> {
+    let mut primera = vec![usize::MAX; eng.plazas.len()];
+    for i in 0..eng.n {
+        let c = eng.plaza_ids.get(i);
+        if c == i64::MIN { continue; }
+        let p = &mut primera[c as usize];
+        if *p == usize::MAX || eng.fila_original(i) < eng.fila_original(*p) { *p = i; }
+    }
+    primera
+}
+
+/// Estado, situación y métricas de la primera fila con ese identificador.
+fn fila_de_plaza(eng: &EngineData, id: &str) -> Option<[i64; 8]> {
+    let codigo = eng.plazas.iter().position(|p| p == id)? as i64;
+    let i = (0..eng.n)
+        .filter(|&i| eng.plaza_ids.get(i) == codigo)
+        .min_by_key(|&i| eng.fila_original(i))?;
+    let mut v = [0i64; 8];
+    v[0] = eng.estado_ids.get(i);
+    v[1] = eng.situaciones.get(i);
+    for (x, col) in v[2..].iter_mut().zip(eng.metricas()) {
+        *x = col.get(i);
+    }
+    Some(v)
+}
+
+fn exigir_plazas(key: PeriodoKey, eng: &EngineData) -> Result<(), Error> {
+    if eng.tiene_plazas() { return Ok(()); }
+    Err(Error::Motor(format!("Periodo {key} sin columna plaza_id")))
+}
+
+/// (altas, bajas): identificadores solo en key2 / solo en key1, ordenados.
+fn comparar_plazas(key1: PeriodoKey, key2: PeriodoKey) -> Result<(Vec<String>, Vec<String>), Error> {
+    let (e1, e2) = (periodo(key1)?, periodo(key2)?);
+    exigir_plazas(key1, &e1)?;
+    exigir_plazas(key2, &e2)?;
+    let ids1: FxHashSet<&str> = e1.plazas.iter().map(String::as_str).collect();
+    let ids2: FxHashSet<&str> = e2.plazas.iter().map(String::as_str).collect();
+    let mut altas: Vec<String> = ids2.difference(&ids1).map(|s| s.to_string()).collect();
+    let mut bajas: Vec<String> = ids1.difference(&ids2).map(|s| s.to_string()).collect();
+    altas.sort_unstable();
+    bajas.sort_unstable();
+    Ok((altas, bajas))
+}
+
+/// cohorte → periodo → [plazas, métricas...] (como el acumulador de agregar).
+type Cohortes = BTreeMap<PeriodoKey, BTreeMap<PeriodoKey, [i64; 7]>>;
+
+/// Recorre los periodos en orden; cada identificador pertenece a la cohorte
+/// del primer periodo en que aparece y, en cada periodo donde está, suma a
+/// esa cohorte su primera fila. Métricas con la misma política de negativos
+/// que agregar(); una suma que desborda es error.
+fn calcular_cohortes(mut keys: Vec<PeriodoKey>) -> Result<Cohortes, Error> {
+    keys.sort_unstable();
+    keys.dedup();
+    let periodos = keys.iter()
+        .map(|&k| { let e = periodo(k)?; exigir_plazas(k, &e)?; Ok((k, e)) })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let pols = config::negativos();
+    let mut cohorte_de: FxHashMap<&str, PeriodoKey> = FxHashMap::default();
+    let mut out = Cohortes::new();
+    for (k, eng) in &periodos {
+        eng.tocar();
+        let metricas = eng.metricas();
+        for (c, i) in primeras_filas(eng).into_iter().enumerate() {
+            if i == usize::MAX { continue; }
+            let cohorte = *cohorte_de.entry(eng.plazas[c].as_str()).or_insert(*k);
+            let acc = out.entry(cohorte).or_default().entry(*k).or_insert([0; 7]);
+            acc[0] += 1;
+            for (m, (col, pol)) in metricas.iter().zip(pols).enumerate() {
+                let x = col.get(i);
+                if pol == config::Negativos::Error && x < 0 && x != i64::MIN {
+                    return Err(Error::Motor(format!(
+                        "{}: valor negativo {x} en el periodo {k} (política \"error\")",
+                        config::METRICAS_COLUMNA[m],
+                    )));
+                }
+                acc[m + 1] = acc[m + 1].checked_add(pol.valor(x)).ok_or_else(|| {
+                    Error::Motor(format!("desbordamiento i64 sumando {}", METRICAS[m + 1]))
+                })?;
+            }
+        }
+    }
+    Ok(out)
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// {periodo_key: {"estado_id", "situacion", "inc_total", ...}} de la plaza en
+/// cada periodo de `keys` (default: todos los cargados) donde aparece.
+/// Nulos como None. Los periodos sin columna plaza_id se omiten.
+#[pyfunction]
+#[pyo3(signature = (plaza_id, keys = None))]
+pub(crate) fn historial_plaza<'py>(
+    py:       Python<'py>,
+    plaza_id: &str,
+    keys:     Option<Vec<ArgClave>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let keys = keys.map(|ks| ks.into_iter().map(clave).collect::<PyResult<Vec<_>>>()).transpose()?;
+    let id = plaza_id.trim();
+    let filas: BTreeMap<PeriodoKey, [i64; 8]> = py.allow_threads(|| -> Result<_, Error> {
+        Ok(periodos_con_plazas(keys)?.iter()
+            .filter_map(|(k, e)| fila_de_plaza(e, id).map(|v| (*k, v)))
+            .collect())
+    })?;
+
+    let nombres = ["estado_id", "situacion"].into_iter().chain(METRICAS[1..].iter().copied());
+    let nombres: Vec<&str> = nombres.collect();
+    let out = PyDict::new(py);
+    for (k, v) in filas {
+        let m = PyDict::new(py);
+        for (nombre, x) in nombres.iter().zip(v) {
+            m.set_item(nombre, (x != i64::MIN).then_some(x))?;
+        }
+        out.set_item(k, m)?;
+    }
+    Ok(out)
+}
+
+/// {cohorte: {periodo_key: {"plazas": n, "inc_total": ..., ...}}}: cohorte es
+/// el primer periodo de `keys` en que aparece cada plaza; "plazas" es cuántas
+/// de esa cohorte siguen presentes en cada periodo posterior. Todos los
+/// periodos deben traer plaza_id.
+#[pyfunction]
+pub(crate) fn cohortes(py: Python<'_>, keys: Vec<ArgClave>) -> PyResult<Bound<'_, PyDict>> {
+    let keys = keys.into_iter().map(clave).collect::<PyResult<Vec<_>>>()?;
+    let c = py.allow_threads(|| calcular_cohortes(keys))?;
+    let out = PyDict::new(py);
+    for (cohorte, evolucion) in c {
+        let por_periodo = PyDict::new(py);
+        for (k, v) in evolucion {
+            let m = PyDict::new(py);
+            for (nombre, x) in METRICAS.iter().zip(v) {
+                m.set_item(nombre, x)?;
+            }
+            por_periodo.set_item(k, m)?;
+        }
+        out.set_item(cohorte, por_periodo)?;
+    }
+    Ok(out)
+}
+
+/// {"altas": [...], "bajas": [...]}: identificadores que están en key2 y no
+/// en key1, y viceversa. Error si alguno de los periodos no trae plaza_id.
+#[pyfunction]
+pub(crate) fn altas_bajas(py: Python<'_>, key1: ArgClave, key2: ArgClave) -> PyResult<Bound<'_, PyDict>> {
+    let (key1, key2) = (clave(key1)?, clave(key2)?);
+    let (altas, bajas) = py.allow_threads(|| comparar_plazas(key1, key2))?;
+    let out = PyDict::new(py);
+    out.set_item("altas", altas)?;
+    out.set_item("bajas", bajas)?;
+    Ok(out)
+}
@@ -0,0 +1,177 @@
+// ==============================================================================
+// plaza_rust/src/poligonos.rs
+//
+// Agregación por regiones propias (jurisdicciones sanitarias, distritos):
+// cada plaza se asigna al polígono que la contiene y se suman las métricas
+// por id de polígono, como agregar() por estado. Reemplaza el spatial join
+// de geopandas para los coropléticos.
+//
+// Entrada: un FeatureCollection GeoJSON (dict ya parseado o str) con
+// geometrías Polygon / MultiPolygon en [lng, lat]; huecos por regla par-impar.
+// Con polígonos encimados gana el primer feature que contiene la plaza.
+// Features con el mismo id se suman juntos.
+// ==============================================================================
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyString};
+use rayon::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{clave, config, parcial_rango, periodo, Acumulador, EngineData, ANCHO};
+
+/// Un polígono (con sus huecos) de un grupo; coordenadas (lng, lat).
+struct Poligono {
+    grupo:   usize,
+    anillos: Vec<Vec<(f64, f64)>>,
+    // (lng_min, lat_min, lng_max, lat_max)
+    caja:    (f64, f64, f64, f64),
+}
+
+impl Poligono {
+    fn new(grupo: usize, anillos: Vec<Vec<(f64, f64)>>) -> Self {
+        let caja = anillos.iter().flatten().fold(
+            (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            |(a, b, c, d), &(x, y)| (a.min(x), b.min(y), c.max(x), d.max(y)),
+        );
+        Poligono { grupo, anillos, caja }
+    }
+
+    /// Par-impar sobre todos los anillos: los huecos quedan fuera.
+    fn contiene(&self, x: f64, y: f64) -> bool {
+        let (x0, y0, x1, y1) = self.caja;
+        if x < x0 || x > x1 || y < y0 || y > y1 { return false; }
+        let mut dentro = false;
+        for anillo in &self.anillos {
+            let mut j = anillo.len().wrapping_sub(1);
+            for (i, &(xi, yi)) in anillo.iter().enumerate() {
+                let (xj, yj) = anillo[j];
+                if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                    dentro = !dentro;
+                }
+                j = i;
+            }
+        }
+        dentro
+    }
+}
+
+fn item<'py>(d: &Bound<'py, PyAny>, k: &str, donde: &str) -> PyResult<Bound<'py, PyAny>> {
+    d.get_item(k).map_err(|_| PyValueError::new_err(format!("{donde}: falta {k:?}")))
+}
+
+/// Anillos de un Polygon ([[[lng, lat], ...], ...]); ignora la altura.
+fn anillos(coords: &Bound<'_, PyAny>, donde: &str) -> PyResult<Vec<Vec<(f64, f64)>>> {
+    let crudos: Vec<Vec<Vec<f64>>> = coords.extract()
+        .map_err(|_| PyValueError::new_err(format!("{donde}: coordinates inválidas")))?;
+    crudos.into_iter().map(|anillo| anillo.into_iter().map(|p| match p[..] {
+        [lng, lat, ..] => Ok((lng, lat)),
+        _ => Err(PyValueError::new_err(format!("{donde}: posición con menos de 2 coordenadas"))),
+    }).collect()).collect()
+}
+
+/// (ids por grupo, polígonos) del FeatureCollection.
+fn leer_features(py: Python<'_>, fc: &Bound<'_, PyAny>, id_property: &str) -> PyResult<(Vec<PyObject>, Vec<Poligono>)> {
+    let fc = if fc.is_instance_of::<PyString>() {
+        py.import("json")?.call_method1("loads", (fc,))?
+    } else {
+        fc.clone()
+    };
+    // id → grupo, con la igualdad de Python
+    let grupos = PyDict::new(py);
+    let mut ids: Vec<PyObject> = Vec::new();
+    let mut poligonos = Vec::new();
+    for (k, f) in item(&fc, "features", "FeatureCollection")?.try_iter()?.enumerate() {
+        let f = f?;
+        let donde = format!("feature {k}");
+        let id = item(&item(&f, "properties", &donde)?, id_property, &donde)?;
+        let grupo = match grupos.get_item(&id)? {
+            Some(g) => g.extract::<usize>()?,
+            None => {
+                grupos.set_item(&id, ids.len())?;
+                ids.push(id.unbind());
+                ids.len() - 1
+            }
+        };
+        let geom = item(&f, "geometry", &donde)?;
+        let coords = item(&geom, "coordinates", &donde)?;
+        match item(&geom, "type", &donde)?.extract::<String>()?.as_str() {
+            "Polygon" => poligonos.push(Poligono::new(grupo, anillos(&coords, &donde)?)),
+            "MultiPolygon" => {
+                for p in coords.try_iter()? {
+                    poligonos.push(Poligono::new(grupo, anillos(&p?, &donde)?));
+                }
+            }
+            otro => return Err(PyValueError::new_err(format!("{donde}: geometría {otro:?} (Polygon | MultiPolygon)"))),
+        }
+    }
+    Ok((ids, poligonos))
+}
+
+/// Grupo de cada fila interna (None: sin coordenadas o fuera de todo).
+fn asignar(eng: &EngineData, poligonos: &[Poligono]) -> Vec<Option<usize>> {
+    let por_fila = |i: usize| {
+        let (la, lo) = (eng.lats.get(i), eng.lngs.get(i));
+        if la.is_nan() || lo.is_nan() { return None; }
+        poligonos.iter().find(|p| p.contiene(lo, la)).map(|p| p.grupo)
+    };
+    if config::secuencial(eng.n.saturating_mul(poligonos.len().max(1))) {
+        (0..eng.n).map(por_fila).collect()
+    } else {
+        config::en_pool(|| (0..eng.n).into_par_iter().map(por_fila).collect())
+    }
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"por_poligono": {id: {"plazas", "inc_total", ...}}, "sin_poligono",
+/// "sin_coordenadas"}. Trae todos los ids del FeatureCollection (en ceros si
+/// no contienen plazas); `filtro` es la situación (< 0 = todas) y las
+/// métricas siguen la política de negativos de agregar().
+#[pyfunction]
+#[pyo3(signature = (periodo_key, geojson_featurecollection, id_property, filtro = -1))]
+pub(crate) fn agregar_por_poligonos<'py>(
+    py:                        Python<'py>,
+    periodo_key:               ArgClave,
+    geojson_featurecollection: &Bound<'py, PyAny>,
+    id_property:               &str,
+    filtro:                    i64,
+) -> PyResult<Bound<'py, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    let (ids, poligonos) = leer_features(py, geojson_featurecollection, id_property)?;
+
+    let (totales, sin_poligono, sin_coordenadas) = py.allow_threads(|| -> Result<_, Error> {
+        let eng = periodo(periodo_key)?;
+        eng.tocar();
+        let grupos = asignar(&eng, &poligonos);
+        let pols = config::negativos();
+        let mut acc = Acumulador::new();
+        let (mut sin_poligono, mut sin_coordenadas) = (0usize, 0usize);
+        for (i, g) in grupos.iter().enumerate() {
+            match g {
+                Some(g) => acc.agregar_parcial(*g as i64, &parcial_rango(&eng, &pols, i, i + 1, filtro, None)),
+                None if eng.lats.get(i).is_nan() || eng.lngs.get(i).is_nan() => sin_coordenadas += 1,
+                None => sin_poligono += 1,
+            }
+        }
+        Ok((acc.into_map()?, sin_poligono, sin_coordenadas))
+    })?;
+
+    let por_poligono = PyDict::new(py);
+    for (g, id) in ids.iter().enumerate() {
+        let v = totales.get(&(g as i64)).copied().unwrap_or([0; ANCHO]);
+        let m = PyDict::new(py);
+        for (k, x) in crate::claves_salida(config::nombres()).iter().zip(v) {
+            m.set_item(k, x)?;
+        }
+        por_poligono.set_item(id, m)?;
+    }
+    let out = PyDict::new(py);
+    out.set_item("por_poligono", por_poligono)?;
+    out.set_item("sin_poligono", sin_poligono)?;
+    out.set_item("sin_coordenadas", sin_coordenadas)?;
+    Ok(out)
+}
@@ -0,0 +1,95 @@
+// ==============================================================================
+// plaza_rust/src/predicados.rs
+//
+// Condiciones sobre métricas de fila para filtrar_indices(..., donde=...):
+//
+//   "cn_total >= 10 AND aten_total == 0"
+//
+// Una o más comparaciones `métrica op entero` unidas por AND (sin importar
+// mayúsculas); op es ==, !=, >, >=, < o <=. Las métricas son las seis de
+// columna, por nombre de salida (cn_ini) o canónico (cn_inicial). Una fila
+// con la métrica nula no cumple ninguna comparación sobre ella. Se evalúa
+// dentro del mismo recorrido paralelo que el filtro de estado/situación.
+// ==============================================================================
+
+use crate::config::METRICAS_COLUMNA;
+use crate::{EngineData, METRICAS};
+
+#[derive(Clone, Copy)]
+enum Op {
+    Igual,
+    Distinto,
+    Mayor,
+    MayorIgual,
+    Menor,
+    MenorIgual,
+}
+
+// En la misma posición gana el más largo: ">=" no se lee como ">"
+const OPERADORES: [(&str, Op); 6] = [
+    ("==", Op::Igual),
+    ("!=", Op::Distinto),
+    (">=", Op::MayorIgual),
+    ("<=", Op::MenorIgual),
+    (">",  Op::Mayor),
+    ("<",  Op::Menor),
+];
+
+struct Comparacion {
+    // Índice en EngineData::metricas()
+    metrica: usize,
+    op:      Op,
+    valor:   i64,
+}
+
+/// Conjunción de comparaciones; vacía = toda fila cumple.
+#[derive(Default)]
+pub(crate) struct Predicado(Vec<Comparacion>);
+
+fn metrica(nombre: &str) -> Option<usize> {
+    let n = nombre.to_ascii_lowercase();
+    METRICAS[1..].iter().position(|&m| m == n)
+        .or_else(|| METRICAS_COLUMNA.iter().position(|&m| m == n))
+}
+
+fn comparacion(texto: &str) -> Result<Comparacion, String> {
+    let (pos, simbolo, op) = OPERADORES.iter()
+        .filter_map(|&(s, op)| texto.find(s).map(|p| (p, s, op)))
+        .min_by_key(|&(p, s, _)| (p, std::cmp::Reverse(s.len())))
+        .ok_or_else(|| format!("donde: falta el operador en {texto:?} (==, !=, >, >=, <, <=)"))?;
+    let (izq, der) = (texto[..pos].trim(), texto[pos + simbolo.len()..].trim());
+    let metrica = metrica(izq).ok_or_else(|| format!(
+        "donde: métrica desconocida {izq:?} (se espera una de {:?})", &METRICAS[1..]
+    ))?;
+    let valor = der.parse().map_err(|_| format!("donde: valor no entero {der:?} en {texto:?}"))?;
+    Ok(Comparacion { metrica, op, valor })
+}
+
+impl Predicado {
+    pub(crate) fn parse(texto: &str) -> Result<Self, String> {
+        if texto.trim().is_empty() { return Ok(Self::default()); }
+        // Separa por AND como palabra, sin tocar los nombres de métricas
+        let palabras: Vec<&str> = texto.split_whitespace().collect();
+        palabras.split(|p| p.eq_ignore_ascii_case("and"))
+            .map(|partes| comparacion(&partes.join(" ")))
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// `true` si la fila `i` (orden interno) cumple todas las comparaciones.
+    #[inline]
+    pub(crate) fn cumple(&self, eng: &EngineData, i: usize) -> bool {
+        let cols = eng.metricas();
+        self.0.iter().all(|c| {
+            let x = cols[c.metrica].get(i);
+            x != i64::MIN && match c.op {
+                Op::Igual      => x == c.valor,
+                Op::Distinto   => x != c.valor,
+                Op::Mayor      => x >  c.valor,
+                Op::MayorIgual => x >= c.valor,
+                Op::Menor      => x <  c.valor,
+                Op::MenorIgual => x <= c.valor,
+            }
+        })
+    }
+}
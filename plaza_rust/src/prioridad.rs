@@ -0,0 +1,123 @@
+// ==============================================================================
+// plaza_rust/src/prioridad.rs
+//
+// Prioridad de las comparaciones (comparar_periodos(..., prioridad=...)),
+// para que el precálculo de fondo no suba la latencia del tablero:
+//
+//   "alta"    interactiva; mientras haya alguna en curso las bajas esperan
+//   "normal"  como siempre (default)
+//   "baja"    precálculo: entra cuando no hay altas en curso, corre en el
+//             hilo que llama (sin tomar el pool de Rayon) y entre bloque y
+//             bloque cede si llegó una alta, hasta que terminen
+//
+// Una baja no se interrumpe a media suma: cede en ceder(), que agregar()
+// llama por bloque de BLOQUE filas.
+// ==============================================================================
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Prioridad {
+    Alta,
+    Normal,
+    Baja,
+}
+
+impl Prioridad {
+    pub(crate) fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "alta"   => Ok(Prioridad::Alta),
+            "normal" => Ok(Prioridad::Normal),
+            "baja"   => Ok(Prioridad::Baja),
+            otro => Err(format!("prioridad '{otro}': se espera alta | normal | baja")),
+        }
+    }
+}
+
+static ALTAS: AtomicUsize = AtomicUsize::new(0);
+static BAJAS: AtomicUsize = AtomicUsize::new(0);
+static ESPERANDO: AtomicUsize = AtomicUsize::new(0);
+static CESIONES: AtomicU64 = AtomicU64::new(0);
+// Solo para esperar sin altas; el estado son los atómicos
+static TURNO: Mutex<()> = Mutex::new(());
+static SIN_ALTAS: Condvar = Condvar::new();
+
+thread_local! {
+    static EN_FONDO: Cell<bool> = const { Cell::new(false) };
+}
+
+struct Alta;
+
+impl Drop for Alta {
+    fn drop(&mut self) {
+        if ALTAS.fetch_sub(1, Ordering::SeqCst) == 1 {
+            // Con el mutex: una baja entre el chequeo y el wait no se pierde el aviso
+            let _g = TURNO.lock();
+            SIN_ALTAS.notify_all();
+        }
+    }
+}
+
+/// Bloquea hasta que no haya altas en curso.
+fn esperar_sin_altas() {
+    if ALTAS.load(Ordering::SeqCst) == 0 { return; }
+    ESPERANDO.fetch_add(1, Ordering::Relaxed);
+    CESIONES.fetch_add(1, Ordering::Relaxed);
+    if let Ok(g) = TURNO.lock() {
+        let _g = SIN_ALTAS.wait_while(g, |_| ALTAS.load(Ordering::SeqCst) > 0);
+    }
+    ESPERANDO.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Corre `f` con la prioridad `p`.
+pub(crate) fn ejecutar<R>(p: Prioridad, f: impl FnOnce() -> R) -> R {
+    match p {
+        Prioridad::Normal => f(),
+        Prioridad::Alta => {
+            ALTAS.fetch_add(1, Ordering::SeqCst);
+            let _alta = Alta;
+            f()
+        }
+        Prioridad::Baja => {
+            esperar_sin_altas();
+            BAJAS.fetch_add(1, Ordering::Relaxed);
+            let antes = EN_FONDO.with(|c| c.replace(true));
+            let r = f();
+            EN_FONDO.with(|c| c.set(antes));
+            BAJAS.fetch_sub(1, Ordering::Relaxed);
+            r
+        }
+    }
+}
+
+/// `true` dentro de una baja: config::secuencial() no usa el pool.
+pub(crate) fn en_fondo() -> bool {
+    EN_FONDO.with(Cell::get)
+}
+
+/// Punto de cesión de una baja (no hace nada fuera de una).
+#[inline]
+pub(crate) fn ceder() {
+    if en_fondo() { esperar_sin_altas(); }
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"altas", "bajas", "bajas_esperando", "cesiones"}: en curso ahora y
+/// cuántas veces una baja esperó a que terminaran las altas.
+#[pyfunction]
+pub(crate) fn estado_prioridades(py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+    let d = PyDict::new(py);
+    d.set_item("altas", ALTAS.load(Ordering::Relaxed))?;
+    d.set_item("bajas", BAJAS.load(Ordering::Relaxed))?;
+    d.set_item("bajas_esperando", ESPERANDO.load(Ordering::Relaxed))?;
+    d.set_item("cesiones", CESIONES.load(Ordering::Relaxed))?;
+    Ok(d)
+}
@@ -0,0 +1,148 @@
+// ==============================================================================
+// plaza_rust/src/proyeccion.rs
+//
+// Proyección de una métrica por estado para la columna "esperado el próximo
+// mes" de planeación: recta de mínimos cuadrados sobre los periodos pedidos
+// y banda de predicción al 95% (t de Student con n-2 grados de libertad).
+//
+// El eje x son meses (año*12 + mes) con [cache] claves_calendario, así que
+// un hueco en la serie se respeta; sin el flag, las claves mismas. Cada
+// estado se ajusta con los periodos donde aparece y necesita al menos dos;
+// con dos la recta pasa por ambos y no hay banda.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::alias::ArgClave;
+use crate::errores::Error;
+use crate::{agregar, clave, config, periodo, PeriodoKey, METRICAS};
+
+// t de Student bilateral al 95% para 1..=30 grados de libertad; después, z
+const T95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+fn t95(gl: usize) -> f64 {
+    T95.get(gl.wrapping_sub(1)).copied().unwrap_or(1.960)
+}
+
+fn a_x(key: PeriodoKey) -> i64 {
+    if config::claves_calendario() { (key / 100) as i64 * 12 + (key % 100) as i64 - 1 } else { key as i64 }
+}
+
+fn de_x(x: i64) -> PeriodoKey {
+    if config::claves_calendario() { ((x / 12) * 100 + x % 12 + 1) as PeriodoKey } else { x as PeriodoKey }
+}
+
+struct Punto {
+    periodo: PeriodoKey,
+    valor:   f64,
+    // None con solo dos puntos (sin grados de libertad para el error)
+    banda:   Option<(f64, f64)>,
+}
+
+struct Ajuste {
+    pendiente: f64,
+    n:         usize,
+    puntos:    Vec<Punto>,
+}
+
+/// Mínimos cuadrados sobre (x, y); proyecta `horizonte` pasos tras el último x.
+fn ajustar(serie: &[(i64, f64)], horizonte: u32) -> Ajuste {
+    let n = serie.len() as f64;
+    let mx = serie.iter().map(|p| p.0 as f64).sum::<f64>() / n;
+    let my = serie.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = serie.iter().map(|p| (p.0 as f64 - mx).powi(2)).sum();
+    let sxy: f64 = serie.iter().map(|p| (p.0 as f64 - mx) * (p.1 - my)).sum();
+    let pendiente = sxy / sxx;
+    // Centrado en mx: con x en meses (~24 000) el intercepto en 0 pierde precisión
+    let recta = |x: i64| my + pendiente * (x as f64 - mx);
+
+    let gl = serie.len() - 2;
+    let s = (gl > 0).then(|| {
+        let sse: f64 = serie.iter().map(|p| (p.1 - recta(p.0)).powi(2)).sum();
+        (sse / gl as f64).sqrt()
+    });
+    let ultimo = serie.iter().map(|p| p.0).max().unwrap_or(0);
+    let puntos = (1..=horizonte as i64).map(|h| {
+        let x = ultimo + h;
+        let valor = recta(x);
+        let banda = s.map(|s| {
+            let m = t95(gl) * s * (1.0 + 1.0 / n + (x as f64 - mx).powi(2) / sxx).sqrt();
+            (valor - m, valor + m)
+        });
+        Punto { periodo: de_x(x), valor, banda }
+    }).collect();
+    Ajuste { pendiente, n: serie.len(), puntos }
+}
+
+/// estado → ajuste. Agrega cada periodo sin pasar por RESULT_CACHE: una
+/// serie larga no debe desalojar comparaciones reales.
+fn proyectar_metrica(keys: &[PeriodoKey], m: usize, horizonte: u32) -> Result<BTreeMap<i64, Ajuste>, Error> {
+    let mut series: BTreeMap<i64, Vec<(i64, f64)>> = BTreeMap::new();
+    for &k in keys {
+        let eng = periodo(k)?;
+        eng.tocar();
+        if eng.n == 0 { continue; }
+        let agr = config::en_pool_si(eng.n, || agregar(&eng, -1))?;
+        for (&eid, v) in &agr {
+            series.entry(eid).or_default().push((a_x(k), v[m] as f64));
+        }
+    }
+    Ok(series.into_iter()
+        .filter(|(_, s)| s.len() >= 2)
+        .map(|(eid, s)| (eid, ajustar(&s, horizonte)))
+        .collect())
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {estado_id: {"pendiente", "n_periodos", "proyecciones": [{"periodo",
+/// "valor", "inferior", "superior"}, ...]}} con `horizonte` periodos después
+/// del último de `keys`. Los estados presentes en menos de dos periodos se
+/// omiten; "inferior"/"superior" son None cuando solo hay dos.
+#[pyfunction]
+#[pyo3(signature = (keys, metric, horizonte = 1))]
+pub(crate) fn proyectar<'py>(
+    py:        Python<'py>,
+    keys:      Vec<ArgClave>,
+    metric:    &str,
+    horizonte: u32,
+) -> PyResult<Bound<'py, PyDict>> {
+    let mut keys = keys.into_iter().map(clave).collect::<PyResult<Vec<_>>>()?;
+    keys.sort_unstable();
+    keys.dedup();
+    if keys.len() < 2 {
+        return Err(PyValueError::new_err("proyectar necesita al menos dos periodos"));
+    }
+    let m = METRICAS.iter().position(|&x| x == metric).ok_or_else(|| {
+        PyValueError::new_err(format!("métrica desconocida: {metric:?} ({})", METRICAS.join(" | ")))
+    })?;
+    let ajustes = py.allow_threads(|| proyectar_metrica(&keys, m, horizonte))?;
+
+    let out = PyDict::new(py);
+    for (eid, a) in ajustes {
+        let d = PyDict::new(py);
+        d.set_item("pendiente", a.pendiente)?;
+        d.set_item("n_periodos", a.n)?;
+        let pts = a.puntos.iter().map(|p| {
+            let q = PyDict::new(py);
+            q.set_item("periodo", p.periodo)?;
+            q.set_item("valor", p.valor)?;
+            q.set_item("inferior", p.banda.map(|b| b.0))?;
+            q.set_item("superior", p.banda.map(|b| b.1))?;
+            Ok(q)
+        }).collect::<PyResult<Vec<_>>>()?;
+        d.set_item("proyecciones", pts)?;
+        out.set_item(eid, d)?;
+    }
+    Ok(out)
+}
@@ -0,0 +1,48 @@
+// ==============================================================================
+// plaza_rust/src/pruebas.rs
+//
+// Apoyo de los tests: parquets en memoria con las columnas canónicas, para
+// cargar periodos sin archivos. Los periodos de los tests viven en los
+// caches globales y los tests corren en paralelo, así que cada test usa
+// claves propias (año 2300 en adelante).
+// ==============================================================================
+
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+use bytes::Bytes;
+use parquet::arrow::ArrowWriter;
+
+/// (estado_id, situacion, valor): las seis métricas valen `valor`;
+/// estado_id None = nulo.
+pub(crate) type FilaPrueba = (Option<i64>, i64, i64);
+
+/// Parquet con una fila por elemento de `filas`.
+pub(crate) fn parquet(filas: &[FilaPrueba]) -> Bytes {
+    let n = filas.len();
+    let entero = |f: &dyn Fn(&FilaPrueba) -> Option<i64>| -> ArrayRef {
+        Arc::new(filas.iter().map(f).collect::<Int64Array>())
+    };
+    let mut columnas: Vec<(&str, ArrayRef)> = vec![
+        ("lat",       Arc::new(Float64Array::from(vec![19.4; n]))),
+        ("lng",       Arc::new(Float64Array::from(vec![-99.1; n]))),
+        ("estado_id", entero(&|f| f.0)),
+        ("situacion", entero(&|f| Some(f.1))),
+        ("plaza_id",  Arc::new((0..n).map(|i| format!("P{i:06}")).map(Some).collect::<StringArray>())),
+    ];
+    for m in crate::config::METRICAS_COLUMNA {
+        columnas.push((m, entero(&|f| Some(f.2))));
+    }
+    let batch = RecordBatch::try_from_iter(columnas).expect("batch de prueba");
+    let mut out = Vec::new();
+    let mut w = ArrowWriter::try_new(&mut out, batch.schema(), None).expect("writer de prueba");
+    w.write(&batch).expect("escribir parquet de prueba");
+    w.close().expect("cerrar parquet de prueba");
+    Bytes::from(out)
+}
+
+/// Carga `filas` como el periodo `key` (estricta, definitiva, forzada).
+pub(crate) fn cargar(key: u32, filas: &[FilaPrueba]) {
+    let opciones = crate::OpcionesCarga { forzar: true, ..Default::default() };
+    crate::cargar_periodo_con(parquet(filas), key, opciones).expect("carga de prueba");
+}
@@ -0,0 +1,234 @@
+// ==============================================================================
+// plaza_rust/src/refresco.rs
+//
+// Refresco programado de comparaciones: la vista por defecto del tablero
+// (p. ej. "latest" contra "previous") se recalcula sola para que siempre
+// sea un hit de RESULT_CACHE.
+//
+//   programar_refresco([("latest", "previous"), (202401, 202312)], 600)
+//
+// Un hilo de mantenimiento ("plaza-refresco", se arranca con la primera
+// programación) recalcula cada par cada `intervalo_s` segundos y, además,
+// justo después de cada carga de periodo que termina bien (el alias
+// "latest" puede haber cambiado). intervalo_s = 0: solo tras cargas. Los
+// alias se resuelven en cada refresco. Corre con prioridad baja
+// (prioridad.rs) y reemplaza la entrada conservando sus accesos.
+//
+// El mismo hilo atiende la cola de revalidaciones de revalidacion.rs
+// (resultados vencidos servidos mientras se recalculan), de a una y
+// después de los pares programados que tocan.
+// ==============================================================================
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::alias::ArgClave;
+use crate::prioridad::{self, Prioridad};
+use crate::{bitacora, revalidacion, PeriodoKey, ResultKey};
+
+// Espera máxima del hilo entre revisiones sin avisos
+const ESPERA_MAXIMA: Duration = Duration::from_secs(60);
+
+struct Programado {
+    key1:        ArgClave,
+    key2:        ArgClave,
+    filtro:      i64,
+    intervalo_s: u64,
+    // Último refresco intentado; None = pendiente (recién programado o tras una carga)
+    ultimo:      Option<Instant>,
+    refrescos:   u64,
+    error:       Option<String>,
+}
+
+impl Programado {
+    fn toca(&self, ahora: Instant) -> bool {
+        match self.ultimo {
+            None    => true,
+            Some(t) => self.intervalo_s > 0 && ahora.duration_since(t).as_secs() >= self.intervalo_s,
+        }
+    }
+
+    /// Cuánto falta para el próximo refresco por intervalo.
+    fn falta(&self, ahora: Instant) -> Option<Duration> {
+        if self.intervalo_s == 0 { return self.ultimo.is_none().then_some(Duration::ZERO); }
+        let t = self.ultimo?;
+        Some((t + Duration::from_secs(self.intervalo_s)).saturating_duration_since(ahora))
+    }
+}
+
+struct Estado {
+    programados: Vec<Programado>,
+    // (clave, request_id del pedido que la encoló); acotada en revalidacion.rs
+    revalidar:   VecDeque<(ResultKey, Option<String>)>,
+}
+
+static ESTADO: Mutex<Estado> = Mutex::new(Estado { programados: Vec::new(), revalidar: VecDeque::new() });
+static AVISO: Condvar = Condvar::new();
+static ARRANCADO: AtomicBool = AtomicBool::new(false);
+
+fn describir(k: &ArgClave) -> String {
+    match k {
+        ArgClave::Numero(n) => n.to_string(),
+        ArgClave::Alias(a)  => a.trim().to_lowercase(),
+    }
+}
+
+fn mismo_par(p: &Programado, key1: &ArgClave, key2: &ArgClave, filtro: i64) -> bool {
+    p.filtro == filtro && describir(&p.key1) == describir(key1) && describir(&p.key2) == describir(key2)
+}
+
+fn refrescar(key1: ArgClave, key2: ArgClave, filtro: i64) -> Result<(PeriodoKey, PeriodoKey), String> {
+    let (k1, k2) = (key1.resolver().map_err(|e| e.to_string())?, key2.resolver().map_err(|e| e.to_string())?);
+    prioridad::ejecutar(Prioridad::Baja, || crate::calcular(k1, k2, filtro)).map_err(|e| e.to_string())?;
+    Ok((k1, k2))
+}
+
+fn hilo() {
+    let Ok(mut g) = ESTADO.lock() else { return };
+    loop {
+        let ahora = Instant::now();
+        let pendientes: Vec<(ArgClave, ArgClave, i64)> = g.programados.iter_mut()
+            .filter(|p| p.toca(ahora))
+            .map(|p| {
+                p.ultimo = Some(ahora);
+                (p.key1.clone(), p.key2.clone(), p.filtro)
+            })
+            .collect();
+        if !pendientes.is_empty() {
+            // Sin el lock: programar/quitar no esperan a los recálculos
+            drop(g);
+            let resultados: Vec<_> = pendientes.into_iter().map(|(key1, key2, filtro)| {
+                let id = (describir(&key1), describir(&key2), filtro);
+                (id, refrescar(key1, key2, filtro))
+            }).collect();
+            let Ok(g2) = ESTADO.lock() else { return };
+            g = g2;
+            for ((k1, k2, filtro), r) in resultados {
+                if let Err(e) = &r {
+                    bitacora::anotar("refresco", format!("key1={k1} key2={k2} filtro={filtro} error={e}"));
+                }
+                let Some(p) = g.programados.iter_mut().find(|p| {
+                    p.filtro == filtro && describir(&p.key1) == k1 && describir(&p.key2) == k2
+                }) else { continue };
+                match r {
+                    Ok(_)  => { p.refrescos += 1; p.error = None; }
+                    Err(e) => p.error = Some(e),
+                }
+            }
+            continue;
+        }
+        if let Some((key, request_id)) = g.revalidar.pop_front() {
+            drop(g);
+            bitacora::con_request_id(request_id, || revalidacion::recalcular(key));
+            let Ok(g2) = ESTADO.lock() else { return };
+            g = g2;
+            continue;
+        }
+        let ahora = Instant::now();
+        let espera = g.programados.iter().filter_map(|p| p.falta(ahora)).min()
+            .unwrap_or(ESPERA_MAXIMA)
+            .min(ESPERA_MAXIMA);
+        let Ok((g2, _)) = AVISO.wait_timeout(g, espera) else { return };
+        g = g2;
+    }
+}
+
+fn arrancar() -> Result<(), String> {
+    if ARRANCADO.swap(true, Ordering::SeqCst) { return Ok(()); }
+    std::thread::Builder::new()
+        .name("plaza-refresco".into())
+        .spawn(hilo)
+        .map(drop)
+        .map_err(|e| {
+            ARRANCADO.store(false, Ordering::SeqCst);
+            format!("thread: {e}")
+        })
+}
+
+/// Tras una carga que terminó bien: todo par programado queda pendiente.
+pub(crate) fn tras_carga() {
+    let Ok(mut g) = ESTADO.lock() else { return };
+    if g.programados.is_empty() { return; }
+    g.programados.iter_mut().for_each(|p| p.ultimo = None);
+    AVISO.notify_all();
+}
+
+/// Encola el recálculo de `key` (revalidacion.rs ya descartó repetidos).
+pub(crate) fn revalidar(key: ResultKey, request_id: Option<String>) -> Result<(), String> {
+    ESTADO.lock()
+        .map_err(|_| "Mutex: ESTADO envenenado".to_string())?
+        .revalidar.push_back((key, request_id));
+    arrancar()?;
+    AVISO.notify_all();
+    Ok(())
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Programa el refresco de cada (key1, key2) de `pares` (claves o alias)
+/// cada `intervalo_s` segundos (0 = solo tras cargas) y tras cada carga.
+/// Un par ya programado con el mismo filtro cambia de intervalo. Devuelve
+/// cuántos pares hay programados.
+#[pyfunction]
+#[pyo3(signature = (pares, intervalo_s, filtro_situacion = -1))]
+pub(crate) fn programar_refresco(
+    pares:            Vec<(ArgClave, ArgClave)>,
+    intervalo_s:      u64,
+    filtro_situacion: i64,
+) -> PyResult<usize> {
+    let mut g = ESTADO.lock().map_err(|_| PyRuntimeError::new_err("Mutex: ESTADO envenenado"))?;
+    for (key1, key2) in pares {
+        match g.programados.iter_mut().find(|p| mismo_par(p, &key1, &key2, filtro_situacion)) {
+            Some(p) => p.intervalo_s = intervalo_s,
+            None => g.programados.push(Programado {
+                key1, key2, filtro: filtro_situacion, intervalo_s,
+                ultimo: None, refrescos: 0, error: None,
+            }),
+        }
+    }
+    let n = g.programados.len();
+    drop(g);
+    arrancar().map_err(PyRuntimeError::new_err)?;
+    AVISO.notify_all();
+    Ok(n)
+}
+
+/// Deja de refrescar los `pares` con `filtro_situacion` (None = todos).
+/// Devuelve cuántos se quitaron.
+#[pyfunction]
+#[pyo3(signature = (pares = None, filtro_situacion = -1))]
+pub(crate) fn quitar_refresco(pares: Option<Vec<(ArgClave, ArgClave)>>, filtro_situacion: i64) -> PyResult<usize> {
+    let mut g = ESTADO.lock().map_err(|_| PyRuntimeError::new_err("Mutex: ESTADO envenenado"))?;
+    let antes = g.programados.len();
+    match pares {
+        None => g.programados.clear(),
+        Some(pares) => g.programados.retain(|p| !pares.iter().any(|(k1, k2)| mismo_par(p, k1, k2, filtro_situacion))),
+    }
+    Ok(antes - g.programados.len())
+}
+
+/// [{"key1", "key2", "filtro", "intervalo_s", "refrescos", "hace_s",
+/// "error"}]: hace_s = segundos desde el último refresco (None = pendiente).
+#[pyfunction]
+pub(crate) fn refrescos_programados(py: Python<'_>) -> PyResult<Vec<Bound<'_, PyDict>>> {
+    let g = ESTADO.lock().map_err(|_| PyRuntimeError::new_err("Mutex: ESTADO envenenado"))?;
+    g.programados.iter().map(|p| {
+        let d = PyDict::new(py);
+        d.set_item("key1", describir(&p.key1))?;
+        d.set_item("key2", describir(&p.key2))?;
+        d.set_item("filtro", p.filtro)?;
+        d.set_item("intervalo_s", p.intervalo_s)?;
+        d.set_item("refrescos", p.refrescos)?;
+        d.set_item("hace_s", p.ultimo.map(|t| t.elapsed().as_secs()))?;
+        d.set_item("error", p.error.as_deref())?;
+        Ok(d)
+    }).collect()
+}
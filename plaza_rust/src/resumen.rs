@@ -0,0 +1,125 @@
+// ==============================================================================
+// plaza_rust/src/resumen.rs
+//
+// Resumen de un periodo cargado en una sola llamada, para la validación de
+// ingesta: filas, min/max/media/suma y nulos por métrica, estados y
+// situaciones distintos, bbox de coordenadas y cuándo se cargó. Valores
+// crudos, sin política de negativos (se quiere ver lo que trae el archivo).
+// ==============================================================================
+
+use std::collections::BTreeSet;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::alias::ArgClave;
+use crate::columna::{ColF, ColI};
+use crate::{clave, periodo, EngineData, METRICAS};
+
+#[derive(Default)]
+struct Estadistica {
+    nulos: usize,
+    min:   Option<i64>,
+    max:   Option<i64>,
+    // i128: la suma de una columna i64 completa no desborda
+    suma:  i128,
+}
+
+impl Estadistica {
+    fn de(col: impl Iterator<Item = i64>) -> Self {
+        col.fold(Estadistica::default(), |mut e, x| {
+            if x == i64::MIN {
+                e.nulos += 1;
+            } else {
+                e.min = Some(e.min.map_or(x, |m| m.min(x)));
+                e.max = Some(e.max.map_or(x, |m| m.max(x)));
+                e.suma += x as i128;
+            }
+            e
+        })
+    }
+}
+
+struct Resumen {
+    filas:       usize,
+    metricas:    Vec<Estadistica>,
+    nulos:       [(&'static str, usize); 4],
+    estados:     BTreeSet<i64>,
+    situaciones: BTreeSet<i64>,
+    // (lat_min, lng_min, lat_max, lng_max); None sin coordenadas válidas
+    bbox:        Option<(f64, f64, f64, f64)>,
+    cargado_at:  u64,
+}
+
+fn resumir(eng: &EngineData) -> Resumen {
+    let filas = 0..eng.n;
+    let metricas = eng.metricas().iter()
+        .map(|c| Estadistica::de(filas.clone().map(|i| c.get(i))))
+        .collect();
+    let nulos_f = |c: &ColF| filas.clone().filter(|&i| c.get(i).is_nan()).count();
+    let nulos_i = |c: &ColI| filas.clone().filter(|&i| c.get(i) == i64::MIN).count();
+    let distintos = |c: &ColI| filas.clone().map(|i| c.get(i)).filter(|&x| x != i64::MIN).collect();
+    let bbox = filas.clone()
+        .map(|i| (eng.lats.get(i), eng.lngs.get(i)))
+        .filter(|(la, lo)| la.is_finite() && lo.is_finite())
+        .fold(None, |b: Option<(f64, f64, f64, f64)>, (la, lo)| Some(match b {
+            None => (la, lo, la, lo),
+            Some((a, b, c, d)) => (a.min(la), b.min(lo), c.max(la), d.max(lo)),
+        }));
+    Resumen {
+        filas: eng.n,
+        metricas,
+        nulos: [
+            ("lat", nulos_f(&eng.lats)),
+            ("lng", nulos_f(&eng.lngs)),
+            ("estado_id", nulos_i(&eng.estado_ids)),
+            ("situacion", nulos_i(&eng.situaciones)),
+        ],
+        estados: distintos(&eng.estado_ids),
+        situaciones: distintos(&eng.situaciones).into_iter()
+            .chain(eng.otras_situaciones.iter().map(|o| o.1))
+            .collect(),
+        bbox,
+        cargado_at: eng.cargado_at,
+    }
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"filas", "cargado_at", "metricas": {nombre: {"min", "max", "media",
+/// "suma", "nulos"}}, "nulos": {"lat", "lng", "estado_id", "situacion"},
+/// "estados": [...], "situaciones": [...], "bbox": (lat_min, lng_min,
+/// lat_max, lng_max) | None}. min/max/media son None si la columna es toda
+/// nula.
+#[pyfunction]
+pub(crate) fn resumen_periodo(py: Python<'_>, periodo_key: ArgClave) -> PyResult<Bound<'_, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    let r = py.allow_threads(|| periodo(periodo_key).map(|e| resumir(&e)))?;
+
+    let metricas = PyDict::new(py);
+    for (nombre, e) in METRICAS[1..].iter().zip(&r.metricas) {
+        let validos = r.filas - e.nulos;
+        let d = PyDict::new(py);
+        d.set_item("min", e.min)?;
+        d.set_item("max", e.max)?;
+        d.set_item("media", (validos > 0).then(|| e.suma as f64 / validos as f64))?;
+        d.set_item("suma", e.suma)?;
+        d.set_item("nulos", e.nulos)?;
+        metricas.set_item(*nombre, d)?;
+    }
+    let nulos = PyDict::new(py);
+    for (nombre, n) in r.nulos {
+        nulos.set_item(nombre, n)?;
+    }
+    let out = PyDict::new(py);
+    out.set_item("filas", r.filas)?;
+    out.set_item("cargado_at", r.cargado_at)?;
+    out.set_item("metricas", metricas)?;
+    out.set_item("nulos", nulos)?;
+    out.set_item("estados", r.estados.into_iter().collect::<Vec<_>>())?;
+    out.set_item("situaciones", r.situaciones.into_iter().collect::<Vec<_>>())?;
+    out.set_item("bbox", r.bbox)?;
+    Ok(out)
+}
@@ -0,0 +1,97 @@
+// ==============================================================================
+// plaza_rust/src/revalidacion.rs
+//
+// Vencimiento de RESULT_CACHE y stale-while-revalidate:
+//
+//   [cache]
+//   ttl_resultados_s   = 600
+//   revalidar_en_fondo = true
+//
+// Un resultado calculado hace ttl_resultados_s o más está vencido. Sin
+// revalidar_en_fondo el primer pedido que lo encuentra lo recalcula en
+// línea, como un miss. Con revalidar_en_fondo se sirve la entrada vencida
+// de inmediato y, ya soltado el lock de RESULT_CACHE, su recálculo se
+// encola (una vez por clave) en el hilo de mantenimiento de refresco.rs,
+// que los corre de a uno con prioridad baja (prioridad.rs). La cola está
+// acotada a MAX_EN_CURSO claves: si un cache tibio vence entero de golpe no
+// se dispara un hilo por clave, y lo que no entra se encola en el próximo
+// pedido. Si el recálculo falla la entrada vencida sigue ahí y el próximo
+// pedido lo vuelve a encolar.
+// ==============================================================================
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use rustc_hash::FxHashSet;
+
+use crate::prioridad::{self, Prioridad};
+use crate::{bitacora, config, refresco, ResultKey};
+
+const MAX_EN_CURSO: usize = 256;
+
+// Claves encoladas o recalculándose
+static EN_CURSO: Mutex<Option<FxHashSet<ResultKey>>> = Mutex::new(None);
+static SERVIDOS_VENCIDOS: AtomicU64 = AtomicU64::new(0);
+static REVALIDADOS: AtomicU64 = AtomicU64::new(0);
+static FALLIDOS: AtomicU64 = AtomicU64::new(0);
+
+/// `true` si un resultado calculado en `calculado_at` ya venció en `ahora`.
+pub(crate) fn vencido(calculado_at: u64, ahora: u64) -> bool {
+    let ttl = config::ttl_resultados_s();
+    ttl > 0 && ahora.saturating_sub(calculado_at) >= ttl
+}
+
+/// Para un resultado vencido, bajo el lock de RESULT_CACHE: `true` = se
+/// sirve igual (revalidar_en_fondo) y quien llama pide en_fondo() después
+/// de soltar el lock; `false` = recalcular en línea.
+pub(crate) fn servir_vencido() -> bool {
+    let si = config::revalidar_en_fondo();
+    if si { SERVIDOS_VENCIDOS.fetch_add(1, Ordering::Relaxed); }
+    si
+}
+
+fn soltar(key: ResultKey) {
+    if let Ok(mut g) = EN_CURSO.lock() {
+        if let Some(s) = g.as_mut() { s.remove(&key); }
+    }
+}
+
+/// Encola el recálculo de `key` en el hilo de mantenimiento si no está ya
+/// (ni la cola llena). Se llama sin locks de cache; el recálculo lleva el
+/// request_id del pedido que lo disparó.
+pub(crate) fn en_fondo(key: ResultKey) {
+    let Ok(mut g) = EN_CURSO.lock() else { return };
+    let s = g.get_or_insert_with(FxHashSet::default);
+    if s.len() >= MAX_EN_CURSO || !s.insert(key) { return; }
+    drop(g);
+    if let Err(e) = refresco::revalidar(key, bitacora::actual()) {
+        soltar(key);
+        bitacora::anotar("revalidacion", format!("clave={key:?} error={e}"));
+    }
+}
+
+/// Recalcula `key` (lo llama el hilo de mantenimiento).
+pub(crate) fn recalcular(key: ResultKey) {
+    let (k1, k2, filtro) = key;
+    match prioridad::ejecutar(Prioridad::Baja, || crate::calcular(k1, k2, filtro)) {
+        Ok(_)  => { REVALIDADOS.fetch_add(1, Ordering::Relaxed); }
+        Err(e) => {
+            FALLIDOS.fetch_add(1, Ordering::Relaxed);
+            bitacora::anotar("revalidacion", format!("clave=({k1}, {k2}, {filtro}) error={e}"));
+        }
+    }
+    soltar(key);
+}
+
+/// Contadores para recursos(): (nombre, valor).
+pub(crate) fn estadisticas() -> [(&'static str, u64); 4] {
+    let en_curso = EN_CURSO.lock().ok()
+        .and_then(|g| g.as_ref().map(|s| s.len() as u64))
+        .unwrap_or(0);
+    [
+        ("revalidaciones_en_curso",       en_curso),
+        ("resultados_servidos_vencidos", SERVIDOS_VENCIDOS.load(Ordering::Relaxed)),
+        ("revalidaciones",               REVALIDADOS.load(Ordering::Relaxed)),
+        ("revalidaciones_fallidas",      FALLIDOS.load(Ordering::Relaxed)),
+    ]
+}
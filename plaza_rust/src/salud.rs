@@ -0,0 +1,124 @@
+// ==============================================================================
+// plaza_rust/src/salud.rs
+//
+// Autodiagnóstico para readiness probes. healthcheck() corre cuatro chequeos:
+//
+//   locks     ENGINE_PERIODOS / RESULT_CACHE / ENGINE adquiribles en lectura
+//             antes de timeout_lock_ms; si no, qué escritor los retiene
+//   pool      el pool de Rayon ejecuta un join trivial antes del timeout
+//   memoria   RSS del proceso (o RAM estimada de datos) bajo memoria_max_mb
+//   errores   errores en los últimos ventana_errores_s <= errores_max
+//
+// Umbrales en la sección [salud] de cargar_configuracion().
+// ==============================================================================
+
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::{config, metricas};
+
+pub(crate) struct Chequeo {
+    pub nombre:  &'static str,
+    pub ok:      bool,
+    pub detalle: String,
+    pub ms:      f64,
+}
+
+fn chequeo_locks(timeout: Duration) -> (bool, String) {
+    let locks: [(&str, Result<(), String>); 3] = [
+        ("ENGINE_PERIODOS", crate::ENGINE_PERIODOS.probar(timeout)),
+        ("RESULT_CACHE",    crate::RESULT_CACHE.probar(timeout)),
+        ("ENGINE",          crate::ENGINE.probar(timeout)),
+    ];
+    // El error ya nombra el lock y quién lo tiene
+    let fallas: Vec<String> = locks.iter()
+        .filter_map(|(_, r)| r.as_ref().err().cloned())
+        .collect();
+    if fallas.is_empty() { (true, "ok".into()) } else { (false, fallas.join("; ")) }
+}
+
+fn chequeo_pool(timeout: Duration) -> (bool, String) {
+    let (tx, rx) = mpsc::channel();
+    config::spawn_en_pool(move || {
+        let (a, b) = rayon::join(|| 1u8, || 1u8);
+        let _ = tx.send((a + b, rayon::current_num_threads()));
+    });
+    match rx.recv_timeout(timeout) {
+        Ok((2, hilos)) => (true, format!("ok ({hilos} hilos)")),
+        Ok(_) => (false, "resultado inesperado".into()),
+        Err(_) => (false, format!("sin respuesta en {} ms", timeout.as_millis())),
+    }
+}
+
+/// VmRSS de /proc/self/status en KB (solo Linux).
+fn rss_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status.lines()
+        .find_map(|l| l.strip_prefix("VmRSS:"))
+        .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+fn chequeo_memoria(max_mb: u64) -> (bool, String) {
+    let (kb, fuente) = match rss_kb() {
+        Some(kb) => (kb, "rss"),
+        None => (crate::recursos().get("ram_datos_kb").copied().unwrap_or(0), "datos"),
+    };
+    let mb = kb / 1024;
+    if max_mb == 0 {
+        (true, format!("{fuente}={mb} MB (sin límite)"))
+    } else {
+        (mb <= max_mb, format!("{fuente}={mb} MB / límite {max_mb} MB"))
+    }
+}
+
+fn chequeo_errores(max: u64, ventana_s: u64) -> (bool, String) {
+    let n = metricas::errores_recientes(ventana_s);
+    (n <= max, format!("{n} errores en {ventana_s} s (máx {max})"))
+}
+
+/// Corre todos los chequeos; `timeout_ms` sobreescribe salud.timeout_lock_ms.
+pub(crate) fn evaluar(timeout_ms: Option<u64>) -> Vec<Chequeo> {
+    let cfg = config::actual();
+    let s = &cfg.salud;
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(s.timeout_lock_ms));
+
+    let medir = |nombre, f: &dyn Fn() -> (bool, String)| {
+        let t0 = Instant::now();
+        let (ok, detalle) = f();
+        Chequeo { nombre, ok, detalle, ms: t0.elapsed().as_secs_f64() * 1000.0 }
+    };
+    vec![
+        medir("locks",   &|| chequeo_locks(timeout)),
+        medir("pool",    &|| chequeo_pool(timeout)),
+        medir("memoria", &|| chequeo_memoria(s.memoria_max_mb)),
+        medir("errores", &|| chequeo_errores(s.errores_max, s.ventana_errores_s)),
+    ]
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"ok": bool, "timestamp": s, "checks": {nombre: {"ok", "detalle", "ms"}}}
+#[pyfunction]
+#[pyo3(signature = (timeout_ms = None))]
+pub(crate) fn healthcheck<'py>(py: Python<'py>, timeout_ms: Option<u64>) -> PyResult<Bound<'py, PyDict>> {
+    let chequeos = py.allow_threads(|| evaluar(timeout_ms));
+
+    let checks = PyDict::new(py);
+    for c in &chequeos {
+        let d = PyDict::new(py);
+        d.set_item("ok", c.ok)?;
+        d.set_item("detalle", &c.detalle)?;
+        d.set_item("ms", c.ms)?;
+        checks.set_item(c.nombre, d)?;
+    }
+    let out = PyDict::new(py);
+    out.set_item("ok", chequeos.iter().all(|c| c.ok))?;
+    out.set_item("timestamp", crate::now_secs())?;
+    out.set_item("checks", checks)?;
+    Ok(out)
+}
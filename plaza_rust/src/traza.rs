@@ -0,0 +1,309 @@
+// ==============================================================================
+// plaza_rust/src/traza.rs
+//
+// Grabación y reproducción de la secuencia de cargas y comparaciones, para
+// tener un banco de regresión de rendimiento con tráfico real:
+//
+//   grabar_traza(path)     desde aquí toda carga / comparación (Python o
+//                          HTTP) deja una línea en `path`
+//   detener_traza()        cierra el archivo
+//   reproducir_traza(path) repite las llamadas en el mismo orden, una tras
+//                          otra, y compara tiempos con los originales
+//
+// Formato: texto, una llamada por línea y campos separados por tabulador:
+//
+//   <t_ms> carga       <periodo_key> <archivo> <duracion_us> <ok> <opciones|->
+//   <t_ms> comparacion <key1> <key2> <filtro> <estados|-> <duracion_us> <ok>
+//
+// t_ms es relativo al inicio de la grabación. Los parquet cargados se
+// guardan tal cual llegaron en `<path>.datos/<huella>.parquet`, una vez por
+// contenido distinto. Si escribir falla se pierde la línea, nunca la
+// operación (como en la bitácora).
+//
+// <opciones> son las de la carga (tolerancia, aliases, metadatos, version)
+// en TOML, en una sola línea con %, tabulador y saltos escritos %25, %09,
+// %0A y %0D; "-" = las de por omisión. La reproducción las reconstruye, así
+// una carga tolerante o preliminar se repite igual. Una línea de carga de
+// la versión 1 (sin opciones) se repite como carga estricta definitiva.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::{Deserialize, Serialize};
+
+use crate::versiones::Version;
+use crate::{comparar_con_meta, OpcionesCarga, PeriodoKey};
+
+const CABECERA: &str = "# plaza_rust traza 2";
+
+struct Grabacion {
+    archivo: BufWriter<File>,
+    datos:   PathBuf,
+    t0:      Instant,
+    eventos: usize,
+}
+
+static GRABACION: Mutex<Option<Grabacion>> = Mutex::new(None);
+// Atajo sin lock para el caso normal (no se está grabando)
+static ACTIVA: AtomicBool = AtomicBool::new(false);
+
+fn datos_de(path: &Path) -> PathBuf {
+    let mut d = path.as_os_str().to_owned();
+    d.push(".datos");
+    PathBuf::from(d)
+}
+
+fn linea(campos: impl FnOnce(u128) -> String) {
+    let Ok(mut g) = GRABACION.lock() else { return };
+    let Some(gr) = g.as_mut() else { return };
+    let texto = campos(gr.t0.elapsed().as_millis());
+    if writeln!(gr.archivo, "{texto}").is_ok() { gr.eventos += 1; }
+}
+
+/// Lo de OpcionesCarga que cambia el resultado, como se graba.
+#[derive(Serialize, Deserialize)]
+struct Opciones {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tolerancia: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aliases:    Option<BTreeMap<String, Vec<String>>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadatos:  BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    version:    String,
+}
+
+fn escapar(s: &str) -> String {
+    s.replace('%', "%25").replace('\t', "%09").replace('\n', "%0A").replace('\r', "%0D")
+}
+
+fn desescapar(s: &str) -> String {
+    // %25 al final: un "%0A" literal llega como "%250A"
+    s.replace("%0D", "\r").replace("%0A", "\n").replace("%09", "\t").replace("%25", "%")
+}
+
+/// Campo <opciones> de la línea de carga; None si no se está grabando
+/// (se llama antes de la carga, que consume `opciones`).
+pub(crate) fn opciones(opciones: &OpcionesCarga) -> Option<String> {
+    if !ACTIVA.load(Ordering::Relaxed) { return None; }
+    let o = Opciones {
+        tolerancia: opciones.tolerancia,
+        aliases:    opciones.aliases.as_ref().map(|a| a.iter().map(|(k, v)| (k.clone(), v.clone())).collect()),
+        metadatos:  opciones.metadatos.clone(),
+        version:    match opciones.version {
+            Version::Definitivo => String::new(),
+            v                   => v.nombre().to_string(),
+        },
+    };
+    let texto = toml::to_string(&o).ok()?;
+    Some(if texto.trim().is_empty() { "-".to_string() } else { escapar(&texto) })
+}
+
+fn opciones_de(campo: &str) -> Result<OpcionesCarga, String> {
+    // Siempre se parsea: se mide la carga, no el atajo de contenido repetido
+    let base = OpcionesCarga { forzar: true, ..Default::default() };
+    if campo == "-" { return Ok(base); }
+    let o: Opciones = toml::from_str(&desescapar(campo)).map_err(|e| format!("opciones: {e}"))?;
+    Ok(OpcionesCarga {
+        tolerancia: o.tolerancia,
+        aliases:    o.aliases.map(|a| a.into_iter().collect()),
+        metadatos:  o.metadatos,
+        version:    if o.version.is_empty() { Version::Definitivo } else { Version::parse(&o.version)? },
+        ..base
+    })
+}
+
+/// Anota una carga de `raw` (tal como llegó, antes de descomprimir) con
+/// `opciones` de opciones().
+pub(crate) fn carga(periodo_key: PeriodoKey, raw: &[u8], opciones: Option<String>, duracion: Duration, ok: bool) {
+    let Some(opciones) = opciones else { return };
+    if !ACTIVA.load(Ordering::Relaxed) { return; }
+    let mut h = DefaultHasher::new();
+    h.write(raw);
+    let archivo = format!("{:016x}.parquet", h.finish());
+    // El payload se escribe fuera del lock; mismo contenido, mismo archivo
+    let Some(datos) = GRABACION.lock().ok().and_then(|g| g.as_ref().map(|gr| gr.datos.clone())) else { return };
+    let destino = datos.join(&archivo);
+    if !destino.exists() && std::fs::write(&destino, raw).is_err() { return; }
+    linea(|t| format!(
+        "{t}\tcarga\t{periodo_key}\t{archivo}\t{}\t{}\t{opciones}", duracion.as_micros(), ok as u8
+    ));
+}
+
+/// Anota una comparación (con `estados` si fue restringida).
+pub(crate) fn comparacion(key1: PeriodoKey, key2: PeriodoKey, filtro: i64, estados: Option<&[i64]>, duracion: Duration, ok: bool) {
+    if !ACTIVA.load(Ordering::Relaxed) { return; }
+    let estados = estados.map_or_else(|| "-".to_string(), |es| {
+        es.iter().map(i64::to_string).collect::<Vec<_>>().join(",")
+    });
+    linea(|t| format!(
+        "{t}\tcomparacion\t{key1}\t{key2}\t{filtro}\t{estados}\t{}\t{}", duracion.as_micros(), ok as u8
+    ));
+}
+
+enum Llamada {
+    Carga { periodo_key: PeriodoKey, archivo: PathBuf, opciones: String },
+    Comparacion { key1: PeriodoKey, key2: PeriodoKey, filtro: i64, estados: Option<Vec<i64>> },
+}
+
+impl Llamada {
+    fn operacion(&self) -> &'static str {
+        match self {
+            Llamada::Carga { .. }       => "carga",
+            Llamada::Comparacion { .. } => "comparacion",
+        }
+    }
+}
+
+/// (llamada, duración original en µs) por línea de la traza.
+fn leer(path: &Path) -> Result<Vec<(Llamada, u64)>, String> {
+    let f = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let datos = datos_de(path);
+    let mut out = Vec::new();
+    for (n, l) in BufReader::new(f).lines().enumerate() {
+        let l = l.map_err(|e| format!("{}: {e}", path.display()))?;
+        if l.is_empty() || l.starts_with('#') { continue; }
+        let invalida = || format!("{}:{}: línea inválida: {l:?}", path.display(), n + 1);
+        let c: Vec<&str> = l.split('\t').collect();
+        let num = |i: usize| c.get(i).and_then(|x| x.parse::<i64>().ok()).ok_or_else(invalida);
+        let clave = |i: usize| c.get(i).and_then(|x| x.parse::<PeriodoKey>().ok()).ok_or_else(invalida);
+        let llamada = match c.get(1).copied() {
+            // Versión 1: sin opciones
+            Some("carga") if c.len() == 6 || c.len() == 7 => (
+                Llamada::Carga {
+                    periodo_key: clave(2)?,
+                    archivo:     datos.join(c[3]),
+                    opciones:    c.get(6).copied().unwrap_or("-").to_string(),
+                },
+                num(4)? as u64,
+            ),
+            Some("comparacion") if c.len() == 8 => {
+                let estados = match c[5] {
+                    "-" => None,
+                    es  => Some(es.split(',').map(|e| e.parse().map_err(|_| invalida())).collect::<Result<_, _>>()?),
+                };
+                (Llamada::Comparacion { key1: clave(2)?, key2: clave(3)?, filtro: num(4)?, estados }, num(6)? as u64)
+            }
+            _ => return Err(invalida()),
+        };
+        out.push(llamada);
+    }
+    Ok(out)
+}
+
+/// Ejecuta una llamada; Err con el mensaje si falló.
+fn ejecutar(llamada: &Llamada) -> Result<Duration, (Duration, String)> {
+    match llamada {
+        Llamada::Carga { periodo_key, archivo, opciones } => {
+            let raw = std::fs::read(archivo)
+                .map_err(|e| (Duration::ZERO, format!("{}: {e}", archivo.display())))?;
+            let opciones = opciones_de(opciones).map_err(|e| (Duration::ZERO, e))?;
+            let t0 = Instant::now();
+            crate::cargar_periodo_con(Bytes::from(raw), *periodo_key, opciones)
+                .map(|_| t0.elapsed())
+                .map_err(|e| (t0.elapsed(), e.to_string()))
+        }
+        Llamada::Comparacion { key1, key2, filtro, estados } => {
+            let t0 = Instant::now();
+            comparar_con_meta(*key1, *key2, *filtro, estados.as_deref())
+                .map(|_| t0.elapsed())
+                .map_err(|e| (t0.elapsed(), e.to_string()))
+        }
+    }
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Empieza a grabar en `path` (lo trunca) y crea `<path>.datos/`.
+#[pyfunction]
+pub(crate) fn grabar_traza(path: &str) -> PyResult<()> {
+    let mut g = GRABACION.lock().map_err(|_| PyRuntimeError::new_err("Mutex: GRABACION envenenado"))?;
+    if g.is_some() {
+        return Err(PyRuntimeError::new_err("Ya hay una traza grabándose; detener_traza() primero"));
+    }
+    let datos = datos_de(Path::new(path));
+    let os = |e: std::io::Error| PyOSError::new_err(format!("{path}: {e}"));
+    std::fs::create_dir_all(&datos).map_err(os)?;
+    let mut archivo = BufWriter::new(File::create(path).map_err(os)?);
+    writeln!(archivo, "{CABECERA}").map_err(os)?;
+    *g = Some(Grabacion { archivo, datos, t0: Instant::now(), eventos: 0 });
+    ACTIVA.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Deja de grabar y devuelve cuántas llamadas quedaron en la traza (None si
+/// no se estaba grabando).
+#[pyfunction]
+pub(crate) fn detener_traza() -> PyResult<Option<usize>> {
+    let mut g = GRABACION.lock().map_err(|_| PyRuntimeError::new_err("Mutex: GRABACION envenenado"))?;
+    ACTIVA.store(false, Ordering::Relaxed);
+    let Some(mut gr) = g.take() else { return Ok(None) };
+    gr.archivo.flush().map_err(|e| PyOSError::new_err(format!("traza: {e}")))?;
+    Ok(Some(gr.eventos))
+}
+
+/// Repite la traza en orden contra el motor actual. {"eventos", "errores",
+/// "original_ms", "reproduccion_ms", "por_operacion": {op: {"n", "errores",
+/// "original_ms", "reproduccion_ms"}}, "detalle": [{"operacion",
+/// "original_us", "reproduccion_us", "error"}]}. Una llamada que falla se
+/// cuenta y se sigue con la siguiente.
+#[pyfunction]
+pub(crate) fn reproducir_traza<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyDict>> {
+    if ACTIVA.load(Ordering::Relaxed) {
+        return Err(PyRuntimeError::new_err("No se reproduce mientras se graba una traza"));
+    }
+    let llamadas = leer(Path::new(path)).map_err(PyValueError::new_err)?;
+    let resultados: Vec<(Duration, Option<String>)> = py.allow_threads(|| {
+        llamadas.iter().map(|(l, _)| match ejecutar(l) {
+            Ok(d)       => (d, None),
+            Err((d, e)) => (d, Some(e)),
+        }).collect()
+    });
+
+    let ms = |us: u64| us as f64 / 1_000.0;
+    // operación → (n, errores, original_us, reproduccion_us)
+    let mut por_op: BTreeMap<&str, (usize, usize, u64, u64)> = BTreeMap::new();
+    let mut detalle = Vec::with_capacity(llamadas.len());
+    for ((l, original_us), (d, error)) in llamadas.iter().zip(&resultados) {
+        let us = d.as_micros() as u64;
+        let e = por_op.entry(l.operacion()).or_default();
+        *e = (e.0 + 1, e.1 + error.is_some() as usize, e.2 + original_us, e.3 + us);
+        let f = PyDict::new(py);
+        f.set_item("operacion", l.operacion())?;
+        f.set_item("original_us", original_us)?;
+        f.set_item("reproduccion_us", us)?;
+        f.set_item("error", error.as_deref())?;
+        detalle.push(f);
+    }
+
+    let resumen = PyDict::new(py);
+    for (op, (n, errores, orig, rep)) in &por_op {
+        let d = PyDict::new(py);
+        d.set_item("n", n)?;
+        d.set_item("errores", errores)?;
+        d.set_item("original_ms", ms(*orig))?;
+        d.set_item("reproduccion_ms", ms(*rep))?;
+        resumen.set_item(op, d)?;
+    }
+    let out = PyDict::new(py);
+    out.set_item("eventos", llamadas.len())?;
+    out.set_item("errores", resultados.iter().filter(|r| r.1.is_some()).count())?;
+    out.set_item("original_ms", ms(llamadas.iter().map(|l| l.1).sum()))?;
+    out.set_item("reproduccion_ms", ms(resultados.iter().map(|r| r.0.as_micros() as u64).sum()))?;
+    out.set_item("por_operacion", resumen)?;
+    out.set_item("detalle", detalle)?;
+    Ok(out)
+}
@@ -0,0 +1,169 @@
+// ==============================================================================
+// plaza_rust/src/validacion.rs
+//
+// Validación opcional de agregados ([motor] validacion = "registrar" |
+// "error"): después de cada agregar() se recalcula el mismo agregado fila
+// por fila, sin kernels ni bitmaps, con checked_add y saltando los nulos, y
+// se compara posición por posición. Una diferencia es una violación:
+//
+//   desborde     el recálculo desborda i64 y la agregación no dio error
+//   sentinela    el estado tiene nulos en esa métrica y la suma no coincide
+//                (un i64::MIN, o el nulo de una columna compacta, se sumó)
+//   discrepancia cualquier otra diferencia
+//
+// Las violaciones se guardan (las últimas CAPACIDAD) y se leen con
+// violaciones_validacion(). Cuesta una pasada secuencial extra por
+// agregación: es para diagnosticar, no para producción.
+// ==============================================================================
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rustc_hash::FxHashMap;
+
+use crate::config::{self, Validacion};
+use crate::{now_secs, EngineData, Fila, Local, ANCHO, CLAVES, METRICAS};
+
+const CAPACIDAD: usize = 100;
+
+struct Violacion {
+    ts:        u64,
+    filas:     usize,
+    filtro:    i64,
+    estado_id: i64,
+    metrica:   &'static str,
+    tipo:      &'static str,
+    esperado:  Option<i64>,
+    obtenido:  Option<i64>,
+    nulos:     i64,
+}
+
+impl Violacion {
+    fn mensaje(&self) -> String {
+        format!(
+            "validación: {} en estado {} métrica {} (esperado {:?}, obtenido {:?}, filtro {})",
+            self.tipo, self.estado_id, self.metrica, self.esperado, self.obtenido, self.filtro,
+        )
+    }
+}
+
+static VIOLACIONES: Mutex<VecDeque<Violacion>> = Mutex::new(VecDeque::new());
+static REVISADAS: AtomicU64 = AtomicU64::new(0);
+static TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Recálculo de referencia: por estado, la fila del acumulador y qué
+/// posiciones desbordaron.
+fn recalcular(eng: &EngineData, filtro: i64, estados: Option<&[i64]>) -> FxHashMap<i64, (Fila, [bool; ANCHO])> {
+    let pols = config::negativos();
+    let metricas = eng.metricas();
+    let mut out: FxHashMap<i64, (Fila, [bool; ANCHO])> = FxHashMap::default();
+    for i in 0..eng.n {
+        let eid = eng.estado_ids.get(i);
+        if eid == i64::MIN || estados.is_some_and(|es| es.binary_search(&eid).is_err()) { continue; }
+        if filtro >= 0 && !eng.tiene_situacion(i, filtro) { continue; }
+        let (v, desbordes) = out.entry(eid).or_insert(([0; ANCHO], [false; ANCHO]));
+        v[0] += 1;
+        for (k, col) in metricas.iter().enumerate() {
+            let x = col.get(i);
+            if x == i64::MIN { continue; }
+            v[k + METRICAS.len()] += 1;
+            match v[k + 1].checked_add(pols[k].valor(x)) {
+                Some(s) => v[k + 1] = s,
+                None    => desbordes[k + 1] = true,
+            }
+        }
+    }
+    out
+}
+
+/// Compara `agr` contra el recálculo; en modo error falla con la primera
+/// violación. Devuelve `agr` sin tocar si no hay validación.
+pub(crate) fn revisar(eng: &EngineData, filtro: i64, estados: Option<&[i64]>, agr: Local) -> Result<Local, String> {
+    let modo = config::validacion();
+    if modo == Validacion::Apagada { return Ok(agr); }
+    REVISADAS.fetch_add(1, Ordering::Relaxed);
+
+    let referencia = recalcular(eng, filtro, estados);
+    let mut ids: Vec<i64> = referencia.keys().chain(agr.keys()).copied().collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let ts = now_secs();
+    let mut nuevas = Vec::new();
+    for eid in ids {
+        let (esperado, desbordes) = referencia.get(&eid).map_or((None, [false; ANCHO]), |(v, d)| (Some(v), *d));
+        let obtenido = agr.get(&eid);
+        for k in 0..ANCHO {
+            let (e, o) = (esperado.map(|v| v[k]), obtenido.map(|v| v[k]));
+            if !desbordes[k] && e == o { continue; }
+            // nulos de la métrica en las filas del estado
+            let nulos = match (k, esperado) {
+                (1..=6, Some(v)) => v[0] - v[k + METRICAS.len() - 1],
+                _ => 0,
+            };
+            let tipo = if desbordes[k] { "desborde" } else if nulos > 0 { "sentinela" } else { "discrepancia" };
+            nuevas.push(Violacion {
+                ts, filas: eng.n, filtro, estado_id: eid, metrica: CLAVES[k], tipo,
+                esperado: if desbordes[k] { None } else { e },
+                obtenido: o,
+                nulos,
+            });
+        }
+    }
+    if nuevas.is_empty() { return Ok(agr); }
+
+    TOTAL.fetch_add(nuevas.len() as u64, Ordering::Relaxed);
+    let error = nuevas[0].mensaje();
+    if let Ok(mut v) = VIOLACIONES.lock() {
+        for n in nuevas {
+            if v.len() >= CAPACIDAD { v.pop_front(); }
+            v.push_back(n);
+        }
+    }
+    match modo {
+        Validacion::Error => Err(error),
+        _                 => Ok(agr),
+    }
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// {"modo", "revisadas", "violaciones_total", "violaciones": [{"ts",
+/// "filas", "filtro", "estado_id", "metrica", "tipo", "esperado",
+/// "obtenido", "nulos"}]}, de la más vieja a la más nueva. Con `vaciar` se
+/// descartan las guardadas después de leerlas.
+#[pyfunction]
+#[pyo3(signature = (vaciar = false))]
+pub(crate) fn violaciones_validacion(py: Python<'_>, vaciar: bool) -> PyResult<Bound<'_, PyDict>> {
+    let mut v = VIOLACIONES.lock()
+        .map_err(|_| pyo3::exceptions::PyRuntimeError::new_err("Mutex: VIOLACIONES envenenado"))?;
+    let lista = v.iter().map(|x| {
+        let d = PyDict::new(py);
+        d.set_item("ts", x.ts)?;
+        d.set_item("filas", x.filas)?;
+        d.set_item("filtro", x.filtro)?;
+        d.set_item("estado_id", x.estado_id)?;
+        d.set_item("metrica", x.metrica)?;
+        d.set_item("tipo", x.tipo)?;
+        d.set_item("esperado", x.esperado)?;
+        d.set_item("obtenido", x.obtenido)?;
+        d.set_item("nulos", x.nulos)?;
+        Ok(d)
+    }).collect::<PyResult<Vec<_>>>()?;
+    if vaciar { v.clear(); }
+    let modo = match config::validacion() {
+        Validacion::Apagada   => "apagada",
+        Validacion::Registrar => "registrar",
+        Validacion::Error     => "error",
+    };
+    let out = PyDict::new(py);
+    out.set_item("modo", modo)?;
+    out.set_item("revisadas", REVISADAS.load(Ordering::Relaxed))?;
+    out.set_item("violaciones_total", TOTAL.load(Ordering::Relaxed))?;
+    out.set_item("violaciones", lista)?;
+    Ok(out)
+}
@@ -0,0 +1,302 @@
+// ==============================================================================
+// plaza_rust/src/versiones.rs
+//
+// Versión preliminar y definitiva de un mismo periodo_key. Una carga con
+// version="preliminar" no pisa la definitiva ya cargada:
+//
+//   - si hay definitiva, la preliminar queda en reserva (no se sirve);
+//   - si no, la preliminar se sirve como cualquier periodo (vigente).
+//
+// Una carga definitiva se sirve siempre; si la vigente era preliminar pasa a
+// reserva. promover_preliminar() reemplaza la definitiva por la preliminar y
+// descartar_preliminar() la quita; ambas invalidan los resultados de la
+// clave cuando cambia lo que se sirve.
+//
+// La reserva no cuenta para max_periodos ni para los desalojos por memoria.
+// Si la vigente preliminar se desaloja, la marca queda y se ignora.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::cerrojos::Cerrojo;
+use crate::errores::Error;
+use crate::{bitacora, clave, config, insertar_periodo, quitar_periodo, virtuales, ArgClave, EngineData, PeriodoKey, ENGINE_PERIODOS};
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub(crate) enum Version {
+    #[default]
+    Definitivo,
+    Preliminar,
+}
+
+impl Version {
+    pub(crate) fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "definitivo" | "definitiva" => Ok(Version::Definitivo),
+            "preliminar"                => Ok(Version::Preliminar),
+            otro => Err(format!("version '{otro}': se espera 'definitivo' o 'preliminar'")),
+        }
+    }
+
+    pub(crate) fn nombre(self) -> &'static str {
+        match self {
+            Version::Definitivo => "definitivo",
+            Version::Preliminar => "preliminar",
+        }
+    }
+}
+
+enum Preliminar {
+    // La que está en ENGINE_PERIODOS es la preliminar
+    Vigente,
+    // Hay definitiva en ENGINE_PERIODOS; la preliminar espera aquí
+    Reserva(Arc<EngineData>),
+}
+
+// Lock: PRELIMINARES antes que ENGINE_PERIODOS
+static PRELIMINARES: Cerrojo<BTreeMap<PeriodoKey, Preliminar>> = Cerrojo::new("PRELIMINARES", BTreeMap::new());
+
+fn cargado(key: PeriodoKey) -> Result<Option<Arc<EngineData>>, Error> {
+    Ok(ENGINE_PERIODOS.leer("versiones")?.as_ref().and_then(|m| m.get(&key).cloned()))
+}
+
+/// insertar_periodo() según la versión de la carga.
+pub(crate) fn insertar(key: PeriodoKey, eng: EngineData, cfg: &config::Config, version: Version) -> Result<(), Error> {
+    let mut pre = PRELIMINARES.escribir("insertar_version")?;
+    let vigente_preliminar = matches!(pre.get(&key), Some(Preliminar::Vigente));
+    match version {
+        Version::Definitivo => {
+            if vigente_preliminar {
+                match cargado(key)? {
+                    Some(e) => { pre.insert(key, Preliminar::Reserva(e)); }
+                    None    => { pre.remove(&key); }
+                }
+            }
+            insertar_periodo(key, Arc::new(eng), cfg)
+        }
+        Version::Preliminar if !vigente_preliminar && cargado(key)?.is_some() => {
+            pre.insert(key, Preliminar::Reserva(Arc::new(eng)));
+            Ok(())
+        }
+        Version::Preliminar => {
+            insertar_periodo(key, Arc::new(eng), cfg)?;
+            pre.insert(key, Preliminar::Vigente);
+            Ok(())
+        }
+    }
+}
+
+/// Versión que se sirve para `key` (None si no está cargado).
+pub(crate) fn vigente(key: PeriodoKey) -> Result<Option<Version>, Error> {
+    let pre = PRELIMINARES.leer("versiones")?;
+    if cargado(key)?.is_none() { return Ok(None) }
+    Ok(Some(match pre.get(&key) {
+        Some(Preliminar::Vigente) => Version::Preliminar,
+        _                         => Version::Definitivo,
+    }))
+}
+
+fn promover(key: PeriodoKey) -> Result<bool, Error> {
+    let mut pre = PRELIMINARES.escribir("promover_preliminar")?;
+    match pre.remove(&key) {
+        // Ya se servía: solo cambia la marca
+        Some(Preliminar::Vigente) => cargado(key).map(|e| e.is_some()),
+        Some(Preliminar::Reserva(e)) => {
+            insertar_periodo(key, e, &config::actual())?;
+            virtuales::invalidar_resultados(key)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn descartar(key: PeriodoKey) -> Result<bool, Error> {
+    let mut pre = PRELIMINARES.escribir("descartar_preliminar")?;
+    match pre.remove(&key) {
+        Some(Preliminar::Reserva(_)) => Ok(true),
+        Some(Preliminar::Vigente) => {
+            let quitado = quitar_periodo(key)?;
+            virtuales::invalidar_resultados(key)?;
+            Ok(quitado)
+        }
+        None => Ok(false),
+    }
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// La preliminar de `periodo_key` pasa a definitiva (reemplaza la que
+/// hubiera). False si no había preliminar.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, request_id = None))]
+pub(crate) fn promover_preliminar(periodo_key: ArgClave, request_id: Option<&str>) -> PyResult<bool> {
+    let periodo_key = clave(periodo_key)?;
+    Ok(bitacora::auditar("promover_preliminar", format!("periodo_key={periodo_key}"), request_id, || {
+        promover(periodo_key)
+    })?)
+}
+
+/// Quita la preliminar de `periodo_key`; la definitiva, si hay, no se toca.
+/// False si no había preliminar.
+#[pyfunction]
+#[pyo3(signature = (periodo_key, request_id = None))]
+pub(crate) fn descartar_preliminar(periodo_key: ArgClave, request_id: Option<&str>) -> PyResult<bool> {
+    let periodo_key = clave(periodo_key)?;
+    Ok(bitacora::auditar("descartar_preliminar", format!("periodo_key={periodo_key}"), request_id, || {
+        descartar(periodo_key)
+    })?)
+}
+
+/// {"vigente": "definitivo" | "preliminar" | None, "definitivo": bool,
+/// "preliminar": bool, "preliminar_filas": int | None}: qué versiones hay de
+/// `periodo_key` y cuál se sirve.
+#[pyfunction]
+pub(crate) fn versiones_periodo(py: Python<'_>, periodo_key: ArgClave) -> PyResult<Bound<'_, PyDict>> {
+    let periodo_key = clave(periodo_key)?;
+    let pre = PRELIMINARES.leer("versiones_periodo")?;
+    let servido = cargado(periodo_key)?;
+    let (vigente, definitivo, preliminar) = match (pre.get(&periodo_key), &servido) {
+        (Some(Preliminar::Vigente), Some(e)) => (Some(Version::Preliminar), false, Some(e.n)),
+        (Some(Preliminar::Reserva(p)), s)    => (s.as_ref().map(|_| Version::Definitivo), s.is_some(), Some(p.n)),
+        (_, Some(_))                         => (Some(Version::Definitivo), true, None),
+        (_, None)                            => (None, false, None),
+    };
+    let d = PyDict::new(py);
+    d.set_item("vigente", vigente.map(Version::nombre))?;
+    d.set_item("definitivo", definitivo)?;
+    d.set_item("preliminar", preliminar.is_some())?;
+    d.set_item("preliminar_filas", preliminar)?;
+    Ok(d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruebas::{self, FilaPrueba};
+    use crate::{cargar_periodo_con, comparar_con_meta, OpcionesCarga, RESULT_CACHE};
+
+    const DEFINITIVA: &[FilaPrueba] = &[(Some(9), 1, 10), (Some(15), 1, 5)];
+    const PRELIMINAR: &[FilaPrueba] = &[(Some(9), 1, 12)];
+    const OTRO: &[FilaPrueba] = &[(Some(9), 1, 1)];
+
+    fn cargar(key: PeriodoKey, filas: &[FilaPrueba], version: Version) {
+        let opciones = OpcionesCarga { version, forzar: true, ..Default::default() };
+        cargar_periodo_con(pruebas::parquet(filas), key, opciones).unwrap();
+    }
+
+    /// Compara `key` contra `otro` (queda en cache) y devuelve lo servido.
+    fn servido(key: PeriodoKey, otro: PeriodoKey) -> i64 {
+        let ((agr, ..), _) = comparar_con_meta(key, otro, -1, None).unwrap();
+        agr[&9][1]
+    }
+
+    fn en_cache(key1: PeriodoKey, key2: PeriodoKey) -> bool {
+        RESULT_CACHE.leer("test").unwrap().as_ref().is_some_and(|m| m.contains_key(&(key1, key2, -1)))
+    }
+
+    #[test]
+    fn promover_sin_preliminar_no_cambia_nada() {
+        let (key, otro) = (230_501, 230_502);
+        cargar(key, DEFINITIVA, Version::Definitivo);
+        cargar(otro, OTRO, Version::Definitivo);
+        let antes = cargado(key).unwrap().unwrap();
+        assert_eq!(servido(key, otro), 10);
+
+        assert!(!promover(key).unwrap());
+        assert!(Arc::ptr_eq(&antes, &cargado(key).unwrap().unwrap()));
+        assert!(en_cache(key, otro));
+        assert_eq!(vigente(key).unwrap(), Some(Version::Definitivo));
+        // Ni con la clave sin cargar
+        assert!(!promover(230_509).unwrap());
+    }
+
+    #[test]
+    fn promover_la_reserva_reemplaza_la_definitiva_e_invalida() {
+        let (key, otro) = (230_511, 230_512);
+        cargar(key, DEFINITIVA, Version::Definitivo);
+        cargar(otro, OTRO, Version::Definitivo);
+        assert_eq!(servido(key, otro), 10);
+
+        // La preliminar queda en reserva: se sigue sirviendo la definitiva
+        cargar(key, PRELIMINAR, Version::Preliminar);
+        assert_eq!(vigente(key).unwrap(), Some(Version::Definitivo));
+        assert!(en_cache(key, otro));
+        assert_eq!(servido(key, otro), 10);
+
+        assert!(promover(key).unwrap());
+        assert!(!en_cache(key, otro));
+        assert_eq!(vigente(key).unwrap(), Some(Version::Definitivo));
+        assert_eq!(servido(key, otro), 12);
+        assert!(!promover(key).unwrap());
+    }
+
+    #[test]
+    fn promover_la_vigente_solo_cambia_la_marca() {
+        let (key, otro) = (230_521, 230_522);
+        cargar(key, PRELIMINAR, Version::Preliminar);
+        cargar(otro, OTRO, Version::Definitivo);
+        assert_eq!(vigente(key).unwrap(), Some(Version::Preliminar));
+        let antes = cargado(key).unwrap().unwrap();
+        assert_eq!(servido(key, otro), 12);
+
+        assert!(promover(key).unwrap());
+        assert_eq!(vigente(key).unwrap(), Some(Version::Definitivo));
+        assert!(Arc::ptr_eq(&antes, &cargado(key).unwrap().unwrap()));
+        // Lo servido no cambió: el resultado sigue valiendo
+        assert!(en_cache(key, otro));
+    }
+
+    #[test]
+    fn descartar_la_reserva_mientras_se_sirve_la_definitiva() {
+        let (key, otro) = (230_531, 230_532);
+        cargar(key, DEFINITIVA, Version::Definitivo);
+        cargar(otro, OTRO, Version::Definitivo);
+        cargar(key, PRELIMINAR, Version::Preliminar);
+        let definitiva = cargado(key).unwrap().unwrap();
+        assert_eq!(servido(key, otro), 10);
+
+        assert!(descartar(key).unwrap());
+        assert!(Arc::ptr_eq(&definitiva, &cargado(key).unwrap().unwrap()));
+        assert!(en_cache(key, otro));
+        assert_eq!(vigente(key).unwrap(), Some(Version::Definitivo));
+        // Ya no hay preliminar que promover ni descartar
+        assert!(!promover(key).unwrap());
+        assert!(!descartar(key).unwrap());
+    }
+
+    #[test]
+    fn descartar_la_vigente_la_quita_e_invalida() {
+        let (key, otro) = (230_541, 230_542);
+        cargar(key, PRELIMINAR, Version::Preliminar);
+        cargar(otro, OTRO, Version::Definitivo);
+        assert_eq!(servido(key, otro), 12);
+
+        assert!(descartar(key).unwrap());
+        assert!(cargado(key).unwrap().is_none());
+        assert!(!en_cache(key, otro));
+        assert_eq!(vigente(key).unwrap(), None);
+        assert!(comparar_con_meta(key, otro, -1, None).is_err());
+    }
+
+    #[test]
+    fn una_definitiva_manda_la_vigente_preliminar_a_reserva() {
+        let (key, otro) = (230_551, 230_552);
+        cargar(key, PRELIMINAR, Version::Preliminar);
+        cargar(otro, OTRO, Version::Definitivo);
+        assert_eq!(servido(key, otro), 12);
+
+        cargar(key, DEFINITIVA, Version::Definitivo);
+        assert_eq!(vigente(key).unwrap(), Some(Version::Definitivo));
+        assert!(!en_cache(key, otro));
+        assert_eq!(servido(key, otro), 10);
+        // La preliminar vuelve con promover
+        assert!(promover(key).unwrap());
+        assert_eq!(servido(key, otro), 12);
+    }
+}
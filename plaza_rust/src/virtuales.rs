@@ -0,0 +1,238 @@
+// ==============================================================================
+// plaza_rust/src/virtuales.rs
+//
+// Periodos virtuales: una clave que representa la unión de varios periodos
+// cargados (un trimestre, un año) sin copiar sus filas. Solo se guarda la
+// lista de componentes; comparar() agrega cada componente y suma los
+// resultados, así las comparaciones trimestrales reutilizan las cargas
+// mensuales.
+//
+// Un virtual solo sirve para comparaciones (comparar_periodos, el reporte de
+// cambios, HTTP /comparar); las funciones que leen filas piden un periodo
+// físico. Sus componentes deben ser físicos y estar cargados al momento de
+// consultarlo; si uno se desaloja, la comparación falla diciendo cuál.
+// Recargar un componente invalida los resultados del virtual junto con los
+// suyos.
+//
+// La clave virtual no necesita ser año*100+mes aunque [cache]
+// claves_calendario esté activo (p. ej. 202400 para el año, 202491..202494
+// para los trimestres); una vez creada, toda entrada la acepta.
+// ==============================================================================
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rustc_hash::FxHashMap;
+
+use crate::alias::ArgClave;
+use crate::cerrojos::Cerrojo;
+use crate::errores::Error;
+use crate::{bitacora, clave, periodo, EngineData, PeriodoKey, ENGINE_PERIODOS, RESULT_CACHE};
+
+static VIRTUALES: Cerrojo<Option<FxHashMap<PeriodoKey, Vec<PeriodoKey>>>> = Cerrojo::new("VIRTUALES", None);
+
+/// Componentes de `key` si es virtual.
+pub(crate) fn componentes_de(key: PeriodoKey) -> Result<Option<Vec<PeriodoKey>>, Error> {
+    Ok(VIRTUALES.leer("componentes_de")?.as_ref().and_then(|m| m.get(&key).cloned()))
+}
+
+pub(crate) fn es_virtual(key: PeriodoKey) -> bool {
+    componentes_de(key).ok().flatten().is_some()
+}
+
+/// Datos a agregar para `key`: el periodo mismo o los componentes del virtual.
+pub(crate) fn lados(key: PeriodoKey) -> Result<Vec<Arc<EngineData>>, Error> {
+    let Some(comps) = componentes_de(key)? else { return Ok(vec![periodo(key)?]) };
+    comps.iter()
+        .map(|&k| periodo(k).map_err(|_| Error::Motor(format!("Periodo virtual {key}: componente {k} no cargado"))))
+        .collect()
+}
+
+/// Quita del cache los resultados que mencionan `key` o un virtual que lo
+/// tiene de componente: su definición o sus datos cambiaron.
+pub(crate) fn invalidar_resultados(key: PeriodoKey) -> Result<(), Error> {
+    let mut claves = vec![key];
+    if let Some(m) = VIRTUALES.leer("invalidar_resultados")?.as_ref() {
+        claves.extend(m.iter().filter(|(_, comps)| comps.contains(&key)).map(|(&v, _)| v));
+    }
+    if let Some(m) = RESULT_CACHE.escribir("invalidar_resultados")?.as_mut() {
+        m.retain(|&(k1, k2, _), _| !claves.contains(&k1) && !claves.contains(&k2));
+    }
+    Ok(())
+}
+
+fn crear(nueva: PeriodoKey, mut keys: Vec<PeriodoKey>) -> Result<usize, Error> {
+    keys.sort_unstable();
+    keys.dedup();
+    if keys.is_empty() {
+        return Err(Error::Motor("crear_periodo_virtual: keys vacío".into()));
+    }
+    if keys.contains(&nueva) {
+        return Err(Error::Motor(format!("Periodo virtual {nueva}: no puede ser su propio componente")));
+    }
+    let guard = ENGINE_PERIODOS.leer("crear_periodo_virtual")?;
+    let map = guard.as_ref();
+    if map.is_some_and(|m| m.contains_key(&nueva)) {
+        return Err(Error::Motor(format!("Periodo {nueva} ya está cargado como periodo físico")));
+    }
+    let mut filas = 0;
+    for k in &keys {
+        let e = map.and_then(|m| m.get(k))
+            .ok_or_else(|| Error::Motor(format!("Periodo virtual {nueva}: componente {k} no cargado")))?;
+        filas += e.n;
+    }
+    drop(guard);
+    VIRTUALES.escribir("crear_periodo_virtual")?
+        .get_or_insert_with(FxHashMap::default)
+        .insert(nueva, keys);
+    invalidar_resultados(nueva)?;
+    Ok(filas)
+}
+
+// ===========================================================================
+// FUNCIONES EXPORTADAS A PYTHON
+// ===========================================================================
+
+/// Define (o redefine) `nueva_key` como la unión de `keys`, que deben estar
+/// cargados. `nueva_key` puede ser cualquier clave que no sea un periodo
+/// físico cargado. Devuelve el total de filas que representa.
+#[pyfunction]
+#[pyo3(signature = (nueva_key, keys, request_id = None))]
+pub(crate) fn crear_periodo_virtual(
+    py:         Python<'_>,
+    nueva_key:  u32,
+    keys:       Vec<ArgClave>,
+    request_id: Option<&str>,
+) -> PyResult<usize> {
+    let keys = keys.into_iter().map(clave).collect::<PyResult<Vec<_>>>()?;
+    if let Some(&k) = keys.iter().find(|&&k| es_virtual(k)) {
+        return Err(PyValueError::new_err(format!("componente {k} es virtual; use sus periodos físicos")));
+    }
+    let params = format!("nueva_key={nueva_key} keys={keys:?}");
+    Ok(py.allow_threads(|| bitacora::auditar("crear_periodo_virtual", params, request_id, || {
+        crear(nueva_key, keys)
+    }))?)
+}
+
+#[pyfunction]
+pub(crate) fn eliminar_periodo_virtual(periodo_key: ArgClave) -> PyResult<bool> {
+    let key = clave(periodo_key)?;
+    let quitado = VIRTUALES.escribir("eliminar_periodo_virtual")?
+        .as_mut()
+        .is_some_and(|m| m.remove(&key).is_some());
+    if quitado { invalidar_resultados(key)?; }
+    Ok(quitado)
+}
+
+/// {clave virtual: [componentes]}.
+#[pyfunction]
+pub(crate) fn periodos_virtuales() -> PyResult<BTreeMap<PeriodoKey, Vec<PeriodoKey>>> {
+    let guard = VIRTUALES.leer("periodos_virtuales")?;
+    Ok(guard.as_ref().map(|m| m.iter().map(|(&k, v)| (k, v.clone())).collect()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruebas::{self, FilaPrueba};
+    use crate::{comparar_con_meta, quitar_periodo, ResultKey};
+
+    const ENERO: &[FilaPrueba] = &[(Some(9), 1, 10), (Some(9), 2, 4), (None, 1, 6), (Some(15), 1, -3)];
+    const FEBRERO: &[FilaPrueba] = &[(Some(9), 1, 7), (Some(32), 1, 5), (Some(15), 2, 2)];
+    const OTRO: &[FilaPrueba] = &[(Some(9), 1, 1), (Some(32), 1, 8)];
+
+    fn en_cache(key: ResultKey) -> bool {
+        RESULT_CACHE.leer("test").unwrap().as_ref().is_some_and(|m| m.contains_key(&key))
+    }
+
+    #[test]
+    fn comparar_un_virtual_es_comparar_sus_datos_unidos() {
+        let (ene, feb, unido, otro, virt) = (230_401, 230_402, 230_403, 230_404, 230_490);
+        pruebas::cargar(ene, ENERO);
+        pruebas::cargar(feb, FEBRERO);
+        pruebas::cargar(unido, &[ENERO, FEBRERO].concat());
+        pruebas::cargar(otro, OTRO);
+        assert_eq!(crear(virt, vec![feb, ene]).unwrap(), ENERO.len() + FEBRERO.len());
+
+        for filtro in [-1, 1, 2] {
+            assert_eq!(
+                comparar_con_meta(virt, otro, filtro, None).unwrap(),
+                comparar_con_meta(unido, otro, filtro, None).unwrap(),
+            );
+            assert_eq!(
+                comparar_con_meta(otro, virt, filtro, Some(&[15, 9])).unwrap(),
+                comparar_con_meta(otro, unido, filtro, Some(&[15, 9])).unwrap(),
+            );
+            assert_eq!(
+                comparar_con_meta(virt, virt, filtro, None).unwrap(),
+                comparar_con_meta(unido, unido, filtro, None).unwrap(),
+            );
+        }
+    }
+
+    #[test]
+    fn lados_y_crear_fallan_diciendo_por_que() {
+        let (a, b, virt) = (230_411, 230_412, 230_491);
+        let error = |r: Result<Vec<Arc<EngineData>>, Error>| r.err().map(|e| e.to_string()).unwrap_or_default();
+        assert!(error(lados(230_419)).contains("Periodo 230419 no cargado"));
+
+        pruebas::cargar(a, ENERO);
+        pruebas::cargar(b, FEBRERO);
+        crear(virt, vec![a, b]).unwrap();
+        assert_eq!(lados(virt).unwrap().len(), 2);
+        // Un virtual solo sirve para comparar; sus componentes deben seguir cargados
+        assert!(periodo(virt).err().unwrap().to_string().contains("es virtual"));
+        quitar_periodo(b).unwrap();
+        assert!(error(lados(virt)).contains(&format!("Periodo virtual {virt}: componente {b} no cargado")));
+        assert!(comparar_con_meta(virt, a, -1, None).is_err());
+
+        let falla = |nueva, keys: Vec<PeriodoKey>| crear(nueva, keys).err().unwrap().to_string();
+        assert!(falla(230_492, vec![]).contains("keys vacío"));
+        assert!(falla(230_492, vec![a, 230_492]).contains("su propio componente"));
+        assert!(falla(a, vec![virt]).contains("ya está cargado como periodo físico"));
+        assert!(falla(230_492, vec![a, b]).contains(&format!("componente {b} no cargado")));
+    }
+
+    #[test]
+    fn no_se_carga_sobre_una_clave_virtual() {
+        let (a, virt) = (230_421, 230_493);
+        pruebas::cargar(a, ENERO);
+        crear(virt, vec![a]).unwrap();
+        let opciones = crate::OpcionesCarga { forzar: true, ..Default::default() };
+        let e = crate::cargar_periodo_con(pruebas::parquet(FEBRERO), virt, opciones).err().unwrap();
+        assert!(e.to_string().contains("es virtual"));
+        assert_eq!(componentes_de(virt).unwrap(), Some(vec![a]));
+    }
+
+    #[test]
+    fn cambiar_un_componente_o_la_definicion_invalida_resultados() {
+        let (a, b, otro, virt) = (230_431, 230_432, 230_433, 230_494);
+        pruebas::cargar(a, ENERO);
+        pruebas::cargar(b, FEBRERO);
+        pruebas::cargar(otro, OTRO);
+        crear(virt, vec![a, b]).unwrap();
+
+        // Recargar un componente quita los resultados del virtual
+        let ((antes, ..), _) = comparar_con_meta(virt, otro, -1, None).unwrap();
+        assert!(en_cache((virt, otro, -1)));
+        pruebas::cargar(a, &[(Some(9), 1, 100)]);
+        assert!(!en_cache((virt, otro, -1)));
+        let ((despues, ..), _) = comparar_con_meta(virt, otro, -1, None).unwrap();
+        assert_eq!((antes[&9][1], despues[&9][1]), (21, 107));
+
+        // Redefinirlo también
+        comparar_con_meta(otro, virt, -1, None).unwrap();
+        assert!(en_cache((otro, virt, -1)));
+        crear(virt, vec![a]).unwrap();
+        assert!(!en_cache((otro, virt, -1)) && !en_cache((virt, otro, -1)));
+        let ((_, m1, _), _) = comparar_con_meta(virt, otro, -1, None).unwrap();
+        assert_eq!(m1.filas, 1);
+
+        // Los resultados de otras claves no se tocan
+        comparar_con_meta(b, otro, -1, None).unwrap();
+        invalidar_resultados(virt).unwrap();
+        assert!(en_cache((b, otro, -1)));
+    }
+}
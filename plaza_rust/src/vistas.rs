@@ -0,0 +1,101 @@
+// ==============================================================================
+// plaza_rust/src/vistas.rs
+//
+// Acceso de solo lectura a las columnas en cache como arreglos de NumPy sin
+// copiar: columna() devuelve un np.ndarray que mira directo el buffer del
+// periodo, así un análisis ad hoc no exporta ni duplica gigas.
+//
+//   est = columna(202401, "estado_id")     # int8/16/32/64 según compacto
+//   cn  = columna(202401, "cn_total")
+//   np.bincount(est[cn > 0])
+//
+// El dtype es el del almacenamiento (columna.rs): con [motor] compacto los
+// enteros salen en el ancho más chico y lat/lng en float32. Los nulos son
+// el MIN del dtype (NaN en lat/lng). Las filas van en el orden interno del
+// motor (agrupadas por estado); columna(k, "fila") da la fila original en
+// el parquet de cada una. En filas multivalor situacion trae la menor. El
+// arreglo no es escribible y su `base` sostiene el Arc del periodo: un
+// desalojo mientras se usa no lo invalida, pero la memoria no se libera
+// hasta soltar todas las vistas. Una métrica en diccionario ([motor]
+// diccionario = true) no tiene buffer propio y da error.
+// ==============================================================================
+
+use std::sync::Arc;
+
+use numpy::ndarray::ArrayView1;
+use numpy::{Element, PyArray1};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::alias::ArgClave;
+use crate::calidad::Campo;
+use crate::columna::{ColF, ColI};
+use crate::{clave, periodo, EngineData};
+
+/// Dueño de los buffers de las vistas (queda como `base` del ndarray).
+#[pyclass(module = "plaza_rust", frozen)]
+pub(crate) struct SostenPeriodo {
+    _eng: Arc<EngineData>,
+}
+
+/// Vista de solo lectura de `datos` con `dueño` como base.
+fn vista<'py, T: Element>(datos: &[T], dueño: &Bound<'py, SostenPeriodo>) -> PyResult<Bound<'py, PyAny>> {
+    let v = ArrayView1::from(datos);
+    // SAFETY: `datos` vive dentro del EngineData que sostiene `dueño`, y el
+    // periodo es inmutable mientras haya un Arc vivo
+    let arr = unsafe { PyArray1::borrow_from_array(&v, dueño.clone().into_any()) };
+    arr.getattr("flags")?.setattr("writeable", false)?;
+    Ok(arr.into_any())
+}
+
+fn vista_i<'py>(nombre: &str, col: &ColI, dueño: &Bound<'py, SostenPeriodo>) -> PyResult<Bound<'py, PyAny>> {
+    match col {
+        ColI::I8(v)  => vista(v, dueño),
+        ColI::I16(v) => vista(v, dueño),
+        ColI::I32(v) => vista(v, dueño),
+        ColI::I64(v) => vista(v, dueño),
+        ColI::Dic(_) => Err(PyValueError::new_err(format!(
+            "columna: {nombre:?} está en diccionario ([motor] diccionario) y no tiene buffer propio"
+        ))),
+    }
+}
+
+fn vista_f<'py>(col: &ColF, dueño: &Bound<'py, SostenPeriodo>) -> PyResult<Bound<'py, PyAny>> {
+    match col {
+        ColF::F32(v) => vista(v, dueño),
+        ColF::F64(v) => vista(v, dueño),
+    }
+}
+
+// ===========================================================================
+// FUNCIÓN EXPORTADA A PYTHON
+// ===========================================================================
+
+/// np.ndarray de solo lectura sobre la columna `nombre` del periodo, sin
+/// copiar: lat, lng, estado_id, situacion, las seis métricas o "fila".
+#[pyfunction]
+pub(crate) fn columna<'py>(py: Python<'py>, periodo_key: ArgClave, nombre: &str) -> PyResult<Bound<'py, PyAny>> {
+    let key = clave(periodo_key)?;
+    let eng = py.allow_threads(|| periodo(key))?;
+    eng.tocar();
+    // Sin agrupar no hay `orden`: la fila interna es la original
+    if nombre == "fila" && eng.orden.is_empty() {
+        return Ok(PyArray1::<u32>::arange(py, 0, eng.n as u32, 1).into_any());
+    }
+    let campo = match nombre {
+        "fila" => None,
+        _      => Some(Campo::parse(nombre).map_err(PyValueError::new_err)?),
+    };
+    let dueño = Bound::new(py, SostenPeriodo { _eng: Arc::clone(&eng) })?;
+    match campo {
+        None                    => vista(&eng.orden, &dueño),
+        Some(Campo::Lat)        => vista_f(&eng.lats, &dueño),
+        Some(Campo::Lng)        => vista_f(&eng.lngs, &dueño),
+        Some(Campo::EstadoId)   => vista_i(nombre, &eng.estado_ids, &dueño),
+        Some(Campo::Situacion)  => vista_i(nombre, &eng.situaciones, &dueño),
+        Some(Campo::Metrica(m)) => vista_i(nombre, eng.metricas()[m], &dueño),
+        Some(Campo::PlazaId)    => Err(PyValueError::new_err(
+            "columna: plaza_id es texto; usar iterar_filas(..., columnas=[\"plaza_id\"])"
+        )),
+    }
+}
@@ -0,0 +1,54 @@
+# ==============================================================================
+# tests/test_rust_bridge.py
+#
+# decodificar_comparacion() contra un blob PLZC armado por el motor Rust
+# (plaza_rust/datos_prueba/comparacion_v1.plzc, lo genera y verifica el test
+# empaquetado::tests::layout_de_referencia). Corre sin plaza_rust instalado:
+#
+#   python -m unittest discover tests
+# ==============================================================================
+import os
+import sys
+import unittest
+
+_RAIZ = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+sys.path.insert(0, os.path.join(_RAIZ, "app"))
+
+import rust_bridge  # noqa: E402
+
+_BLOB = os.path.join(_RAIZ, "plaza_rust", "datos_prueba", "comparacion_v1.plzc")
+_CLAVES = ["filas", "inc_total", "aten_total", "cn_total", "cn_inicial", "cn_prim", "cn_sec"]
+
+
+def _fila(base: int) -> dict:
+    # fila(base) del test de Rust: la métrica k vale base * (k + 1)
+    return {c: base * (k + 1) for k, c in enumerate(_CLAVES)}
+
+
+class DecodificarComparacion(unittest.TestCase):
+    def setUp(self):
+        with open(_BLOB, "rb") as f:
+            self.blob = f.read()
+
+    def test_blob_de_rust(self):
+        r = rust_bridge.decodificar_comparacion(self.blob, _CLAVES)
+        self.assertEqual(r["periodo1"], {-1: _fila(-3), 9: _fila(10), 15: _fila(0)})
+        self.assertEqual(r["periodo2"], {9: _fila(-20), 32: {c: -2**63 for c in _CLAVES}})
+        # Estados en orden ascendente, como los escribe Rust
+        self.assertEqual(list(r["periodo1"]), [-1, 9, 15])
+
+    def test_mismo_periodo(self):
+        # flags bit 0: periodo2 es periodo1 y no hay segundo bloque
+        n1 = int.from_bytes(self.blob[8:12], "little")
+        solo1 = self.blob[:5] + b"\x01" + self.blob[6:12] + b"\x00" * 4 + self.blob[16:16 + n1 * 8 * 8]
+        r = rust_bridge.decodificar_comparacion(solo1, _CLAVES)
+        self.assertIs(r["periodo1"], r["periodo2"])
+        self.assertEqual(r["periodo1"][9], _fila(10))
+
+    def test_version_desconocida(self):
+        with self.assertRaises(ValueError):
+            rust_bridge.decodificar_comparacion(self.blob[:4] + b"\x02" + self.blob[5:], _CLAVES)
+
+
+if __name__ == "__main__":
+    unittest.main()